@@ -1,65 +1,191 @@
+use crate::error::DbError;
 use crate::helper_function::murmur3_32::murmur_hash3_32;
-use serde::Serialize;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
+/// The layout of a serialized `BloomFilter`. Bump it whenever the fields or
+/// the hashing change, so that filters saved by another version are
+/// rejected instead of answering wrongly.
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// The murmur3 seeds of the two hashes combined by double hashing.
+pub(crate) const DEFAULT_SEEDS: (u32, u32) = (0, 0x9747_b28c);
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A probabilistic set: `check` may report an item that was never added,
+/// but never misses one that was.
+pub(crate) trait MembershipFilter<T> {
+    fn add(&mut self, item: &T);
+    fn check(&self, item: &T) -> bool;
+    /// Forgets an item that was added before. Returns false if the filter
+    /// cannot delete, in which case the item is still reported.
+    #[allow(dead_code)]
+    fn remove(&mut self, item: &T) -> bool;
+    /// Serializes the filter with a format version, to be read back by the
+    /// concrete type's `from_bytes`.
+    #[allow(dead_code)]
+    fn to_bytes(&self) -> Result<Vec<u8>, DbError>;
+}
+
+/// A Bloom filter over `size` bits, packed 64 to a word. It serializes with
+/// its parameters, hash seeds and a format version, so a saved filter can
+/// be loaded with its table and checked before use.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BloomFilterData", bound = "")]
 pub(crate) struct BloomFilter<T> {
-    bit_vector: Vec<bool>,
+    version: u32,
     size: usize,
     num_of_functions: usize,
+    seeds: (u32, u32),
+    bits: Vec<u64>,
+    #[serde(skip)]
     phantom: PhantomData<T>,
 }
 
-impl<T: Serialize + Hash> BloomFilter<T> {
+impl<T: Serialize> BloomFilter<T> {
     pub(crate) fn new(n: usize, error_percent: f32) -> Self {
-        let ln2 = 2.0_f32.ln();
-        let size = (-(n as f32 * (error_percent.ln())) / (ln2.powf(2.0))).ceil() as usize;
-        let num_of_functions = ((size / n) as f32 * (ln2)).ceil() as usize;
-
+        let (size, num_of_functions) = dimensions(n, error_percent);
         BloomFilter {
-            bit_vector: vec![false; size],
+            version: FORMAT_VERSION,
             size,
             num_of_functions,
+            seeds: DEFAULT_SEEDS,
+            bits: vec![0; size.div_ceil(WORD_BITS)],
             phantom: PhantomData,
         }
     }
-    fn hash(&self, item: &T) -> Vec<usize> {
-        let mut bit_position = Vec::new();
-        let bit_stream = bincode::serialize(item).unwrap();
-        for i in 0..self.num_of_functions {
-            let hash1 = murmur_hash3_32(&bit_stream, 0) as usize;
-            let hash2 = Self::fnv1a_32(item) as usize;
-            let result = (hash1.wrapping_add(i * hash2)) % self.size;
+    /// Hashes with other seeds. Items added before are lost to the new
+    /// hashing, so call this on an empty filter.
+    #[must_use]
+    #[allow(dead_code)]
+    pub(crate) fn with_seeds(mut self, seeds: (u32, u32)) -> Self {
+        self.seeds = seeds;
+        self.bits.fill(0);
+        self
+    }
+    #[allow(dead_code)]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, DbError> {
+        bincode::deserialize(bytes).map_err(DbError::from)
+    }
+    fn get_bit(&self, pos: usize) -> bool {
+        self.bits[pos / WORD_BITS] & (1 << (pos % WORD_BITS)) != 0
+    }
+    fn set_bit(&mut self, pos: usize) {
+        self.bits[pos / WORD_BITS] |= 1 << (pos % WORD_BITS);
+    }
+}
 
-            bit_position.push(result);
+impl<T: Serialize> MembershipFilter<T> for BloomFilter<T> {
+    fn add(&mut self, item: &T) {
+        for pos in positions(item, self.num_of_functions, self.size, self.seeds) {
+            self.set_bit(pos);
         }
-        bit_position
     }
-    pub(crate) fn add(&mut self, item: &T) {
-        let positions = self.hash(item);
-        for pos in positions {
-            self.bit_vector[pos] = true;
-        }
+    fn check(&self, item: &T) -> bool {
+        positions(item, self.num_of_functions, self.size, self.seeds)
+            .iter()
+            .all(|&pos| self.get_bit(pos))
+    }
+    /// A bit may be shared with other items, so none can be cleared.
+    fn remove(&mut self, _item: &T) -> bool {
+        false
     }
-    pub(crate) fn check(&self, item: &T) -> bool {
-        let positions = self.hash(item);
-        positions.iter().all(|&pos| self.bit_vector[pos])
+    fn to_bytes(&self) -> Result<Vec<u8>, DbError> {
+        bincode::serialize(self).map_err(DbError::from)
     }
+}
+
+/// The fields of a serialized `BloomFilter`, checked before they become one.
+#[derive(Deserialize)]
+struct BloomFilterData {
+    version: u32,
+    size: usize,
+    num_of_functions: usize,
+    seeds: (u32, u32),
+    bits: Vec<u64>,
+}
+
+impl<T> TryFrom<BloomFilterData> for BloomFilter<T> {
+    type Error = String;
 
-    fn fnv1a_32(key: &T) -> u32 {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        hasher.finish() as u32
+    fn try_from(data: BloomFilterData) -> Result<Self, Self::Error> {
+        if data.version != FORMAT_VERSION {
+            return Err(format!(
+                "Bloom filter format version {} is not supported, expected {FORMAT_VERSION}",
+                data.version
+            ));
+        }
+        if data.size == 0 || data.bits.len() != data.size.div_ceil(WORD_BITS) {
+            return Err(format!(
+                "Bloom filter of {} bits cannot be stored in {} words",
+                data.size,
+                data.bits.len()
+            ));
+        }
+        if data.num_of_functions == 0 || data.num_of_functions > data.size {
+            return Err(format!(
+                "Bloom filter of {} bits cannot use {} hash functions",
+                data.size, data.num_of_functions
+            ));
+        }
+        Ok(BloomFilter {
+            version: data.version,
+            size: data.size,
+            num_of_functions: data.num_of_functions,
+            seeds: data.seeds,
+            bits: data.bits,
+            phantom: PhantomData,
+        })
     }
 }
 
+/// The number of slots and of hash functions that keep the false-positive
+/// rate of a filter holding `n` items at `error_percent`.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn dimensions(n: usize, error_percent: f32) -> (usize, usize) {
+    let ln2 = 2.0_f32.ln();
+    let size = ((-(n as f32 * (error_percent.ln())) / (ln2.powf(2.0))) as usize).max(1);
+    let num_of_functions = (((size / n) as f32 * (ln2)) as usize).max(1);
+    (size, num_of_functions)
+}
+
+/// The slots an item maps to, by double hashing with two seeded murmur3
+/// hashes of its bincode encoding. Both are stable across platforms and
+/// Rust versions, which persisted filters rely on, and the arithmetic is
+/// done in `u64` so that 32-bit builds agree with 64-bit ones.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn positions<T: Serialize>(
+    item: &T,
+    num_of_functions: usize,
+    size: usize,
+    seeds: (u32, u32),
+) -> Vec<usize> {
+    let bit_stream = bincode::serialize(item).unwrap();
+    let hash1 = u64::from(murmur_hash3_32(&bit_stream, seeds.0));
+    let hash2 = u64::from(murmur_hash3_32(&bit_stream, seeds.1));
+    let size = size as u64;
+    // Each position is below `size`, so it fits back into a usize.
+    (0..num_of_functions as u64)
+        .map(|i| (hash1.wrapping_add(i.wrapping_mul(hash2)) % size) as usize)
+        .collect()
+}
+
 #[cfg(test)]
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
 mod tests {
     use super::*;
     use serde::Serialize;
 
-    #[derive(Hash, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Serialize)]
     struct TestItem {
         id: u32,
         value: String,
@@ -84,7 +210,8 @@ mod tests {
 
         assert_eq!(filter.size, expected_size);
         assert_eq!(filter.num_of_functions, expected_num_of_functions);
-        assert_eq!(filter.bit_vector.len(), expected_size);
+        assert_eq!(filter.bits.len(), expected_size.div_ceil(64));
+        assert_eq!(filter.version, FORMAT_VERSION);
     }
 
     #[test]
@@ -100,7 +227,6 @@ mod tests {
     #[test]
     fn check_nonexistent_element() {
         let filter = BloomFilter::<TestItem>::new(100, 0.01_f32);
-        let item = create_item(1, "test");
         let non_existent_item = create_item(2, "nonexistent");
 
         // Not added the item, should ideally be false, acknowledging potential false positives
@@ -109,28 +235,84 @@ mod tests {
 
     #[test]
     fn false_positive_rate() {
-        let mut filter = BloomFilter::<TestItem>::new(100, 0.01_f32);
+        let mut filter = BloomFilter::<TestItem>::new(500, 0.01_f32);
         let mut false_positives = 0;
-        let mut trials = 10000;
+        let trials = 10000;
 
         for id in 0..500 {
             // Add 500 items
-            let item = create_item(id, &format!("item{}", id));
+            let item = create_item(id, &format!("item{id}"));
             filter.add(&item);
         }
 
         for id in 500..(500 + trials) {
             // Check 10000 different items
-            let non_existent_item = create_item(id, &format!("item{}", id));
+            let non_existent_item = create_item(id, &format!("item{id}"));
             if filter.check(&non_existent_item) {
                 false_positives += 1;
             }
         }
 
         let false_positive_rate = false_positives as f32 / trials as f32;
-        println!("False positive rate: {}", false_positive_rate);
+        println!("False positive rate: {false_positive_rate}");
 
         // The actual rate might slightly vary, but should be close to the desired rate
         assert!(false_positive_rate <= 0.01 + 0.005); // Allowing some margin
     }
+
+    #[test]
+    fn positions_are_stable() {
+        // Saved filters depend on these exact slots on every platform.
+        assert_eq!(
+            positions(&create_item(1, "test"), 7, 1000, DEFAULT_SEEDS),
+            vec![363, 998, 633, 268, 903, 538, 173]
+        );
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let mut filter = BloomFilter::<TestItem>::new(100, 0.01_f32).with_seeds((7, 11));
+        for id in 0..50 {
+            filter.add(&create_item(id, "saved"));
+        }
+        let bytes = filter.to_bytes().unwrap();
+        let loaded = BloomFilter::<TestItem>::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded, filter);
+        assert_eq!(loaded.seeds, (7, 11));
+        assert!((0..50).all(|id| loaded.check(&create_item(id, "saved"))));
+        assert!(matches!(
+            BloomFilter::<TestItem>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DbError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn loading_rejects_other_versions_and_shapes() {
+        let filter = BloomFilter::<TestItem>::new(100, 0.01_f32);
+        let mut other_version = filter.clone();
+        other_version.version = FORMAT_VERSION + 1;
+        assert_eq!(
+            BloomFilter::<TestItem>::from_bytes(&other_version.to_bytes().unwrap()),
+            Err(DbError::Parse(format!(
+                "Bloom filter format version {} is not supported, expected {FORMAT_VERSION}",
+                FORMAT_VERSION + 1
+            )))
+        );
+        let mut truncated = filter.clone();
+        truncated.bits.pop();
+        assert!(matches!(
+            BloomFilter::<TestItem>::from_bytes(&truncated.to_bytes().unwrap()),
+            Err(DbError::Parse(_))
+        ));
+        let mut too_many_functions = filter;
+        too_many_functions.num_of_functions = too_many_functions.size + 1;
+        assert_eq!(
+            BloomFilter::<TestItem>::from_bytes(&too_many_functions.to_bytes().unwrap()),
+            Err(DbError::Parse(format!(
+                "Bloom filter of {} bits cannot use {} hash functions",
+                too_many_functions.size,
+                too_many_functions.size + 1
+            )))
+        );
+    }
 }
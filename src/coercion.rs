@@ -0,0 +1,549 @@
+use crate::date_time::{Date, Timestamp};
+use crate::db_type::DbType;
+use crate::decimal::Decimal;
+use crate::schema_struct::{ColumnType, MAX_DECIMAL_PRECISION};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+/// Compares two values with SQL semantics, returning `None` when either side
+/// is NULL. Values of different types are first brought to a common type:
+///
+/// | left                 | right                | compared as                |
+/// |----------------------|----------------------|----------------------------|
+/// | INT, BIGINT, DECIMAL | INT, BIGINT, DECIMAL | DECIMAL, exactly           |
+/// | FLOAT                | any number           | FLOAT                      |
+/// | DATE                 | TIMESTAMP            | TIMESTAMP at midnight      |
+/// | TEXT                 | DATE, TIMESTAMP      | the text parsed as a time  |
+///
+/// Every other pair of different types is a type error.
+pub(crate) fn compare(left: &DbType, right: &DbType) -> Result<Option<Ordering>, TypeError> {
+    if left.is_null() || right.is_null() {
+        return Ok(None);
+    }
+    if left.has_type_of(right) {
+        return Ok(Some(left.cmp(right)));
+    }
+    let ordering = match (left, right) {
+        (DbType::Float(left), right) if right.is_numeric() => left.total_cmp(&as_f64(right)),
+        (left, DbType::Float(right)) if left.is_numeric() => as_f64(left).total_cmp(right),
+        (left, right) if left.is_numeric() && right.is_numeric() => {
+            as_decimal(left).cmp(&as_decimal(right))
+        }
+        (
+            DbType::Date(_) | DbType::Timestamp(_) | DbType::Text(_),
+            DbType::Date(_) | DbType::Timestamp(_) | DbType::Text(_),
+        ) => as_timestamp(left, right)?.cmp(&as_timestamp(right, left)?),
+        _ => {
+            return Err(TypeError::new(&format!(
+                "cannot compare {} with {}",
+                left.type_name(),
+                right.type_name()
+            )))
+        }
+    };
+    Ok(Some(ordering))
+}
+
+/// Checks at planning time that values of two column types can be compared.
+pub(crate) fn check_comparable(left: &ColumnType, right: &ColumnType) -> Result<(), TypeError> {
+    let comparable = match (left, right) {
+        (left, right) if left.is_numeric() && right.is_numeric() => true,
+        (ColumnType::Date | ColumnType::Timestamp, ColumnType::Date | ColumnType::Timestamp)
+        | (ColumnType::Text { .. }, ColumnType::Text { .. })
+        | (ColumnType::Bool, ColumnType::Bool)
+        | (ColumnType::Blob, ColumnType::Blob) => true,
+        _ => false,
+    };
+    if comparable {
+        Ok(())
+    } else {
+        Err(TypeError::new(&format!(
+            "cannot compare {left} with {right}"
+        )))
+    }
+}
+
+/// The type that values of the given types are converted to when they meet
+/// in one column, as the branches of a CASE do. Numbers widen the way
+/// arithmetic results do, DATE meets TIMESTAMP as TIMESTAMP and strings of
+/// different lengths meet as unbounded TEXT. Unknown types are skipped, and
+/// types with nothing in common are an error.
+pub(crate) fn common_type(types: &[Option<ColumnType>]) -> Result<Option<ColumnType>, TypeError> {
+    let mut known = types.iter().flatten();
+    let Some(first) = known.next() else {
+        return Ok(None);
+    };
+    known
+        .try_fold(first.clone(), |common, other| unify(&common, other))
+        .map(Some)
+}
+
+fn unify(left: &ColumnType, right: &ColumnType) -> Result<ColumnType, TypeError> {
+    let common = match (left, right) {
+        (left, right) if left == right => Some(left.clone()),
+        (ColumnType::Int | ColumnType::BigInt, ColumnType::Int | ColumnType::BigInt) => {
+            Some(ColumnType::BigInt)
+        }
+        (ColumnType::Float, other) | (other, ColumnType::Float) if other.is_numeric() => {
+            Some(ColumnType::Float)
+        }
+        (left, right) if left.is_numeric() && right.is_numeric() => Some(ColumnType::Decimal {
+            precision: MAX_DECIMAL_PRECISION,
+            scale: decimal_scale(left).max(decimal_scale(right)),
+        }),
+        (ColumnType::Date | ColumnType::Timestamp, ColumnType::Date | ColumnType::Timestamp) => {
+            Some(ColumnType::Timestamp)
+        }
+        (ColumnType::Text { .. }, ColumnType::Text { .. }) => {
+            Some(ColumnType::Text { max_length: None })
+        }
+        _ => None,
+    };
+    common.ok_or_else(|| TypeError::new(&format!("{left} and {right} have no common type")))
+}
+
+/// The scale of a DECIMAL type, and 0 for the integer types.
+pub(crate) fn decimal_scale(column_type: &ColumnType) -> u32 {
+    match column_type {
+        ColumnType::Decimal { scale, .. } => *scale,
+        _ => 0,
+    }
+}
+
+/// Prepares a literal that is compared with a column of the given type. A
+/// string compared with a DATE or TIMESTAMP column is parsed once here rather
+/// than for every row; any other literal must already be comparable.
+pub(crate) fn coerce_for_comparison(
+    value: &DbType,
+    column_type: &ColumnType,
+) -> Result<DbType, TypeError> {
+    match (value, column_type) {
+        (DbType::Null, _) => Ok(DbType::Null),
+        (DbType::Text(_), ColumnType::Date | ColumnType::Timestamp) => cast(value, column_type),
+        (value, column_type) => {
+            let value_type = ColumnType::of(value).expect("non-null values have a type");
+            check_comparable(column_type, &value_type)?;
+            Ok(value.clone())
+        }
+    }
+}
+
+/// Converts a value into the type of the column it is about to be stored in.
+/// Only conversions that keep the value's meaning happen implicitly: integers
+/// widen to BIGINT, FLOAT or DECIMAL, any number widens to DECIMAL (rounded
+/// to the column's scale) and strings become dates and timestamps. Other
+/// values are returned unchanged for the column's type check to reject.
+pub(crate) fn coerce_for_column(
+    value: DbType,
+    column_type: &ColumnType,
+) -> Result<DbType, TypeError> {
+    let implicit = match (&value, column_type) {
+        (DbType::Int(_), ColumnType::BigInt | ColumnType::Float)
+        | (DbType::BigInt(_), ColumnType::Float)
+        | (DbType::Text(_), ColumnType::Date | ColumnType::Timestamp) => true,
+        (value, ColumnType::Decimal { .. }) => value.is_numeric(),
+        _ => false,
+    };
+    if implicit {
+        cast(&value, column_type)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Explicit `CAST(value AS type)`. Which conversions exist:
+///
+/// | to                 | from                                   |
+/// |--------------------|----------------------------------------|
+/// | INT, BIGINT        | numbers (rounded), BOOL, TEXT          |
+/// | FLOAT, DECIMAL     | numbers, TEXT                          |
+/// | BOOL               | BOOL, INT, BIGINT, TEXT                |
+/// | TEXT, VARCHAR(n)   | anything (truncated to `n` characters) |
+/// | DATE, TIMESTAMP    | DATE, TIMESTAMP, TEXT                  |
+/// | BLOB               | BLOB, TEXT                             |
+///
+/// NULL casts to NULL. A value that does not fit the target, such as
+/// `'abc'` as INT, is an error.
+pub(crate) fn cast(value: &DbType, target: &ColumnType) -> Result<DbType, TypeError> {
+    let Some(value_type) = ColumnType::of(value) else {
+        return Ok(DbType::Null);
+    };
+    check_castable(&value_type, target)?;
+    convert(value, target)
+        .filter(|converted| target.check(converted).is_ok())
+        .ok_or_else(|| {
+            TypeError::new(&format!(
+                "cannot cast {} value '{value}' to {target}",
+                value.type_name()
+            ))
+        })
+}
+
+/// Checks at planning time that `CAST` from one column type to another exists.
+pub(crate) fn check_castable(from: &ColumnType, to: &ColumnType) -> Result<(), TypeError> {
+    if can_cast(from, to) {
+        Ok(())
+    } else {
+        Err(TypeError::new(&format!("cannot cast {from} to {to}")))
+    }
+}
+
+/// Brings a join key to a canonical form so that equal values of different
+/// types, such as `1`, `1.0` and `DECIMAL '1.00'`, hash and compare equal.
+/// Decimals lose their trailing zeros, so equal keys also serialize to the
+/// same bytes.
+pub(crate) fn normalize_key(value: &DbType) -> DbType {
+    match value {
+        DbType::Int(_) | DbType::BigInt(_) | DbType::Decimal(_) => {
+            DbType::Decimal(as_decimal(value).normalized())
+        }
+        DbType::Float(float) => float_to_decimal(*float).map_or(value.clone(), |decimal| {
+            DbType::Decimal(decimal.normalized())
+        }),
+        DbType::Date(date) => midnight(*date).map_or(value.clone(), DbType::Timestamp),
+        value => value.clone(),
+    }
+}
+
+fn can_cast(from: &ColumnType, to: &ColumnType) -> bool {
+    match to {
+        ColumnType::Int | ColumnType::BigInt => {
+            from.is_numeric() || matches!(from, ColumnType::Bool | ColumnType::Text { .. })
+        }
+        ColumnType::Float | ColumnType::Decimal { .. } => {
+            from.is_numeric() || matches!(from, ColumnType::Text { .. })
+        }
+        ColumnType::Bool => matches!(
+            from,
+            ColumnType::Bool | ColumnType::Int | ColumnType::BigInt | ColumnType::Text { .. }
+        ),
+        ColumnType::Text { .. } => true,
+        ColumnType::Date | ColumnType::Timestamp => matches!(
+            from,
+            ColumnType::Date | ColumnType::Timestamp | ColumnType::Text { .. }
+        ),
+        ColumnType::Blob => matches!(from, ColumnType::Blob | ColumnType::Text { .. }),
+    }
+}
+
+fn convert(value: &DbType, target: &ColumnType) -> Option<DbType> {
+    match target {
+        ColumnType::Int => i32::try_from(to_integer(value)?).ok().map(DbType::Int),
+        ColumnType::BigInt => to_integer(value).map(DbType::BigInt),
+        ColumnType::Float => match value {
+            DbType::Text(text) => text.trim().parse().ok(),
+            value => Some(as_f64(value)),
+        }
+        .map(DbType::Float),
+        ColumnType::Decimal { scale, .. } => match value {
+            DbType::Text(text) => Decimal::parse(text),
+            DbType::Float(float) => float_to_decimal(*float),
+            value => Some(as_decimal(value)),
+        }?
+        .rescale(*scale)
+        .map(DbType::Decimal),
+        ColumnType::Bool => match value {
+            DbType::Bool(value) => Some(*value),
+            DbType::Int(value) => Some(*value != 0),
+            DbType::BigInt(value) => Some(*value != 0),
+            DbType::Text(text) => match text.trim().to_ascii_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "1" => Some(true),
+                "false" | "f" | "no" | "n" | "0" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+        .map(DbType::Bool),
+        ColumnType::Text { max_length } => {
+            let text = match value {
+                DbType::Blob(bytes) => String::from_utf8(bytes.clone()).ok()?,
+                value => value.to_string(),
+            };
+            Some(DbType::Text(match max_length {
+                Some(length) => text.chars().take(*length).collect(),
+                None => text,
+            }))
+        }
+        ColumnType::Date => match value {
+            DbType::Date(date) => Some(*date),
+            DbType::Timestamp(timestamp) => Some(timestamp.date()),
+            DbType::Text(text) => {
+                Date::parse(text).or_else(|| Timestamp::parse(text).map(Timestamp::date))
+            }
+            _ => None,
+        }
+        .map(DbType::Date),
+        ColumnType::Timestamp => match value {
+            DbType::Date(date) => midnight(*date),
+            DbType::Timestamp(timestamp) => Some(*timestamp),
+            DbType::Text(text) => Timestamp::parse(text),
+            _ => None,
+        }
+        .map(DbType::Timestamp),
+        ColumnType::Blob => match value {
+            DbType::Blob(bytes) => Some(bytes.clone()),
+            DbType::Text(text) => Some(text.as_bytes().to_vec()),
+            _ => None,
+        }
+        .map(DbType::Blob),
+    }
+}
+
+/// Converts to a whole number, rounding fractions half away from zero.
+fn to_integer(value: &DbType) -> Option<i64> {
+    match value {
+        DbType::Bool(value) => Some(i64::from(*value)),
+        DbType::Int(value) => Some(i64::from(*value)),
+        DbType::BigInt(value) => Some(*value),
+        DbType::Float(float) => decimal_to_integer(float_to_decimal(*float)?),
+        DbType::Decimal(decimal) => decimal_to_integer(*decimal),
+        DbType::Text(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn decimal_to_integer(decimal: Decimal) -> Option<i64> {
+    i64::try_from(decimal.rescale(0)?.get_units()).ok()
+}
+
+fn float_to_decimal(value: f64) -> Option<Decimal> {
+    if value.is_finite() {
+        Decimal::parse(&value.to_string())
+    } else {
+        None
+    }
+}
+
+fn midnight(date: Date) -> Option<Timestamp> {
+    Timestamp::from_date_time(date, 0, 0, 0, 0)
+}
+
+/// Widens an INT, BIGINT or DECIMAL to a `Decimal`.
+pub(crate) fn as_decimal(value: &DbType) -> Decimal {
+    match value {
+        DbType::Int(value) => Decimal::from(i64::from(*value)),
+        DbType::BigInt(value) => Decimal::from(*value),
+        DbType::Decimal(value) => *value,
+        other => unreachable!("{} is not an exact number", other.type_name()),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn as_f64(value: &DbType) -> f64 {
+    match value {
+        DbType::Int(value) => f64::from(*value),
+        DbType::BigInt(value) => *value as f64,
+        DbType::Float(value) => *value,
+        DbType::Decimal(value) => value.to_f64(),
+        other => unreachable!("{} is not a number", other.type_name()),
+    }
+}
+
+/// Reads a DATE, TIMESTAMP or a string compared with one of those as a
+/// timestamp.
+fn as_timestamp(value: &DbType, other: &DbType) -> Result<Timestamp, TypeError> {
+    let timestamp = match value {
+        DbType::Date(date) => midnight(*date),
+        DbType::Timestamp(timestamp) => Some(*timestamp),
+        DbType::Text(text) => Timestamp::parse(text),
+        _ => None,
+    };
+    timestamp.ok_or_else(|| {
+        TypeError::new(&format!(
+            "cannot compare {} with {} '{value}'",
+            other.type_name(),
+            value.type_name()
+        ))
+    })
+}
+
+#[derive(Debug)]
+pub(crate) struct TypeError {
+    pub(crate) message: String,
+}
+
+impl TypeError {
+    pub(crate) fn new(message: &str) -> Self {
+        TypeError {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Error for TypeError {}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Type Error: {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> DbType {
+        DbType::Text(value.to_string())
+    }
+
+    fn decimal(value: &str) -> DbType {
+        DbType::Decimal(Decimal::parse(value).unwrap())
+    }
+
+    #[test]
+    fn test_compare_numbers_across_types() {
+        assert_eq!(
+            compare(&DbType::Int(5), &DbType::BigInt(5)).unwrap(),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            compare(&DbType::Int(2), &decimal("1.99")).unwrap(),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare(&DbType::Float(0.5), &DbType::Int(1)).unwrap(),
+            Some(Ordering::Less)
+        );
+        assert_eq!(compare(&DbType::Int(1), &DbType::Null).unwrap(), None);
+    }
+
+    #[test]
+    fn test_compare_incompatible_types_is_an_error() {
+        let error = compare(&DbType::Int(5), &text("5")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: cannot compare INT with TEXT"
+        );
+        assert!(compare(&DbType::Bool(true), &DbType::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_compare_dates_with_timestamps_and_strings() {
+        let date = DbType::Date(Date::parse("2024-03-01").unwrap());
+        let timestamp = DbType::Timestamp(Timestamp::parse("2024-03-01 08:00:00").unwrap());
+        assert_eq!(compare(&date, &timestamp).unwrap(), Some(Ordering::Less));
+        assert_eq!(
+            compare(&date, &text("2024-03-01")).unwrap(),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            compare(&date, &text("soon")).unwrap_err().to_string(),
+            "Type Error: cannot compare DATE with TEXT 'soon'"
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(
+            cast(&text(" 42 "), &ColumnType::Int).unwrap(),
+            DbType::Int(42)
+        );
+        assert_eq!(
+            cast(&DbType::Float(2.5), &ColumnType::Int).unwrap(),
+            DbType::Int(3)
+        );
+        assert_eq!(
+            cast(&decimal("-2.5"), &ColumnType::BigInt).unwrap(),
+            DbType::BigInt(-3)
+        );
+        assert_eq!(
+            cast(&DbType::Int(7), &ColumnType::Text { max_length: None }).unwrap(),
+            text("7")
+        );
+        assert_eq!(
+            cast(
+                &text("abcdef"),
+                &ColumnType::Text {
+                    max_length: Some(3)
+                }
+            )
+            .unwrap(),
+            text("abc")
+        );
+        assert_eq!(
+            cast(
+                &text("3.14159"),
+                &ColumnType::Decimal {
+                    precision: 5,
+                    scale: 2
+                }
+            )
+            .unwrap(),
+            decimal("3.14")
+        );
+        assert_eq!(
+            cast(&text("2024-03-01 10:00:00"), &ColumnType::Date).unwrap(),
+            DbType::Date(Date::parse("2024-03-01").unwrap())
+        );
+        assert_eq!(
+            cast(&text("yes"), &ColumnType::Bool).unwrap(),
+            DbType::Bool(true)
+        );
+        assert_eq!(cast(&DbType::Null, &ColumnType::Int).unwrap(), DbType::Null);
+    }
+
+    #[test]
+    fn test_failed_casts() {
+        assert_eq!(
+            cast(&text("abc"), &ColumnType::Int)
+                .unwrap_err()
+                .to_string(),
+            "Type Error: cannot cast TEXT value 'abc' to INT"
+        );
+        assert!(cast(&DbType::BigInt(i64::MAX), &ColumnType::Int).is_err());
+        assert!(cast(
+            &DbType::Int(1000),
+            &ColumnType::Decimal {
+                precision: 4,
+                scale: 2
+            }
+        )
+        .is_err());
+        assert_eq!(
+            cast(&DbType::Blob(vec![1]), &ColumnType::Date)
+                .unwrap_err()
+                .to_string(),
+            "Type Error: cannot cast BLOB to DATE"
+        );
+    }
+
+    #[test]
+    fn test_coerce_for_column_only_widens() {
+        assert_eq!(
+            coerce_for_column(DbType::Int(1), &ColumnType::BigInt).unwrap(),
+            DbType::BigInt(1)
+        );
+        assert_eq!(
+            coerce_for_column(text("1"), &ColumnType::Int).unwrap(),
+            text("1")
+        );
+        assert_eq!(
+            coerce_for_column(
+                text("toolong"),
+                &ColumnType::Text {
+                    max_length: Some(3)
+                }
+            )
+            .unwrap(),
+            text("toolong")
+        );
+    }
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(
+            normalize_key(&DbType::Int(1)),
+            normalize_key(&DbType::Float(1.0))
+        );
+        assert_eq!(
+            normalize_key(&DbType::BigInt(3)),
+            normalize_key(&decimal("3.00"))
+        );
+        assert_eq!(normalize_key(&text("a")), text("a"));
+        let DbType::Decimal(key) = normalize_key(&decimal("4.50")) else {
+            panic!("a decimal key stays a decimal");
+        };
+        assert_eq!((key.get_units(), key.get_scale()), (45, 1));
+    }
+}
@@ -1,17 +1,279 @@
+use crate::coercion::{check_comparable, coerce_for_comparison, TypeError};
 use crate::db_type::DbType;
+use crate::expression::{Expr, Literal};
+use crate::like_pattern::LikePattern;
+use crate::rational_algebra::aggregation::AggregateExpr;
+use crate::schema_struct::ColumnType;
+use std::collections::HashMap;
+use std::fmt;
 
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) enum Condition {
     Simple {
         field: String,
         operator: Operator,
         value: DbType,
     },
+    Comparison {
+        left_field: String,
+        operator: Operator,
+        right_field: String,
+    },
+    Aggregate {
+        aggregate: AggregateExpr,
+        operator: Operator,
+        value: DbType,
+    },
+    /// Compares two computed expressions, such as `a + b > c`.
+    Expression {
+        left: Expr,
+        operator: Operator,
+        right: Expr,
+    },
+    /// `operand IN (list)`.
+    In {
+        operand: Expr,
+        list: Vec<Expr>,
+    },
+    /// `operand BETWEEN low AND high`, with both bounds included.
+    Between {
+        operand: Expr,
+        low: Expr,
+        high: Expr,
+    },
+    /// `operand LIKE pattern` or `operand ILIKE pattern`.
+    Like {
+        operand: Expr,
+        pattern: LikePattern,
+    },
+    IsNull {
+        field: String,
+    },
+    IsNotNull {
+        field: String,
+    },
+    Not(Box<Condition>),
     And(Box<Condition>, Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
 }
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum Operator {
     Equals,
     LessThan,
     GreaterThan,
     NotEquals,
+    LessThanOrEquals,
+    GreaterThanOrEquals,
+}
+
+impl Condition {
+    pub(crate) fn get_fields(&self) -> Vec<String> {
+        match self {
+            Condition::Simple { field, .. }
+            | Condition::IsNull { field }
+            | Condition::IsNotNull { field } => vec![field.clone()],
+            Condition::Comparison {
+                left_field,
+                right_field,
+                ..
+            } => vec![left_field.clone(), right_field.clone()],
+            Condition::Aggregate { aggregate, .. } => vec![aggregate.get_canonical_name()],
+            Condition::Expression { .. }
+            | Condition::In { .. }
+            | Condition::Between { .. }
+            | Condition::Like { .. } => self
+                .get_expressions()
+                .into_iter()
+                .flat_map(Expr::get_columns)
+                .collect(),
+            Condition::Not(condition) => condition.get_fields(),
+            Condition::And(lhs, rhs) | Condition::Or(lhs, rhs) => {
+                let mut fields = lhs.get_fields();
+                fields.extend(rhs.get_fields());
+                fields
+            }
+        }
+    }
+
+    pub(crate) fn get_aggregates(&self) -> Vec<&AggregateExpr> {
+        match self {
+            Condition::Aggregate { aggregate, .. } => vec![aggregate],
+            Condition::Expression { .. }
+            | Condition::In { .. }
+            | Condition::Between { .. }
+            | Condition::Like { .. } => self
+                .get_expressions()
+                .into_iter()
+                .flat_map(Expr::get_aggregates)
+                .collect(),
+            Condition::Not(condition) => condition.get_aggregates(),
+            Condition::And(lhs, rhs) | Condition::Or(lhs, rhs) => {
+                let mut aggregates = lhs.get_aggregates();
+                aggregates.extend(rhs.get_aggregates());
+                aggregates
+            }
+            Condition::Simple { .. }
+            | Condition::Comparison { .. }
+            | Condition::IsNull { .. }
+            | Condition::IsNotNull { .. } => Vec::new(),
+        }
+    }
+
+    /// The conditions that must all hold for this one to hold, i.e. the
+    /// operands of its top-level ANDs.
+    pub(crate) fn get_conjuncts(&self) -> Vec<&Condition> {
+        match self {
+            Condition::And(lhs, rhs) => {
+                let mut conjuncts = lhs.get_conjuncts();
+                conjuncts.extend(rhs.get_conjuncts());
+                conjuncts
+            }
+            other => vec![other],
+        }
+    }
+
+    /// Checks that everything the condition compares has comparable types,
+    /// given the types of the columns it reads, so that a mismatch is
+    /// reported before any row is read. Columns missing from `types` and
+    /// aggregates are left to evaluation.
+    pub(crate) fn check_types(&self, types: &HashMap<String, ColumnType>) -> Result<(), TypeError> {
+        match self {
+            Condition::Simple { field, value, .. } => {
+                check_compared(&Expr::column(field), &Expr::Literal(value.clone()), types)
+            }
+            Condition::Comparison {
+                left_field,
+                right_field,
+                ..
+            } => check_compared(&Expr::column(left_field), &Expr::column(right_field), types),
+            Condition::Expression { left, right, .. } => check_compared(left, right, types),
+            Condition::In { operand, list } => list
+                .iter()
+                .try_for_each(|item| check_compared(operand, item, types)),
+            Condition::Between { operand, low, high } => {
+                check_compared(operand, low, types)?;
+                check_compared(operand, high, types)
+            }
+            Condition::Like { operand, .. } => match operand.infer_type(types)? {
+                Some(ColumnType::Text { .. }) | None => Ok(()),
+                Some(column_type) => Err(TypeError::new(&format!(
+                    "LIKE cannot be applied to {column_type}"
+                ))),
+            },
+            Condition::Not(condition) => condition.check_types(types),
+            Condition::And(lhs, rhs) | Condition::Or(lhs, rhs) => {
+                lhs.check_types(types)?;
+                rhs.check_types(types)
+            }
+            Condition::Aggregate { .. }
+            | Condition::IsNull { .. }
+            | Condition::IsNotNull { .. } => Ok(()),
+        }
+    }
+
+    /// The expressions compared by this condition itself, not by nested ones.
+    fn get_expressions(&self) -> Vec<&Expr> {
+        match self {
+            Condition::Expression { left, right, .. } => vec![left, right],
+            Condition::In { operand, list } => {
+                let mut expressions = vec![operand];
+                expressions.extend(list);
+                expressions
+            }
+            Condition::Between { operand, low, high } => vec![operand, low, high],
+            Condition::Like { operand, .. } => vec![operand],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Checks that two compared expressions have comparable types. A literal is
+/// checked the way the planner coerces it, so a string compared with a DATE
+/// column passes.
+fn check_compared(
+    left: &Expr,
+    right: &Expr,
+    types: &HashMap<String, ColumnType>,
+) -> Result<(), TypeError> {
+    match (
+        left,
+        left.infer_type(types)?,
+        right,
+        right.infer_type(types)?,
+    ) {
+        (_, Some(column_type), Expr::Literal(value), _)
+        | (Expr::Literal(value), _, _, Some(column_type)) => {
+            coerce_for_comparison(value, &column_type).map(drop)
+        }
+        (_, Some(left_type), _, Some(right_type)) => check_comparable(&left_type, &right_type),
+        _ => Ok(()),
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Operator::Equals => "=",
+            Operator::LessThan => "<",
+            Operator::GreaterThan => ">",
+            Operator::NotEquals => "<>",
+            Operator::LessThanOrEquals => "<=",
+            Operator::GreaterThanOrEquals => ">=",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Simple {
+                field,
+                operator,
+                value,
+            } => write!(f, "{field} {operator} {}", Literal(value)),
+            Condition::Comparison {
+                left_field,
+                operator,
+                right_field,
+            } => write!(f, "{left_field} {operator} {right_field}"),
+            Condition::Aggregate {
+                aggregate,
+                operator,
+                value,
+            } => write!(
+                f,
+                "{} {operator} {}",
+                aggregate.get_canonical_name(),
+                Literal(value)
+            ),
+            Condition::Expression {
+                left,
+                operator,
+                right,
+            } => write!(f, "{left} {operator} {right}"),
+            Condition::In { operand, list } => {
+                let list: Vec<String> = list.iter().map(ToString::to_string).collect();
+                write!(f, "{operand} IN ({})", list.join(", "))
+            }
+            Condition::Between { operand, low, high } => {
+                write!(f, "{operand} BETWEEN {low} AND {high}")
+            }
+            Condition::Like { operand, pattern } => write!(f, "{operand} {pattern}"),
+            Condition::IsNull { field } => write!(f, "{field} IS NULL"),
+            Condition::IsNotNull { field } => write!(f, "{field} IS NOT NULL"),
+            Condition::Not(condition) => match condition.as_ref() {
+                Condition::And(..) | Condition::Or(..) => write!(f, "NOT ({condition})"),
+                condition => write!(f, "NOT {condition}"),
+            },
+            Condition::And(lhs, rhs) => {
+                let operand = |condition: &Condition| match condition {
+                    Condition::Or(..) => format!("({condition})"),
+                    condition => condition.to_string(),
+                };
+                write!(f, "{} AND {}", operand(lhs), operand(rhs))
+            }
+            Condition::Or(lhs, rhs) => write!(f, "{lhs} OR {rhs}"),
+        }
+    }
 }
@@ -0,0 +1,176 @@
+use crate::bloom_filter::{dimensions, positions, MembershipFilter, DEFAULT_SEEDS};
+use crate::error::DbError;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// The layout of a serialized `CountingBloomFilter`, versioned separately
+/// from the plain filter's.
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// A Bloom filter with a small counter per slot instead of a bit, so items
+/// can be removed again. A counter that reaches its maximum sticks there:
+/// it can no longer tell how many items share the slot, and decrementing it
+/// could make the filter miss one of them. It hashes with `DEFAULT_SEEDS`
+/// and serializes with a format version like `BloomFilter`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "CountingBloomFilterData", bound = "")]
+pub(crate) struct CountingBloomFilter<T> {
+    version: u32,
+    counters: Vec<u8>,
+    num_of_functions: usize,
+    #[serde(skip)]
+    phantom: PhantomData<T>,
+}
+
+impl<T: Serialize> CountingBloomFilter<T> {
+    pub(crate) fn new(n: usize, error_percent: f32) -> Self {
+        let (size, num_of_functions) = dimensions(n, error_percent);
+        CountingBloomFilter {
+            version: FORMAT_VERSION,
+            counters: vec![0; size],
+            num_of_functions,
+            phantom: PhantomData,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, DbError> {
+        bincode::deserialize(bytes).map_err(DbError::from)
+    }
+
+    fn positions(&self, item: &T) -> Vec<usize> {
+        positions(
+            item,
+            self.num_of_functions,
+            self.counters.len(),
+            DEFAULT_SEEDS,
+        )
+    }
+}
+
+impl<T: Serialize> MembershipFilter<T> for CountingBloomFilter<T> {
+    fn add(&mut self, item: &T) {
+        for pos in self.positions(item) {
+            self.counters[pos] = self.counters[pos].saturating_add(1);
+        }
+    }
+
+    fn check(&self, item: &T) -> bool {
+        self.positions(item)
+            .iter()
+            .all(|&pos| self.counters[pos] > 0)
+    }
+
+    /// Only call this for items that were added, otherwise the counters of
+    /// other items are decremented and the filter may miss them.
+    fn remove(&mut self, item: &T) -> bool {
+        if !self.check(item) {
+            return false;
+        }
+        for pos in self.positions(item) {
+            if self.counters[pos] < u8::MAX {
+                self.counters[pos] -= 1;
+            }
+        }
+        true
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, DbError> {
+        bincode::serialize(self).map_err(DbError::from)
+    }
+}
+
+/// The fields of a serialized `CountingBloomFilter`, checked before they
+/// become one.
+#[derive(Deserialize)]
+struct CountingBloomFilterData {
+    version: u32,
+    counters: Vec<u8>,
+    num_of_functions: usize,
+}
+
+impl<T> TryFrom<CountingBloomFilterData> for CountingBloomFilter<T> {
+    type Error = String;
+
+    fn try_from(data: CountingBloomFilterData) -> Result<Self, Self::Error> {
+        if data.version != FORMAT_VERSION {
+            return Err(format!(
+                "counting Bloom filter format version {} is not supported, expected {FORMAT_VERSION}",
+                data.version
+            ));
+        }
+        if data.num_of_functions == 0 || data.num_of_functions > data.counters.len() {
+            return Err(format!(
+                "counting Bloom filter of {} counters cannot use {} hash functions",
+                data.counters.len(),
+                data.num_of_functions
+            ));
+        }
+        Ok(CountingBloomFilter {
+            version: data.version,
+            counters: data.counters,
+            num_of_functions: data.num_of_functions,
+            phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_check_and_remove() {
+        let mut filter = CountingBloomFilter::<u32>::new(100, 0.01);
+        filter.add(&1);
+        filter.add(&2);
+        filter.add(&2);
+        assert!(filter.check(&1) && filter.check(&2));
+        assert!(filter.remove(&1));
+        assert!(!filter.check(&1));
+        assert!(!filter.remove(&1));
+        // An item added twice stays until it is removed twice.
+        assert!(filter.remove(&2));
+        assert!(filter.check(&2));
+        assert!(filter.remove(&2));
+        assert!(!filter.check(&2));
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let mut filter = CountingBloomFilter::<u32>::new(100, 0.01);
+        filter.add(&1);
+        filter.add(&2);
+        filter.add(&2);
+        let mut loaded =
+            CountingBloomFilter::<u32>::from_bytes(&filter.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded, filter);
+        assert!(loaded.remove(&2));
+        assert!(loaded.check(&2));
+
+        let mut other_version = filter.clone();
+        other_version.version = FORMAT_VERSION + 1;
+        assert!(matches!(
+            CountingBloomFilter::<u32>::from_bytes(&other_version.to_bytes().unwrap()),
+            Err(DbError::Parse(_))
+        ));
+        let mut too_many_functions = filter;
+        too_many_functions.num_of_functions = too_many_functions.counters.len() + 1;
+        assert!(matches!(
+            CountingBloomFilter::<u32>::from_bytes(&too_many_functions.to_bytes().unwrap()),
+            Err(DbError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_saturated_counters_stick() {
+        let mut filter = CountingBloomFilter::<u32>::new(1, 0.5);
+        for _ in 0..=u8::MAX {
+            filter.add(&7);
+        }
+        for _ in 0..=u8::MAX {
+            filter.remove(&7);
+        }
+        assert!(filter.check(&7));
+    }
+}
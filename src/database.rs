@@ -0,0 +1,1333 @@
+use crate::coercion::coerce_for_column;
+use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::function_registry::FunctionRegistry;
+use crate::metadata::MetaData;
+use crate::query_result::QueryResult;
+use crate::rational_algebra::aggregation::CustomAggregate;
+use crate::rational_algebra::sort::DEFAULT_SORT_MEMORY_BUDGET;
+use crate::scalar_function::ScalarFunction;
+use crate::schema_struct::{ColumnConstraint, ColumnDef};
+use crate::sql::ast::{CreateIndexStatement, CreateTableStatement, InsertStatement, Statement};
+use crate::sql::parser::parse;
+use crate::sql::planner::execute_select;
+use crate::sql::SqlError;
+use crate::table_struct::Table;
+use crate::ManipulateTable;
+use std::collections::HashMap;
+
+const DEFAULT_TABLE_CAPACITY: usize = 1000;
+const DEFAULT_ERROR_PERCENT: f32 = 0.01;
+
+pub enum ExecutionResult {
+    TableCreated(String),
+    IndexCreated(String),
+    RowsInserted(usize),
+    Rows(QueryResult),
+}
+
+pub struct Database {
+    tables: HashMap<String, Table>,
+    functions: FunctionRegistry,
+    sort_memory_budget: usize,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Database {
+    #[must_use]
+    pub fn new() -> Self {
+        Database {
+            tables: HashMap::new(),
+            functions: FunctionRegistry::new(),
+            sort_memory_budget: DEFAULT_SORT_MEMORY_BUDGET,
+        }
+    }
+
+    /// Sets the approximate number of bytes of rows an ORDER BY keeps in
+    /// memory before it spills sorted runs to temporary files.
+    pub fn set_sort_memory_budget(&mut self, bytes: usize) {
+        self.sort_memory_budget = bytes;
+    }
+
+    /// Makes a scalar function callable from queries under its name.
+    ///
+    /// # Errors
+    ///
+    /// Returns a schema error if a function of that name already exists.
+    pub fn register_scalar_function(&mut self, function: ScalarFunction) -> Result<(), DbError> {
+        self.functions.register_scalar(function)
+    }
+
+    /// Makes a custom aggregate callable from queries as `name(column)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a schema error if a function of that name already exists.
+    pub fn register_aggregate_function<A>(
+        &mut self,
+        name: &str,
+        aggregate: A,
+    ) -> Result<(), DbError>
+    where
+        A: CustomAggregate + 'static,
+    {
+        self.functions.register_aggregate(name, aggregate)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(name)
+    }
+
+    /// Parses and runs one SQL statement.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SqlError::Parse` when `sql` is not a valid statement and
+    /// `SqlError::Execution` when it cannot be planned or run.
+    pub fn execute(&mut self, sql: &str) -> Result<ExecutionResult, SqlError> {
+        match parse(sql)? {
+            Statement::Select(select) => Ok(ExecutionResult::Rows(execute_select(
+                &self.tables,
+                &self.functions,
+                &select,
+                self.sort_memory_budget,
+            )?)),
+            Statement::Insert(insert) => Ok(ExecutionResult::RowsInserted(self.insert(&insert)?)),
+            Statement::CreateTable(create) => {
+                Ok(ExecutionResult::TableCreated(self.create_table(create)?))
+            }
+            Statement::CreateIndex(create) => {
+                Ok(ExecutionResult::IndexCreated(self.create_index(create)?))
+            }
+        }
+    }
+
+    fn create_table(&mut self, create: CreateTableStatement) -> Result<String, DbError> {
+        if self.tables.contains_key(&create.name) {
+            return Err(DbError::Schema(format!(
+                "table '{}' already exists",
+                create.name
+            )));
+        }
+        let mut columns: Vec<ColumnDef> = Vec::new();
+        for mut column in create.columns {
+            if columns.iter().any(|c| c.get_name() == column.get_name()) {
+                return Err(DbError::Schema(format!(
+                    "column '{}' is defined more than once",
+                    column.get_name()
+                )));
+            }
+            if let Some(default) = column.get_default() {
+                let default = adapt_value(default, &column)?;
+                column = column.with_default(default);
+            }
+            if create.primary_key.contains(&column.get_name().to_string()) {
+                column = column.with_constraint(ColumnConstraint::PrimaryKey);
+            }
+            columns.push(column);
+        }
+        if let Some(missing) = create
+            .primary_key
+            .iter()
+            .find(|name| !columns.iter().any(|c| c.get_name() == name.as_str()))
+        {
+            return Err(DbError::NotFound(format!(
+                "primary key column '{missing}' does not exist"
+            )));
+        }
+        let meta_data = MetaData::new(DEFAULT_TABLE_CAPACITY, DEFAULT_ERROR_PERCENT);
+        let mut table = Table::new(create.name.clone(), meta_data, columns);
+        table.set_primary_key(&create.primary_key);
+        self.tables.insert(create.name.clone(), table);
+        Ok(create.name)
+    }
+
+    fn create_index(&mut self, create: CreateIndexStatement) -> Result<String, DbError> {
+        if self.tables.values().any(|table| {
+            table
+                .get_indexes()
+                .iter()
+                .any(|index| index.get_name() == create.name)
+        }) {
+            return Err(DbError::Schema(format!(
+                "index '{}' already exists",
+                create.name
+            )));
+        }
+        let table = self
+            .tables
+            .get_mut(&create.table)
+            .ok_or_else(|| DbError::NotFound(format!("table '{}' does not exist", create.table)))?;
+        table.create_index(&create.name, &create.columns)?;
+        Ok(create.name)
+    }
+
+    fn insert(&mut self, insert: &InsertStatement) -> Result<usize, DbError> {
+        let table = self
+            .tables
+            .get_mut(&insert.table)
+            .ok_or_else(|| DbError::NotFound(format!("table '{}' does not exist", insert.table)))?;
+        let column_names = table.get_column_names();
+        let positions: Vec<Option<usize>> = match &insert.columns {
+            None => (0..column_names.len()).map(Some).collect(),
+            Some(columns) => {
+                if let Some(unknown) = columns.iter().find(|c| !column_names.contains(c)) {
+                    return Err(DbError::NotFound(format!(
+                        "column '{unknown}' does not exist in table '{}'",
+                        table.get_name()
+                    )));
+                }
+                column_names
+                    .iter()
+                    .map(|name| columns.iter().position(|c| c == name))
+                    .collect()
+            }
+        };
+        let expected = insert.columns.as_ref().map_or(column_names.len(), Vec::len);
+        if let Some(row) = insert.rows.iter().find(|row| row.len() != expected) {
+            return Err(DbError::Schema(format!(
+                "expected {expected} values per row, found {}",
+                row.len()
+            )));
+        }
+        let mut rows = Vec::with_capacity(insert.rows.len());
+        for row in &insert.rows {
+            let mut values = Vec::with_capacity(positions.len());
+            for (column, position) in table.get_schema().get_columns().iter().zip(&positions) {
+                let value = match position {
+                    Some(index) => row[*index].clone(),
+                    None => column.get_default().cloned().unwrap_or(DbType::Null),
+                };
+                values.push(adapt_value(&value, column)?);
+            }
+            rows.push(values);
+        }
+        table.insert_all(rows)
+    }
+}
+
+/// Converts a literal to its column's type and checks that the column
+/// accepts it.
+fn adapt_value(value: &DbType, column: &ColumnDef) -> Result<DbType, DbError> {
+    let value = coerce_for_column(value.clone(), column.get_type()).map_err(|error| {
+        DbError::Type(format!("column '{}': {}", column.get_name(), error.message))
+    })?;
+    column.check(&value)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_type::DbType;
+    use crate::decimal::Decimal;
+    use crate::scalar_function::{ParameterKind, Signature};
+    use crate::schema_struct::ColumnType;
+    use crate::table_row::TableRow;
+
+    fn set_up_database() -> Database {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT)",
+            "CREATE TABLE ages (id INT, age INT)",
+            "INSERT INTO users VALUES (1, 'Alice'), (2, 'Bob'), (3, 'Carol')",
+            "INSERT INTO ages (age, id) VALUES (30, 1), (25, 2), (30, 3)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        database
+    }
+
+    fn rows(database: &mut Database, sql: &str) -> QueryResult {
+        match database.execute(sql).unwrap() {
+            ExecutionResult::Rows(result) => result,
+            _ => panic!("expected rows from {sql}"),
+        }
+    }
+
+    #[test]
+    fn test_select_where() {
+        let mut database = set_up_database();
+        let result = rows(&mut database, "SELECT name FROM users WHERE id > 1");
+        assert_eq!(result.get_column_names(), &vec!["name".to_string()]);
+        assert_eq!(result.get_data().len(), 2);
+    }
+
+    #[test]
+    fn test_select_join_with_alias() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT u.name, a.age AS years FROM users u JOIN ages a ON u.id = a.id WHERE 27 < age",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["name".to_string(), "years".to_string()]
+        );
+        assert_eq!(result.get_data().len(), 2);
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Text("Alice".to_string()), DbType::Int(30)]
+        );
+    }
+
+    #[test]
+    fn test_select_left_join() {
+        let mut database = set_up_database();
+        database
+            .execute("INSERT INTO users VALUES (4, 'Dave')")
+            .unwrap();
+        let result = rows(
+            &mut database,
+            "SELECT name, age FROM users LEFT OUTER JOIN ages USING (id)",
+        );
+        assert_eq!(result.get_data().len(), 4);
+        assert_eq!(
+            result.get_data()[3].get_values(),
+            &vec![DbType::Text("Dave".to_string()), DbType::Null]
+        );
+    }
+
+    #[test]
+    fn test_select_join_on_composite_and_range_predicate() {
+        let mut database = set_up_database();
+        for sql in [
+            "CREATE TABLE sessions (user_id INT, day INT, expires INT)",
+            "CREATE TABLE events (user_id INT, day INT, ts INT)",
+            "INSERT INTO sessions VALUES (1, 1, 100), (1, 2, 50), (2, 1, 10)",
+            "INSERT INTO events VALUES (1, 1, 90), (1, 2, 90), (2, 1, 5), (2, 2, 5)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT e.user_id, e.day FROM events e JOIN sessions s \
+             ON e.user_id = s.user_id AND e.day = s.day AND e.ts < s.expires",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["user_id".to_string(), "day".to_string()]
+        );
+        let values: Vec<&Vec<DbType>> =
+            result.get_data().iter().map(TableRow::get_values).collect();
+        assert_eq!(
+            values,
+            vec![
+                &vec![DbType::Int(1), DbType::Int(1)],
+                &vec![DbType::Int(2), DbType::Int(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_column_is_an_error() {
+        let mut database = set_up_database();
+        assert!(database
+            .execute("SELECT id FROM users u JOIN ages a ON u.id = a.id")
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_group_by() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT age, COUNT(*), AVG(id) AS mean_id FROM ages GROUP BY age",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec![
+                "age".to_string(),
+                "COUNT(*)".to_string(),
+                "mean_id".to_string()
+            ]
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Int(30), DbType::BigInt(2), DbType::Float(2.0)]
+        );
+        assert_eq!(
+            result.get_data()[1].get_values(),
+            &vec![DbType::Int(25), DbType::BigInt(1), DbType::Float(2.0)]
+        );
+    }
+
+    #[test]
+    fn test_select_aggregate_over_join_without_group_by() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT SUM(a.age), MAX(u.name) FROM users u JOIN ages a ON u.id = a.id",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["SUM(age)".to_string(), "MAX(name)".to_string()]
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::BigInt(85), DbType::Text("Carol".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_select_having() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT age FROM ages GROUP BY age HAVING COUNT(*) > 1 AND 10 < MIN(id) OR age = 25",
+        );
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(result.get_data()[0].get_values(), &vec![DbType::Int(25)]);
+        let result = rows(
+            &mut database,
+            "SELECT age, COUNT(*) AS n FROM ages GROUP BY age HAVING COUNT(*) > 1",
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Int(30), DbType::BigInt(2)]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_in_where_is_an_error() {
+        let mut database = set_up_database();
+        assert!(database
+            .execute("SELECT age FROM ages WHERE COUNT(*) > 1")
+            .is_err());
+        assert!(database
+            .execute("SELECT age FROM ages GROUP BY age HAVING id > 1")
+            .is_err());
+    }
+
+    #[test]
+    fn test_ungrouped_column_is_an_error() {
+        let mut database = set_up_database();
+        assert!(database
+            .execute("SELECT id, COUNT(*) FROM ages GROUP BY age")
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_distinct() {
+        let mut database = set_up_database();
+        let result = rows(&mut database, "SELECT DISTINCT age FROM ages");
+        assert_eq!(result.get_data().len(), 2);
+    }
+
+    #[test]
+    fn test_select_order_by() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT u.name FROM users u JOIN ages a USING (id) ORDER BY a.age DESC, u.name",
+        );
+        let names: Vec<DbType> = result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values()[0].clone())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                DbType::Text("Alice".to_string()),
+                DbType::Text("Carol".to_string()),
+                DbType::Text("Bob".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_order_by_spills_with_a_small_memory_budget() {
+        let mut database = Database::new();
+        database.execute("CREATE TABLE t (id INT, v INT)").unwrap();
+        let values: Vec<String> = (0..300)
+            .map(|id| format!("({id}, {})", (id * 37) % 101))
+            .collect();
+        database
+            .execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+            .unwrap();
+        let sorted = |database: &mut Database| -> Vec<Vec<DbType>> {
+            rows(database, "SELECT v, id FROM t ORDER BY v DESC, id")
+                .into_data()
+                .into_iter()
+                .map(|row| row.get_values().clone())
+                .collect()
+        };
+        let expected = sorted(&mut database);
+        database.set_sort_memory_budget(256);
+        assert_eq!(sorted(&mut database), expected);
+        assert_eq!(expected[0], vec![DbType::Int(100), DbType::Int(30)]);
+    }
+
+    #[test]
+    fn test_select_order_by_aggregate_alias() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT age, COUNT(*) AS total FROM ages GROUP BY age ORDER BY total",
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Int(25), DbType::BigInt(1)]
+        );
+    }
+
+    #[test]
+    fn test_select_order_by_limit_offset() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT name FROM users ORDER BY id DESC LIMIT 1 OFFSET 1",
+        );
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Text("Bob".to_string())]
+        );
+
+        let result = rows(&mut database, "SELECT DISTINCT age FROM ages LIMIT 1");
+        assert_eq!(result.get_data()[0].get_values(), &vec![DbType::Int(30)]);
+    }
+
+    #[test]
+    fn test_select_is_null_after_left_join() {
+        let mut database = set_up_database();
+        database
+            .execute("INSERT INTO users VALUES (4, 'Dave')")
+            .unwrap();
+        let result = rows(
+            &mut database,
+            "SELECT name FROM users LEFT JOIN ages USING (id) WHERE age IS NULL",
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Text("Dave".to_string())]
+        );
+        let result = rows(
+            &mut database,
+            "SELECT name FROM users LEFT JOIN ages USING (id) WHERE age IS NOT NULL OR age = NULL",
+        );
+        assert_eq!(result.get_data().len(), 3);
+    }
+
+    #[test]
+    fn test_select_with_in_between_and_like() {
+        let mut database = set_up_database();
+        let names = |database: &mut Database, sql: &str| -> Vec<DbType> {
+            rows(database, sql)
+                .get_data()
+                .iter()
+                .map(|row| row.get_values()[0].clone())
+                .collect()
+        };
+        assert_eq!(
+            names(
+                &mut database,
+                "SELECT name FROM users WHERE id NOT IN (1, 3) OR name LIKE 'C%'"
+            ),
+            vec![
+                DbType::Text("Bob".to_string()),
+                DbType::Text("Carol".to_string())
+            ]
+        );
+        assert_eq!(
+            names(
+                &mut database,
+                "SELECT name FROM users JOIN ages USING (id) \
+                 WHERE age BETWEEN 26 AND 30 AND NOT name ILIKE '%ROL' AND id <= 3"
+            ),
+            vec![DbType::Text("Alice".to_string())]
+        );
+        let error = database
+            .execute("SELECT name FROM users WHERE id LIKE '1%'")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: LIKE cannot be applied to INT"
+        );
+        assert!(database
+            .execute("SELECT name FROM users WHERE id IN (1, 'x')")
+            .is_err());
+    }
+
+    #[test]
+    fn test_insert_null_into_not_null_column() {
+        let mut database = set_up_database();
+        database
+            .execute("CREATE TABLE pets (id INT PRIMARY KEY, name TEXT NOT NULL, owner INT NULL)")
+            .unwrap();
+        database
+            .execute("INSERT INTO pets VALUES (1, 'Rex', NULL)")
+            .unwrap();
+        let error = database
+            .execute("INSERT INTO pets VALUES (2, NULL, 1)")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Constraint Error: column 'name' cannot be NULL"
+        );
+        assert!(database
+            .execute("INSERT INTO pets VALUES (NULL, 'Tom', 1)")
+            .is_err());
+    }
+
+    #[test]
+    fn test_rich_column_types() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE orders (id BIGINT PRIMARY KEY, paid BOOLEAN, placed DATE, \
+             shipped TIMESTAMP, total DECIMAL(10, 2), receipt BLOB, weight FLOAT)",
+            "INSERT INTO orders VALUES (1, TRUE, DATE '2024-03-01', \
+             TIMESTAMP '2024-03-02 10:30:00', 19.9, X'CAFE', 2)",
+            "INSERT INTO orders VALUES (9000000000, FALSE, NULL, NULL, DECIMAL '5.125', NULL, 1.5)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT id, total, weight FROM orders WHERE paid = TRUE",
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::BigInt(1),
+                DbType::Decimal(Decimal::parse("19.90").unwrap()),
+                DbType::Float(2.0),
+            ]
+        );
+        let result = rows(&mut database, "SELECT SUM(total) FROM orders");
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Decimal(Decimal::parse("25.03").unwrap())]
+        );
+        let result = rows(
+            &mut database,
+            "SELECT id FROM orders WHERE placed = DATE '2024-03-01'",
+        );
+        assert_eq!(result.get_data().len(), 1);
+    }
+
+    #[test]
+    fn test_insert_type_mismatch_is_an_error() {
+        let mut database = set_up_database();
+        let error = database
+            .execute("INSERT INTO users VALUES ('one', 'Eve')")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: column 'id' expects INT, found TEXT"
+        );
+    }
+
+    #[test]
+    fn test_select_by_primary_key() {
+        let mut database = set_up_database();
+        let names = |database: &mut Database, sql: &str| -> Vec<DbType> {
+            rows(database, sql)
+                .get_data()
+                .iter()
+                .map(|row| row.get_values()[0].clone())
+                .collect()
+        };
+        assert_eq!(
+            names(&mut database, "SELECT name FROM users WHERE id = 2"),
+            vec![DbType::Text("Bob".to_string())]
+        );
+        assert_eq!(
+            names(&mut database, "SELECT name FROM users WHERE 3.0 = id"),
+            vec![DbType::Text("Carol".to_string())]
+        );
+        assert!(names(
+            &mut database,
+            "SELECT name FROM users WHERE id = 2 AND name = 'Alice'"
+        )
+        .is_empty());
+        assert!(names(&mut database, "SELECT name FROM users WHERE id = NULL").is_empty());
+    }
+
+    #[test]
+    fn test_select_by_primary_key_of_different_scale() {
+        let mut database = Database::new();
+        database
+            .execute("CREATE TABLE p (price DECIMAL(10, 2) PRIMARY KEY, name TEXT)")
+            .unwrap();
+        database
+            .execute("INSERT INTO p VALUES (3, 'three'), (DECIMAL '4.5', 'four')")
+            .unwrap();
+        database
+            .execute("CREATE TABLE f (weight FLOAT PRIMARY KEY, name TEXT)")
+            .unwrap();
+        database
+            .execute("INSERT INTO f VALUES (2.5, 'two')")
+            .unwrap();
+        let name = |database: &mut Database, sql: &str| -> Vec<DbType> {
+            rows(database, sql)
+                .get_data()
+                .iter()
+                .map(|row| row.get_values()[0].clone())
+                .collect()
+        };
+        let text = |value: &str| vec![DbType::Text(value.to_string())];
+        for sql in [
+            "SELECT name FROM p WHERE price = 3",
+            "SELECT name FROM p WHERE price = 3.0",
+            "SELECT name FROM p WHERE price = DECIMAL '3.000'",
+        ] {
+            assert_eq!(name(&mut database, sql), text("three"), "{sql}");
+        }
+        assert_eq!(
+            name(
+                &mut database,
+                "SELECT name FROM p WHERE price = DECIMAL '4.5'"
+            ),
+            text("four")
+        );
+        assert_eq!(
+            name(
+                &mut database,
+                "SELECT name FROM f WHERE weight = DECIMAL '2.50'"
+            ),
+            text("two")
+        );
+    }
+
+    #[test]
+    fn test_range_queries_with_and_without_index() {
+        let mut database = set_up_database();
+        let queries = [
+            "SELECT id FROM ages WHERE age = 30",
+            "SELECT id FROM ages WHERE age > 25",
+            "SELECT id FROM ages WHERE age <= 25 OR id = 3",
+            "SELECT id FROM ages WHERE age BETWEEN 26 AND 40 AND id < 3",
+            "SELECT id FROM ages WHERE age > 27.5",
+            "SELECT id FROM users WHERE name LIKE 'Ca%'",
+            "SELECT id FROM users WHERE name LIKE 'B_b'",
+            "SELECT id FROM users WHERE name ILIKE 'a%'",
+        ];
+        let results = |database: &mut Database| -> Vec<Vec<TableRow>> {
+            queries
+                .iter()
+                .map(|sql| rows(database, sql).get_data().clone())
+                .collect()
+        };
+        let ids = |rows: &Vec<TableRow>| -> Vec<DbType> {
+            rows.iter().map(|row| row.get_values()[0].clone()).collect()
+        };
+        let scanned = results(&mut database);
+        database
+            .execute("CREATE INDEX by_age ON ages (age)")
+            .unwrap();
+        database
+            .execute("CREATE INDEX by_name ON users (name, id)")
+            .unwrap();
+        let indexed = results(&mut database);
+        assert_eq!(
+            scanned.iter().map(ids).collect::<Vec<_>>(),
+            indexed.iter().map(ids).collect::<Vec<_>>()
+        );
+        assert_eq!(ids(&indexed[0]), vec![DbType::Int(1), DbType::Int(3)]);
+        assert_eq!(ids(&indexed[3]), vec![DbType::Int(1)]);
+        assert_eq!(ids(&indexed[5]), vec![DbType::Int(3)]);
+        database.execute("INSERT INTO ages VALUES (4, 50)").unwrap();
+        assert_eq!(
+            ids(rows(&mut database, "SELECT id FROM ages WHERE age > 40").get_data()),
+            vec![DbType::Int(4)]
+        );
+    }
+
+    #[test]
+    fn test_create_index_errors() {
+        let mut database = set_up_database();
+        database
+            .execute("CREATE INDEX by_age ON ages (age)")
+            .unwrap();
+        let error =
+            |database: &mut Database, sql: &str| database.execute(sql).err().unwrap().to_string();
+        assert_eq!(
+            error(&mut database, "CREATE INDEX by_age ON users (name)"),
+            "Schema Error: index 'by_age' already exists"
+        );
+        assert_eq!(
+            error(&mut database, "CREATE INDEX by_city ON people (city)"),
+            "Not Found Error: table 'people' does not exist"
+        );
+        assert_eq!(
+            error(&mut database, "CREATE INDEX by_city ON users (city)"),
+            "Not Found Error: column 'city' does not exist in table 'users'"
+        );
+    }
+
+    #[test]
+    fn test_insert_duplicate_primary_key_is_an_error() {
+        let mut database = set_up_database();
+        let error = database
+            .execute("INSERT INTO users VALUES (1, 'Eve')")
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            SqlError::Execution(DbError::Constraint(ref message))
+                if message == "duplicate primary key [Int(1)]"
+        ));
+        // A statement is checked as a whole: no row of a rejected one stays,
+        // whether it clashes with the table or with another row it inserts.
+        for sql in [
+            "INSERT INTO users VALUES (4, 'Dan'), (1, 'Eve')",
+            "INSERT INTO users VALUES (5, 'Fay'), (5, 'Gus')",
+        ] {
+            assert!(matches!(
+                database.execute(sql),
+                Err(SqlError::Execution(DbError::Constraint(_)))
+            ));
+        }
+        assert_eq!(
+            rows(&mut database, "SELECT id FROM users").get_data().len(),
+            3
+        );
+        database
+            .execute("CREATE TABLE tags (id INT PRIMARY KEY, code TEXT UNIQUE)")
+            .unwrap();
+        assert!(database
+            .execute("INSERT INTO tags VALUES (1, 'a'), (2, 'a')")
+            .is_err());
+        assert!(rows(&mut database, "SELECT id FROM tags")
+            .get_data()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_insert_uses_column_defaults() {
+        let mut database = Database::new();
+        database
+            .execute(
+                "CREATE TABLE accounts (id INT PRIMARY KEY, name VARCHAR(8) NOT NULL, \
+                 balance DECIMAL(8, 2) DEFAULT 0, note TEXT)",
+            )
+            .unwrap();
+        database
+            .execute("INSERT INTO accounts (name, id) VALUES ('Alice', 1)")
+            .unwrap();
+        let result = rows(&mut database, "SELECT balance, note FROM accounts");
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::Decimal(Decimal::parse("0.00").unwrap()),
+                DbType::Null
+            ]
+        );
+        let error = database
+            .execute("INSERT INTO accounts (id) VALUES (2)")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Constraint Error: column 'name' cannot be NULL"
+        );
+        let error = database
+            .execute("INSERT INTO accounts (id, name) VALUES (3, 'Bartholomew')")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: column 'name' value Text(\"Bartholomew\") does not fit VARCHAR(8)"
+        );
+        assert!(database
+            .execute("CREATE TABLE bad (id INT DEFAULT 'none')")
+            .is_err());
+    }
+
+    #[test]
+    fn test_comparisons_coerce_or_fail() {
+        let mut database = set_up_database();
+        database
+            .execute("CREATE TABLE events (id BIGINT, day DATE, code TEXT, score FLOAT)")
+            .unwrap();
+        database
+            .execute(
+                "INSERT INTO events VALUES (1, '2024-01-05', '10', 1.5), (2, '2024-02-01', '7', 3)",
+            )
+            .unwrap();
+        let result = rows(
+            &mut database,
+            "SELECT id FROM events WHERE day > '2024-01-31' AND score > 2",
+        );
+        assert_eq!(result.get_data()[0].get_values(), &vec![DbType::BigInt(2)]);
+        let result = rows(
+            &mut database,
+            "SELECT u.name FROM users u JOIN events e ON u.id = e.id WHERE e.id = 1",
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Text("Alice".to_string())]
+        );
+        let error = database
+            .execute("SELECT id FROM events WHERE code = 10")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: cannot compare TEXT with INT"
+        );
+        assert!(database
+            .execute("SELECT id FROM events WHERE day = 'tomorrow'")
+            .is_err());
+        assert!(database
+            .execute("SELECT e.id FROM events e JOIN users u ON e.code = u.id")
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_cast() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE codes (id INT, code TEXT, price DECIMAL(6, 2))",
+            "INSERT INTO codes VALUES (1, '10', 2.50), (2, '9', 10.75), (3, '100', 0.99)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT id, CAST(price AS INT) AS whole FROM codes \
+             WHERE CAST(code AS INT) > 9 ORDER BY whole DESC",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["id".to_string(), "whole".to_string()]
+        );
+        assert_eq!(
+            result
+                .get_data()
+                .iter()
+                .map(|row| row.get_values().clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![DbType::Int(1), DbType::Int(3)],
+                vec![DbType::Int(3), DbType::Int(1)]
+            ]
+        );
+        let result = rows(
+            &mut database,
+            "SELECT * FROM codes WHERE code = CAST(9 AS TEXT)",
+        );
+        assert_eq!(result.get_column_names().len(), 3);
+        assert_eq!(result.get_data()[0].get_values()[0], DbType::Int(2));
+        let error = database
+            .execute("SELECT id FROM codes WHERE id = CAST('x' AS INT)")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: cannot cast TEXT value 'x' to INT"
+        );
+        assert!(database
+            .execute("SELECT CAST(price AS DATE) FROM codes")
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_computed_columns() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE orders (id INT, item TEXT, price DECIMAL(6, 2), qty INT, discount INT)",
+            "INSERT INTO orders VALUES (1, 'pen', 1.50, 4, 1), (2, 'ink', 12.00, 1, 0), \
+             (3, 'pad', 3.25, 2, NULL)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT id, price * qty AS total, item || '#' || id, \
+             CASE WHEN qty > 1 THEN 'bulk' ELSE 'single' END AS kind \
+             FROM orders WHERE qty - discount > 1 ORDER BY total DESC",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec![
+                "id".to_string(),
+                "total".to_string(),
+                "(item || '#') || id".to_string(),
+                "kind".to_string()
+            ]
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::Int(1),
+                DbType::Decimal(Decimal::parse("6.00").unwrap()),
+                DbType::Text("pen#1".to_string()),
+                DbType::Text("bulk".to_string())
+            ]
+        );
+        assert_eq!(result.get_data().len(), 1);
+        let result = rows(
+            &mut database,
+            "SELECT id FROM orders WHERE (qty + 1) * 2 > id AND UPPER(item) <> 'INK'",
+        );
+        assert_eq!(result.get_data().len(), 2);
+        let error = database
+            .execute("SELECT item + 1 FROM orders")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: operator + cannot be applied to TEXT and INT"
+        );
+        assert!(database
+            .execute("SELECT id FROM orders WHERE qty * 2 = 'two'")
+            .is_err());
+        assert!(database.execute("SELECT NOPE(id) FROM orders").is_err());
+    }
+
+    #[test]
+    fn test_expression_evaluation_errors_are_returned() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE t (a INT, s TEXT)",
+            "INSERT INTO t VALUES (5, 'abc'), (0, 'x'), (-2147483648, 'y')",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        for sql in [
+            "SELECT 10 / a FROM t",
+            "SELECT a + 2147483647 FROM t",
+            "SELECT ABS(a) FROM t",
+            "SELECT MOD(a, 0) FROM t",
+            "SELECT ROUND(a, 100) FROM t",
+            "SELECT SUBSTR(s, 1, -1) FROM t",
+            "SELECT CAST(s AS INT) FROM t",
+        ] {
+            let error = database.execute(sql).err();
+            assert!(
+                matches!(error, Some(SqlError::Execution(DbError::Type(_)))),
+                "{sql}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_computed_columns_differing_only_in_literal_type() {
+        let mut database = Database::new();
+        for sql in ["CREATE TABLE t (x INT)", "INSERT INTO t VALUES (7)"] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT x / 2, x / 2.0, 7 / 2, 7.0 / 2, x / DECIMAL '2' FROM t",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec![
+                "x / 2".to_string(),
+                "x / 2.0".to_string(),
+                "7 / 2".to_string(),
+                "7.0 / 2".to_string(),
+                "x / DECIMAL '2'".to_string()
+            ]
+        );
+        let values = result.get_data()[0].get_values();
+        assert_eq!(
+            values[..4],
+            [
+                DbType::Int(3),
+                DbType::Float(3.5),
+                DbType::Int(3),
+                DbType::Float(3.5)
+            ]
+        );
+        assert!(matches!(values[4], DbType::Decimal(_)));
+    }
+
+    #[test]
+    fn test_case_branches_share_one_type() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE t (id INT, v INT, f FLOAT)",
+            "INSERT INTO t VALUES (1, 100, 1.25), (2, 2, 0.5), (3, 7, 1.25)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT CASE WHEN id = 1 THEN v ELSE f END AS k FROM t ORDER BY k",
+        );
+        let keys: Vec<DbType> = result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values()[0].clone())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                DbType::Float(0.5),
+                DbType::Float(1.25),
+                DbType::Float(100.0)
+            ]
+        );
+        let result = rows(
+            &mut database,
+            "SELECT DISTINCT CASE WHEN id = 1 THEN 1 ELSE 1.0 END FROM t",
+        );
+        assert_eq!(result.get_data().len(), 1);
+        let error = database
+            .execute("SELECT CASE WHEN id = 1 THEN v ELSE 'none' END FROM t")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: CASE branches do not match: INT and TEXT have no common type"
+        );
+    }
+
+    #[test]
+    fn test_coalesce_and_nullif_share_one_type() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE t (id INT, v INT, f FLOAT)",
+            "INSERT INTO t VALUES (1, 100, 1.25), (2, NULL, 1.25), (3, 2, NULL)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT id, COALESCE(v, f) AS k FROM t ORDER BY k",
+        );
+        let keys: Vec<Vec<DbType>> = result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values().clone())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                vec![DbType::Int(2), DbType::Float(1.25)],
+                vec![DbType::Int(3), DbType::Float(2.0)],
+                vec![DbType::Int(1), DbType::Float(100.0)]
+            ]
+        );
+        let result = rows(&mut database, "SELECT NULLIF(v, 2.5) FROM t WHERE id = 1");
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Float(100.0)]
+        );
+        let error = database
+            .execute("SELECT COALESCE(v, 'none') FROM t")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: INT and TEXT have no common type"
+        );
+    }
+
+    #[test]
+    fn test_select_scalar_functions() {
+        let mut database = Database::new();
+        for sql in [
+            "CREATE TABLE events (id INT, title TEXT, happened DATE, note TEXT, price DECIMAL(6, 2))",
+            "INSERT INTO events VALUES (1, '  Launch ', DATE '2024-03-05', NULL, 10.25), \
+             (2, 'retro', DATE '2023-11-20', 'late', -3.50)",
+        ] {
+            database.execute(sql).unwrap();
+        }
+        let result = rows(
+            &mut database,
+            "SELECT LOWER(TRIM(title)) AS name, SUBSTR(TRIM(title), 1, 3), \
+             COALESCE(note, 'none') AS note, ROUND(ABS(price), 1), MOD(id, 2) \
+             FROM events WHERE YEAR(happened) = 2024 OR LENGTH(title) < 6",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec![
+                "name".to_string(),
+                "SUBSTR(TRIM(title), 1, 3)".to_string(),
+                "note".to_string(),
+                "ROUND(ABS(price), 1)".to_string(),
+                "MOD(id, 2)".to_string()
+            ]
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::Text("launch".to_string()),
+                DbType::Text("Lau".to_string()),
+                DbType::Text("none".to_string()),
+                DbType::Decimal(Decimal::parse("10.3").unwrap()),
+                DbType::Int(1)
+            ]
+        );
+        assert_eq!(result.get_data().len(), 2);
+        let error = database
+            .execute("SELECT NOPE(id) FROM events")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Not Found Error: unknown function 'NOPE'"
+        );
+        let error = database
+            .execute("SELECT id FROM events WHERE LENGTH(id) > 1")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: LENGTH argument 1 must be TEXT, found INT"
+        );
+        assert!(database
+            .execute("SELECT SUBSTR(title) FROM events")
+            .is_err());
+    }
+
+    /// The difference between the largest and smallest value of a group.
+    struct Spread;
+
+    impl CustomAggregate for Spread {
+        type State = Option<(i32, i32)>;
+
+        fn init(&self) -> Self::State {
+            None
+        }
+
+        fn accumulate(&self, state: &mut Self::State, value: &DbType) -> Result<(), DbError> {
+            let DbType::Int(value) = *value else {
+                return Err(DbError::Type(format!(
+                    "SPREAD is not defined for {}",
+                    value.type_name()
+                )));
+            };
+            self.merge(state, Some((value, value)))
+        }
+
+        fn merge(&self, state: &mut Self::State, other: Self::State) -> Result<(), DbError> {
+            if let Some((low, high)) = other {
+                *state = Some(state.map_or((low, high), |(current_low, current_high)| {
+                    (current_low.min(low), current_high.max(high))
+                }));
+            }
+            Ok(())
+        }
+
+        fn finalize(&self, state: Self::State) -> Result<DbType, DbError> {
+            Ok(state.map_or(DbType::Null, |(low, high)| DbType::Int(high - low)))
+        }
+
+        fn return_type(&self, _argument: Option<&ColumnType>) -> Option<ColumnType> {
+            Some(ColumnType::Int)
+        }
+    }
+
+    #[test]
+    fn test_user_defined_functions() {
+        let mut database = set_up_database();
+        let initials = ScalarFunction::new("initials", |arguments| {
+            Ok(match &arguments[0] {
+                DbType::Text(text) => DbType::Text(text.chars().take(2).collect()),
+                _ => DbType::Null,
+            })
+        })
+        .with_signature(Signature::fixed(&[ParameterKind::Text]))
+        .returning(ColumnType::Text { max_length: None });
+        database.register_scalar_function(initials).unwrap();
+        database
+            .register_aggregate_function("spread", Spread)
+            .unwrap();
+        let result = rows(
+            &mut database,
+            "SELECT initials(name) AS short FROM users WHERE INITIALS(name) <> 'Bo' ORDER BY short",
+        );
+        assert_eq!(
+            result.get_data()[1].get_values(),
+            &vec![DbType::Text("Ca".to_string())]
+        );
+        let result = rows(
+            &mut database,
+            "SELECT age, SPREAD(id) AS ids FROM ages GROUP BY age HAVING SPREAD(id) > 0",
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Int(30), DbType::Int(2)]
+        );
+        assert_eq!(result.get_data().len(), 1);
+        let error = database
+            .execute("SELECT INITIALS(id) FROM users")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: INITIALS argument 1 must be TEXT, found INT"
+        );
+        let error = database
+            .register_aggregate_function("upper", Spread)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Schema Error: function 'UPPER' already exists"
+        );
+    }
+
+    #[test]
+    fn test_select_expressions_over_groups() {
+        let mut database = set_up_database();
+        let result = rows(
+            &mut database,
+            "SELECT age, COUNT(*) * 10 AS score, SUM(id) + age FROM ages \
+             GROUP BY age HAVING MAX(id) - MIN(id) > 1 ORDER BY score",
+        );
+        assert_eq!(
+            result.get_column_names(),
+            &vec![
+                "age".to_string(),
+                "score".to_string(),
+                "SUM(id) + age".to_string()
+            ]
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Int(30), DbType::BigInt(20), DbType::BigInt(34)]
+        );
+        assert!(database
+            .execute("SELECT id + 1 FROM ages GROUP BY age")
+            .is_err());
+    }
+
+    #[test]
+    fn test_insert_reorders_columns() {
+        let database = set_up_database();
+        let ages = database.get_table("ages").unwrap();
+        assert_eq!(
+            ages.get_data()[0].get_values(),
+            &vec![DbType::Int(1), DbType::Int(30)]
+        );
+    }
+
+    #[test]
+    fn test_unknown_column_is_an_error() {
+        let mut database = set_up_database();
+        let error = database.execute("SELECT salary FROM users").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Not Found Error: column 'salary' does not exist or is ambiguous"
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_is_an_error() {
+        let mut database = set_up_database();
+        let error = database.execute("SELECT FROM users").err().unwrap();
+        assert!(matches!(error, SqlError::Parse(_)));
+    }
+
+    #[test]
+    fn test_insert_arity_mismatch() {
+        let mut database = set_up_database();
+        assert!(database.execute("INSERT INTO users VALUES (4)").is_err());
+    }
+}
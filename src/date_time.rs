@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+const MICROS_PER_DAY: i64 = SECONDS_PER_DAY * MICROS_PER_SECOND;
+
+/// A calendar date stored as the number of days since 1970-01-01.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Date {
+    days: i32,
+}
+
+impl Date {
+    pub(crate) fn from_days(days: i32) -> Self {
+        Date { days }
+    }
+
+    pub(crate) fn from_ymd(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        i32::try_from(days_from_civil(i64::from(year), month, day))
+            .ok()
+            .map(Date::from_days)
+    }
+
+    /// Parses `YYYY-MM-DD`.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Date::from_ymd(year, month, day)
+    }
+
+    pub(crate) fn get_days(self) -> i32 {
+        self.days
+    }
+
+    pub(crate) fn to_ymd(self) -> (i32, u32, u32) {
+        let (year, month, day) = civil_from_days(i64::from(self.days));
+        (
+            i32::try_from(year).expect("year of an i32 day count fits in i32"),
+            month,
+            day,
+        )
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.to_ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// A point in time stored as microseconds since 1970-01-01 00:00:00 UTC.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Timestamp {
+    micros: i64,
+}
+
+impl Timestamp {
+    pub(crate) fn from_micros(micros: i64) -> Self {
+        Timestamp { micros }
+    }
+
+    pub(crate) fn from_date_time(
+        date: Date,
+        hour: u32,
+        minute: u32,
+        second: u32,
+        micro: u32,
+    ) -> Option<Self> {
+        if hour > 23 || minute > 59 || second > 59 || i64::from(micro) >= MICROS_PER_SECOND {
+            return None;
+        }
+        let seconds = i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second);
+        let micros = i64::from(date.get_days())
+            .checked_mul(MICROS_PER_DAY)?
+            .checked_add(seconds * MICROS_PER_SECOND + i64::from(micro))?;
+        Some(Timestamp::from_micros(micros))
+    }
+
+    /// Parses `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` or the same with a `T`
+    /// separator and up to six fractional second digits.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (date, time) = match text.split_once([' ', 'T']) {
+            Some((date, time)) => (Date::parse(date)?, time),
+            None => return Timestamp::from_date_time(Date::parse(text)?, 0, 0, 0, 0),
+        };
+        let (clock, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let mut parts = clock.splitn(3, ':');
+        let hour = parts.next()?.parse().ok()?;
+        let minute = parts.next()?.parse().ok()?;
+        let second = parts.next().map_or(Some(0), |second| second.parse().ok())?;
+        if fraction.len() > 6 || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let micro = if fraction.is_empty() {
+            0
+        } else {
+            format!("{fraction:0<6}").parse().ok()?
+        };
+        Timestamp::from_date_time(date, hour, minute, second, micro)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn get_micros(self) -> i64 {
+        self.micros
+    }
+
+    pub(crate) fn date(self) -> Date {
+        let days = self.micros.div_euclid(MICROS_PER_DAY);
+        Date::from_days(i32::try_from(days).expect("day count of an i64 timestamp fits in i32"))
+    }
+
+    /// Returns the hour, minute, second and microsecond within the day.
+    pub(crate) fn time(self) -> (u32, u32, u32, u32) {
+        let micros = self.micros.rem_euclid(MICROS_PER_DAY);
+        let seconds = micros / MICROS_PER_SECOND;
+        let part = |value: i64| u32::try_from(value).expect("time of day component fits in u32");
+        (
+            part(seconds / 3600),
+            part(seconds / 60 % 60),
+            part(seconds % 60),
+            part(micros % MICROS_PER_SECOND),
+        )
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (hour, minute, second, micro) = self.time();
+        write!(f, "{} {hour:02}:{minute:02}:{second:02}", self.date())?;
+        if micro > 0 {
+            write!(f, ".{micro:06}")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Proleptic Gregorian conversions following Howard Hinnant's
+// `days_from_civil` and `civil_from_days` algorithms.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = i64::from((month + 9) % 12);
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let narrow = |value: i64| u32::try_from(value).expect("month and day fit in u32");
+    (year, narrow(month), narrow(day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_round_trip() {
+        assert_eq!(Date::from_ymd(1970, 1, 1).unwrap().get_days(), 0);
+        assert_eq!(Date::from_ymd(2000, 3, 1).unwrap().get_days(), 11_017);
+        assert_eq!(Date::from_ymd(1969, 12, 31).unwrap().get_days(), -1);
+        let date = Date::parse("2024-02-29").unwrap();
+        assert_eq!(date.to_ymd(), (2024, 2, 29));
+        assert_eq!(date.to_string(), "2024-02-29");
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        assert!(Date::parse("2023-02-29").is_none());
+        assert!(Date::parse("2024-13-01").is_none());
+        assert!(Date::parse("2024-04-31").is_none());
+        assert!(Date::parse("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_timestamp_parse_and_display() {
+        let timestamp = Timestamp::parse("2024-05-06 07:08:09.25").unwrap();
+        assert_eq!(timestamp.time(), (7, 8, 9, 250_000));
+        assert_eq!(timestamp.date(), Date::parse("2024-05-06").unwrap());
+        assert_eq!(timestamp.to_string(), "2024-05-06 07:08:09.250000");
+        assert_eq!(
+            Timestamp::parse("2024-05-06T07:08:09").unwrap().to_string(),
+            "2024-05-06 07:08:09"
+        );
+        assert_eq!(
+            Timestamp::parse("1969-12-31 23:59:59")
+                .unwrap()
+                .get_micros(),
+            -1_000_000
+        );
+        assert!(Timestamp::parse("2024-05-06 24:00:00").is_none());
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Date::parse("2023-12-31").unwrap() < Date::parse("2024-01-01").unwrap());
+        assert!(
+            Timestamp::parse("2024-01-01 00:00:01").unwrap()
+                > Timestamp::parse("2024-01-01").unwrap()
+        );
+    }
+}
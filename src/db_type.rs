@@ -1,7 +1,170 @@
+use crate::date_time::{Date, Timestamp};
+use crate::decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
-#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize, Hash, Eq)]
-pub(crate) enum DbType {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DbType {
+    Null,
+    Bool(bool),
     Int(i32),
+    BigInt(i64),
+    Float(f64),
+    Decimal(Decimal),
     Text(String),
+    Date(Date),
+    Timestamp(Timestamp),
+    Blob(Vec<u8>),
+}
+
+impl DbType {
+    pub(crate) fn is_null(&self) -> bool {
+        matches!(self, DbType::Null)
+    }
+
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            DbType::Null => "NULL",
+            DbType::Bool(_) => "BOOL",
+            DbType::Int(_) => "INT",
+            DbType::BigInt(_) => "BIGINT",
+            DbType::Float(_) => "FLOAT",
+            DbType::Decimal(_) => "DECIMAL",
+            DbType::Text(_) => "TEXT",
+            DbType::Date(_) => "DATE",
+            DbType::Timestamp(_) => "TIMESTAMP",
+            DbType::Blob(_) => "BLOB",
+        }
+    }
+
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DbType::Int(_) | DbType::BigInt(_) | DbType::Float(_) | DbType::Decimal(_)
+        )
+    }
+
+    /// Whether `self` is a value of the same variant as `other`, ignoring the
+    /// payload. Used to check values against a column's type placeholder.
+    pub(crate) fn has_type_of(&self, other: &DbType) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            DbType::Null => 0,
+            DbType::Bool(_) => 1,
+            DbType::Int(_) => 2,
+            DbType::BigInt(_) => 3,
+            DbType::Float(_) => 4,
+            DbType::Decimal(_) => 5,
+            DbType::Text(_) => 6,
+            DbType::Date(_) => 7,
+            DbType::Timestamp(_) => 8,
+            DbType::Blob(_) => 9,
+        }
+    }
+}
+
+impl PartialEq for DbType {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DbType {}
+
+impl PartialOrd for DbType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DbType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (DbType::Bool(a), DbType::Bool(b)) => a.cmp(b),
+            (DbType::Int(a), DbType::Int(b)) => a.cmp(b),
+            (DbType::BigInt(a), DbType::BigInt(b)) => a.cmp(b),
+            (DbType::Float(a), DbType::Float(b)) => a.total_cmp(b),
+            (DbType::Decimal(a), DbType::Decimal(b)) => a.cmp(b),
+            (DbType::Text(a), DbType::Text(b)) => a.cmp(b),
+            (DbType::Date(a), DbType::Date(b)) => a.cmp(b),
+            (DbType::Timestamp(a), DbType::Timestamp(b)) => a.cmp(b),
+            (DbType::Blob(a), DbType::Blob(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for DbType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            DbType::Null => {}
+            DbType::Bool(value) => value.hash(state),
+            DbType::Int(value) => value.hash(state),
+            DbType::BigInt(value) => value.hash(state),
+            DbType::Float(value) => value.to_bits().hash(state),
+            DbType::Decimal(value) => value.hash(state),
+            DbType::Text(value) => value.hash(state),
+            DbType::Date(value) => value.hash(state),
+            DbType::Timestamp(value) => value.hash(state),
+            DbType::Blob(value) => value.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for DbType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DbType::Null => write!(f, "NULL"),
+            DbType::Bool(value) => write!(f, "{value}"),
+            DbType::Int(value) => write!(f, "{value}"),
+            DbType::BigInt(value) => write!(f, "{value}"),
+            DbType::Float(value) => write!(f, "{value}"),
+            DbType::Decimal(value) => write!(f, "{value}"),
+            DbType::Text(value) => write!(f, "{value}"),
+            DbType::Date(value) => write!(f, "{value}"),
+            DbType::Timestamp(value) => write!(f, "{value}"),
+            DbType::Blob(value) => {
+                write!(f, "X'")?;
+                for byte in value {
+                    write!(f, "{byte:02X}")?;
+                }
+                write!(f, "'")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float_total_ordering() {
+        let mut values = [
+            DbType::Float(2.5),
+            DbType::Float(f64::NAN),
+            DbType::Float(-1.0),
+            DbType::Null,
+        ];
+        values.sort();
+        assert_eq!(values[0], DbType::Null);
+        assert_eq!(values[1], DbType::Float(-1.0));
+        assert_eq!(values[2], DbType::Float(2.5));
+        assert_eq!(values[3], DbType::Float(f64::NAN));
+    }
+
+    #[test]
+    fn test_float_hash_is_consistent_with_eq() {
+        use std::collections::HashSet;
+        let set: HashSet<DbType> = [DbType::Float(0.5), DbType::Float(0.5), DbType::Int(1)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
 }
@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+pub(crate) const MAX_SCALE: u32 = 28;
+pub(crate) const MIN_DIVISION_SCALE: u32 = 6;
+
+/// A fixed-point number stored as `units * 10^-scale`, so `12.50` is 1250
+/// units at scale 2. Values that differ only in trailing zeros are equal and
+/// hash the same.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Decimal {
+    units: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub(crate) fn new(units: i128, scale: u32) -> Self {
+        assert!(
+            scale <= MAX_SCALE,
+            "decimal scale {scale} exceeds {MAX_SCALE}"
+        );
+        Decimal { units, scale }
+    }
+
+    /// Parses a decimal literal such as `-12.50`, keeping its scale.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty()
+            || !all_digits(integer)
+            || !all_digits(fraction)
+        {
+            return None;
+        }
+        let scale = u32::try_from(fraction.len()).ok()?;
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mut units: i128 = 0;
+        for byte in integer.bytes().chain(fraction.bytes()) {
+            units = units
+                .checked_mul(10)?
+                .checked_add(i128::from(byte - b'0'))?;
+        }
+        Some(Decimal::new(if negative { -units } else { units }, scale))
+    }
+
+    pub(crate) fn get_units(&self) -> i128 {
+        self.units
+    }
+
+    pub(crate) fn get_scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Changes the number of fractional digits, rounding half away from zero
+    /// when digits are dropped. Returns `None` on overflow.
+    pub(crate) fn rescale(self, scale: u32) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let units = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.units,
+            Ordering::Greater => self.units.checked_mul(pow10(scale - self.scale)?)?,
+            Ordering::Less => divide_rounded(self.units, pow10(self.scale - scale)?)?,
+        };
+        Some(Decimal::new(units, scale))
+    }
+
+    pub(crate) fn checked_add(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let units = self
+            .rescale(scale)?
+            .units
+            .checked_add(other.rescale(scale)?.units)?;
+        Some(Decimal::new(units, scale))
+    }
+
+    pub(crate) fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(Decimal::new(other.units.checked_neg()?, other.scale))
+    }
+
+    pub(crate) fn checked_mul(self, other: Self) -> Option<Self> {
+        let units = self.units.checked_mul(other.units)?;
+        let scale = self.scale + other.scale;
+        if scale <= MAX_SCALE {
+            Some(Decimal::new(units, scale))
+        } else {
+            let units = divide_rounded(units, pow10(scale - MAX_SCALE)?)?;
+            Some(Decimal::new(units, MAX_SCALE))
+        }
+    }
+
+    /// Divides keeping at least six fractional digits, or more if either
+    /// operand has more. Returns `None` when dividing by zero or on overflow.
+    pub(crate) fn checked_div(self, other: Self) -> Option<Self> {
+        if other.units == 0 {
+            return None;
+        }
+        let scale = self
+            .scale
+            .max(other.scale)
+            .clamp(MIN_DIVISION_SCALE, MAX_SCALE);
+        let numerator = self
+            .units
+            .checked_mul(pow10(scale + other.scale - self.scale)?)?;
+        Some(Decimal::new(divide_rounded(numerator, other.units)?, scale))
+    }
+
+    /// The remainder of truncating division, which has the sign of `self`.
+    /// Returns `None` when dividing by zero or on overflow.
+    pub(crate) fn checked_rem(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let units = self
+            .rescale(scale)?
+            .units
+            .checked_rem(other.rescale(scale)?.units)?;
+        Some(Decimal::new(units, scale))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn to_f64(self) -> f64 {
+        self.units as f64 / 10f64.powi(i32::try_from(self.scale).unwrap_or(i32::MAX))
+    }
+
+    /// The same value with trailing fractional zeros dropped, so `4.50`
+    /// becomes `4.5` and `3.00` becomes `3`.
+    pub(crate) fn normalized(self) -> Self {
+        let mut normalized = self;
+        while normalized.scale > 0 && normalized.units % 10 == 0 {
+            normalized.units /= 10;
+            normalized.scale -= 1;
+        }
+        normalized
+    }
+
+    fn split(self) -> (i128, i128) {
+        let factor = pow10(self.scale).unwrap_or(i128::MAX);
+        (self.units / factor, self.units % factor)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal::new(i128::from(value), 0)
+    }
+}
+
+fn pow10(exponent: u32) -> Option<i128> {
+    10i128.checked_pow(exponent)
+}
+
+fn divide_rounded(numerator: i128, denominator: i128) -> Option<i128> {
+    let quotient = numerator.checked_div(denominator)?;
+    let remainder = numerator % denominator;
+    if remainder.unsigned_abs() * 2 >= denominator.unsigned_abs() {
+        let away_from_zero = if (numerator < 0) == (denominator < 0) {
+            1
+        } else {
+            -1
+        };
+        quotient.checked_add(away_from_zero)
+    } else {
+        Some(quotient)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Comparing integer and fractional parts separately avoids overflowing
+        // when one side would have to be scaled up by many digits.
+        let (integer, fraction) = self.split();
+        let (other_integer, other_fraction) = other.split();
+        let scale = self.scale.max(other.scale);
+        integer.cmp(&other_integer).then_with(|| {
+            let align = |value: i128, from: u32| value * pow10(scale - from).unwrap_or(1);
+            align(fraction, self.scale).cmp(&align(other_fraction, other.scale))
+        })
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalized();
+        normalized.units.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.units < 0 { "-" } else { "" };
+        let digits = self.units.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(decimal("12.50").to_string(), "12.50");
+        assert_eq!(decimal("-0.05").to_string(), "-0.05");
+        assert_eq!(decimal("7").to_string(), "7");
+        assert_eq!(decimal(".5").to_string(), "0.5");
+        assert!(Decimal::parse("1.2.3").is_none());
+        assert!(Decimal::parse("abc").is_none());
+        assert!(Decimal::parse("-").is_none());
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        let sum = decimal("0.1").checked_add(decimal("0.2")).unwrap();
+        assert_eq!(sum, decimal("0.3"));
+        assert_eq!(
+            decimal("10.25")
+                .checked_sub(decimal("0.5"))
+                .unwrap()
+                .to_string(),
+            "9.75"
+        );
+        assert_eq!(
+            decimal("1.5")
+                .checked_mul(decimal("-2.25"))
+                .unwrap()
+                .to_string(),
+            "-3.375"
+        );
+        assert_eq!(
+            decimal("1").checked_div(decimal("3")).unwrap().to_string(),
+            "0.333333"
+        );
+        assert_eq!(
+            decimal("2").checked_div(decimal("3")).unwrap().to_string(),
+            "0.666667"
+        );
+        assert!(decimal("1").checked_div(decimal("0")).is_none());
+        assert_eq!(
+            decimal("-7.5")
+                .checked_rem(decimal("2"))
+                .unwrap()
+                .to_string(),
+            "-1.5"
+        );
+        assert!(decimal("1").checked_rem(decimal("0.00")).is_none());
+    }
+
+    #[test]
+    fn test_rescale_rounds_half_away_from_zero() {
+        assert_eq!(decimal("2.345").rescale(2).unwrap().to_string(), "2.35");
+        assert_eq!(decimal("-2.345").rescale(2).unwrap().to_string(), "-2.35");
+        assert_eq!(decimal("2.344").rescale(2).unwrap().to_string(), "2.34");
+        assert_eq!(decimal("2.3").rescale(3).unwrap().to_string(), "2.300");
+    }
+
+    #[test]
+    fn test_ordering_and_hash_ignore_trailing_zeros() {
+        use std::collections::HashSet;
+        assert_eq!(decimal("1.50"), decimal("1.5"));
+        assert!(decimal("-1.5") < decimal("-1.25"));
+        assert!(decimal("-0.5") < decimal("0.25"));
+        assert!(decimal("100000000000000") > decimal("0.0000000000000000000000000001"));
+        let set: HashSet<Decimal> = [decimal("1.50"), decimal("1.5"), decimal("1.05")]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+}
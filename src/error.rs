@@ -0,0 +1,125 @@
+use crate::coercion::TypeError;
+use crate::expression::EvaluationError;
+use crate::sql::parser::ParseError;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The errors the storage and relational algebra layers report instead of
+/// panicking. Each variant carries a human readable message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DbError {
+    /// The data does not have the shape of the table, e.g. a wrong arity,
+    /// or a definition clashes with an existing one.
+    Schema(String),
+    /// A value does not fit the type of its column.
+    Type(String),
+    /// A NOT NULL, UNIQUE or PRIMARY KEY constraint would be violated.
+    Constraint(String),
+    /// A table, column or function that the operation refers to does not
+    /// exist.
+    NotFound(String),
+    /// A well-formed query that cannot be planned, such as an aggregate in
+    /// WHERE.
+    Query(String),
+    Parse(String),
+    Io(String),
+}
+
+impl DbError {
+    pub(crate) fn message(&self) -> &str {
+        match self {
+            DbError::Schema(message)
+            | DbError::Type(message)
+            | DbError::Constraint(message)
+            | DbError::NotFound(message)
+            | DbError::Query(message)
+            | DbError::Parse(message)
+            | DbError::Io(message) => message,
+        }
+    }
+
+    pub(crate) fn column_not_found(name: &str) -> Self {
+        DbError::NotFound(format!("column '{name}' not found or ambiguous"))
+    }
+}
+
+impl Error for DbError {}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            DbError::Schema(_) => "Schema Error",
+            DbError::Type(_) => "Type Error",
+            DbError::Constraint(_) => "Constraint Error",
+            DbError::NotFound(_) => "Not Found Error",
+            DbError::Query(_) => "Query Error",
+            DbError::Parse(_) => "Parse Error",
+            DbError::Io(_) => "IO Error",
+        };
+        write!(f, "{kind}: {}", self.message())
+    }
+}
+
+impl From<TypeError> for DbError {
+    fn from(error: TypeError) -> Self {
+        DbError::Type(error.message)
+    }
+}
+
+/// Evaluation fails on values outside what an operation or type allows,
+/// such as a division by zero or an INT overflow.
+impl From<EvaluationError> for DbError {
+    fn from(error: EvaluationError) -> Self {
+        DbError::Type(error.message)
+    }
+}
+
+impl From<ParseError> for DbError {
+    fn from(error: ParseError) -> Self {
+        DbError::Parse(error.to_string())
+    }
+}
+
+impl From<io::Error> for DbError {
+    fn from(error: io::Error) -> Self {
+        DbError::Io(error.to_string())
+    }
+}
+
+/// Bytes that end early surface as an IO error, anything else bincode or a
+/// `try_from` check rejects as a parse error.
+impl From<bincode::Error> for DbError {
+    fn from(error: bincode::Error) -> Self {
+        match *error {
+            bincode::ErrorKind::Io(error) => DbError::from(error),
+            error => DbError::Parse(error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_conversions() {
+        let error = DbError::Constraint("column 'id' cannot be NULL".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Constraint Error: column 'id' cannot be NULL"
+        );
+        let error = DbError::from(TypeError::new("cannot compare INT with TEXT 'a'"));
+        assert_eq!(
+            error,
+            DbError::Type("cannot compare INT with TEXT 'a'".to_string())
+        );
+        let error = DbError::Query("SELECT * cannot be combined with GROUP BY".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Query Error: SELECT * cannot be combined with GROUP BY"
+        );
+        let error = DbError::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert_eq!(error.to_string(), "IO Error: missing");
+    }
+}
@@ -0,0 +1,959 @@
+use crate::coercion::{
+    as_decimal, as_f64, cast, check_castable, common_type, compare, decimal_scale, TypeError,
+};
+use crate::condition::Condition;
+use crate::date_time::Date;
+use crate::db_type::DbType;
+use crate::decimal::{Decimal, MAX_SCALE, MIN_DIVISION_SCALE};
+use crate::error::DbError;
+use crate::rational_algebra::aggregation::{AggregateExpr, AggregateFunction};
+use crate::rational_algebra::helper_functions::resolve_column_index;
+use crate::rational_algebra::selection::make_truth_evaluator;
+use crate::scalar_function::ScalarFunction;
+use crate::schema_struct::{ColumnType, MAX_DECIMAL_PRECISION};
+use crate::table_row::TableRow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Concat,
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Concat => "||",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+/// A scalar expression computed from the values of one row. Columns are
+/// referred to by name and resolved when an evaluator is built.
+#[derive(Clone, Debug)]
+pub(crate) enum Expr {
+    Column(String),
+    Literal(DbType),
+    Binary {
+        left: Box<Expr>,
+        operator: BinaryOperator,
+        right: Box<Expr>,
+    },
+    Negate(Box<Expr>),
+    Function {
+        function: Rc<ScalarFunction>,
+        arguments: Vec<Expr>,
+    },
+    Case {
+        branches: Vec<(Condition, Expr)>,
+        otherwise: Option<Box<Expr>>,
+    },
+    Cast {
+        expr: Box<Expr>,
+        target: ColumnType,
+    },
+    /// A conversion added while planning so that all values of an expression
+    /// have its type, such as the INT branch of a CASE whose other branch is
+    /// FLOAT. It prints as the expression it converts.
+    Coerce {
+        expr: Box<Expr>,
+        target: ColumnType,
+    },
+    /// The result of an aggregate, read from the column `aggregate` fills in
+    /// after grouping.
+    Aggregate(AggregateExpr),
+}
+
+impl Expr {
+    pub(crate) fn column(name: &str) -> Self {
+        Expr::Column(name.to_string())
+    }
+
+    pub(crate) fn binary(left: Expr, operator: BinaryOperator, right: Expr) -> Self {
+        Expr::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    /// The columns the expression reads, including those of CASE conditions
+    /// and the output columns of aggregates.
+    pub(crate) fn get_columns(&self) -> Vec<String> {
+        match self {
+            Expr::Column(name) => vec![name.clone()],
+            Expr::Aggregate(aggregate) => vec![aggregate.get_canonical_name()],
+            Expr::Literal(_) => Vec::new(),
+            Expr::Binary { left, right, .. } => {
+                let mut columns = left.get_columns();
+                columns.extend(right.get_columns());
+                columns
+            }
+            Expr::Negate(expr) | Expr::Cast { expr, .. } | Expr::Coerce { expr, .. } => {
+                expr.get_columns()
+            }
+            Expr::Function { arguments, .. } => {
+                arguments.iter().flat_map(Expr::get_columns).collect()
+            }
+            Expr::Case {
+                branches,
+                otherwise,
+            } => {
+                let mut columns: Vec<String> = branches
+                    .iter()
+                    .flat_map(|(condition, result)| {
+                        let mut columns = condition.get_fields();
+                        columns.extend(result.get_columns());
+                        columns
+                    })
+                    .collect();
+                if let Some(otherwise) = otherwise {
+                    columns.extend(otherwise.get_columns());
+                }
+                columns
+            }
+        }
+    }
+
+    pub(crate) fn get_aggregates(&self) -> Vec<&AggregateExpr> {
+        match self {
+            Expr::Aggregate(aggregate) => vec![aggregate],
+            Expr::Column(_) | Expr::Literal(_) => Vec::new(),
+            Expr::Binary { left, right, .. } => {
+                let mut aggregates = left.get_aggregates();
+                aggregates.extend(right.get_aggregates());
+                aggregates
+            }
+            Expr::Negate(expr) | Expr::Cast { expr, .. } | Expr::Coerce { expr, .. } => {
+                expr.get_aggregates()
+            }
+            Expr::Function { arguments, .. } => {
+                arguments.iter().flat_map(Expr::get_aggregates).collect()
+            }
+            Expr::Case {
+                branches,
+                otherwise,
+            } => {
+                let mut aggregates: Vec<&AggregateExpr> = branches
+                    .iter()
+                    .flat_map(|(condition, result)| {
+                        let mut aggregates = condition.get_aggregates();
+                        aggregates.extend(result.get_aggregates());
+                        aggregates
+                    })
+                    .collect();
+                if let Some(otherwise) = otherwise {
+                    aggregates.extend(otherwise.get_aggregates());
+                }
+                aggregates
+            }
+        }
+    }
+
+    /// Works out the type of the expression from the types of the columns it
+    /// reads, rejecting operators applied to the wrong types. `None` means the
+    /// type is not known before evaluation, as for NULL or a column missing
+    /// from `types`.
+    pub(crate) fn infer_type(
+        &self,
+        types: &HashMap<String, ColumnType>,
+    ) -> Result<Option<ColumnType>, TypeError> {
+        match self {
+            Expr::Column(name) => Ok(types.get(name).cloned()),
+            Expr::Literal(value) => Ok(ColumnType::of(value)),
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => match (left.infer_type(types)?, right.infer_type(types)?) {
+                (Some(left), Some(right)) => binary_type(*operator, &left, &right).map(Some),
+                _ if *operator == BinaryOperator::Concat => {
+                    Ok(Some(ColumnType::Text { max_length: None }))
+                }
+                _ => Ok(None),
+            },
+            Expr::Negate(expr) => match expr.infer_type(types)? {
+                Some(column_type) => negate_type(&column_type).map(Some),
+                None => Ok(None),
+            },
+            Expr::Function {
+                function,
+                arguments,
+            } => {
+                let argument_types = arguments
+                    .iter()
+                    .map(|argument| argument.infer_type(types))
+                    .collect::<Result<Vec<_>, TypeError>>()?;
+                function.return_type(&argument_types)
+            }
+            Expr::Case {
+                branches,
+                otherwise,
+            } => {
+                let result_types = branches
+                    .iter()
+                    .map(|(_, result)| result)
+                    .chain(otherwise.as_deref())
+                    .map(|result| result.infer_type(types))
+                    .collect::<Result<Vec<_>, TypeError>>()?;
+                common_type(&result_types).map_err(|error| {
+                    TypeError::new(&format!("CASE branches do not match: {}", error.message))
+                })
+            }
+            Expr::Cast { expr, target } => {
+                if let Some(column_type) = expr.infer_type(types)? {
+                    check_castable(&column_type, target)?;
+                }
+                Ok(Some(target.clone()))
+            }
+            Expr::Coerce { target, .. } => Ok(Some(target.clone())),
+            Expr::Aggregate(aggregate) => Ok(match (&aggregate.function, &aggregate.column) {
+                (AggregateFunction::Count, _) => Some(ColumnType::BigInt),
+                (AggregateFunction::Custom(custom), column) => {
+                    custom.return_type(column.as_ref().and_then(|column| types.get(column)))
+                }
+                (AggregateFunction::Sum, Some(column)) => types.get(column).map(sum_type),
+                (AggregateFunction::Avg, Some(column)) => types.get(column).map(avg_type),
+                (_, Some(column)) => types.get(column).cloned(),
+                (_, None) => None,
+            }),
+        }
+    }
+
+    /// Converts the branches of a CASE, and the result of a function such as
+    /// COALESCE that passes on one of its arguments, to the type of the whole
+    /// expression, so its values have one type whichever branch or argument
+    /// gives them. Any other expression is returned as it is.
+    pub(crate) fn unify_types(
+        self,
+        types: &HashMap<String, ColumnType>,
+    ) -> Result<Self, TypeError> {
+        let target = self.infer_type(types)?;
+        let coerce = |expr: Expr| coerce_to(expr, target.as_ref(), types);
+        match self {
+            Expr::Case {
+                branches,
+                otherwise,
+            } => Ok(Expr::Case {
+                branches: branches
+                    .into_iter()
+                    .map(|(condition, result)| Ok((condition, coerce(result)?)))
+                    .collect::<Result<Vec<_>, TypeError>>()?,
+                otherwise: otherwise
+                    .map(|otherwise| coerce(*otherwise).map(Box::new))
+                    .transpose()?,
+            }),
+            // The function's type is already the common one, but the value
+            // it passes on still has the type of its own argument.
+            Expr::Function { ref function, .. } if function.coerces_result() => match target {
+                Some(target) => Ok(Expr::Coerce {
+                    expr: Box::new(self),
+                    target,
+                }),
+                None => Ok(self),
+            },
+            expr => Ok(expr),
+        }
+    }
+}
+
+/// Wraps `expr` in a conversion to `target` when its type is known and
+/// differs from it.
+fn coerce_to(
+    expr: Expr,
+    target: Option<&ColumnType>,
+    types: &HashMap<String, ColumnType>,
+) -> Result<Expr, TypeError> {
+    match (expr.infer_type(types)?, target) {
+        (Some(expr_type), Some(target)) if expr_type != *target => Ok(Expr::Coerce {
+            expr: Box::new(expr),
+            target: target.clone(),
+        }),
+        _ => Ok(expr),
+    }
+}
+
+/// SUM adds INT values as BIGINT and keeps the type of any other column.
+fn sum_type(column_type: &ColumnType) -> ColumnType {
+    match column_type {
+        ColumnType::Int => ColumnType::BigInt,
+        other => other.clone(),
+    }
+}
+
+/// AVG divides exact numbers exactly and everything else as FLOAT.
+fn avg_type(column_type: &ColumnType) -> ColumnType {
+    match column_type {
+        ColumnType::Decimal { scale, .. } => ColumnType::Decimal {
+            precision: MAX_DECIMAL_PRECISION,
+            scale: (*scale).clamp(MIN_DIVISION_SCALE, MAX_SCALE),
+        },
+        _ => ColumnType::Float,
+    }
+}
+
+/// Structural equality. Literals must also be spelled the same, so `2.0`
+/// and `DECIMAL '2.00'` stay apart even though the values compare equal.
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Expr::Column(name), Expr::Column(other)) => name == other,
+            (Expr::Literal(value), Expr::Literal(other)) => {
+                value == other && Literal(value).to_string() == Literal(other).to_string()
+            }
+            (
+                Expr::Binary {
+                    left,
+                    operator,
+                    right,
+                },
+                Expr::Binary {
+                    left: other_left,
+                    operator: other_operator,
+                    right: other_right,
+                },
+            ) => operator == other_operator && left == other_left && right == other_right,
+            (Expr::Negate(expr), Expr::Negate(other)) => expr == other,
+            (
+                Expr::Function {
+                    function,
+                    arguments,
+                },
+                Expr::Function {
+                    function: other_function,
+                    arguments: other_arguments,
+                },
+            ) => function == other_function && arguments == other_arguments,
+            (
+                Expr::Case {
+                    branches,
+                    otherwise,
+                },
+                Expr::Case {
+                    branches: other_branches,
+                    otherwise: other_otherwise,
+                },
+            ) => branches == other_branches && otherwise == other_otherwise,
+            (
+                Expr::Cast { expr, target },
+                Expr::Cast {
+                    expr: other_expr,
+                    target: other_target,
+                },
+            )
+            | (
+                Expr::Coerce { expr, target },
+                Expr::Coerce {
+                    expr: other_expr,
+                    target: other_target,
+                },
+            ) => target == other_target && expr == other_expr,
+            (Expr::Aggregate(aggregate), Expr::Aggregate(other)) => aggregate == other,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Column(name) => write!(f, "{name}"),
+            Expr::Literal(value) => write!(f, "{}", Literal(value)),
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => write!(f, "{} {operator} {}", Nested(left), Nested(right)),
+            Expr::Negate(expr) => write!(f, "-{}", Nested(expr)),
+            Expr::Function {
+                function,
+                arguments,
+            } => {
+                let arguments: Vec<String> = arguments.iter().map(ToString::to_string).collect();
+                write!(f, "{}({})", function.get_name(), arguments.join(", "))
+            }
+            Expr::Case {
+                branches,
+                otherwise,
+            } => {
+                write!(f, "CASE")?;
+                for (condition, result) in branches {
+                    write!(f, " WHEN {condition} THEN {result}")?;
+                }
+                if let Some(otherwise) = otherwise {
+                    write!(f, " ELSE {otherwise}")?;
+                }
+                write!(f, " END")
+            }
+            Expr::Cast { expr, target } => write!(f, "CAST({expr} AS {target})"),
+            Expr::Coerce { expr, .. } => write!(f, "{expr}"),
+            Expr::Aggregate(aggregate) => write!(f, "{}", aggregate.get_canonical_name()),
+        }
+    }
+}
+
+/// Writes a value the way it would be spelled in SQL, quoting strings and
+/// keeping the fraction of floats and the keyword of typed literals, so
+/// values of different types never print alike.
+pub(crate) struct Literal<'v>(pub(crate) &'v DbType);
+
+impl fmt::Display for Literal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            DbType::Null => write!(f, "NULL"),
+            DbType::Text(text) => write!(f, "'{}'", text.replace('\'', "''")),
+            DbType::Float(value) => write!(f, "{value:?}"),
+            DbType::Decimal(value) => write!(f, "DECIMAL '{value}'"),
+            DbType::Date(value) => write!(f, "DATE '{value}'"),
+            DbType::Timestamp(value) => write!(f, "TIMESTAMP '{value}'"),
+            value => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Parenthesizes binary operations nested inside another expression.
+struct Nested<'e>(&'e Expr);
+
+impl fmt::Display for Nested<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Expr::Binary { .. } => write!(f, "({})", self.0),
+            Expr::Coerce { expr, .. } => write!(f, "{}", Nested(expr)),
+            expr => write!(f, "{expr}"),
+        }
+    }
+}
+
+/// Computes the value of an expression for one row.
+pub(crate) type ExprEvaluator = Box<dyn Fn(&TableRow) -> Result<DbType, EvaluationError>>;
+
+/// Resolves the columns and functions of an expression once, so evaluating it
+/// per row only looks values up by index. Unknown columns are reported here
+/// rather than on the first row.
+pub(crate) fn make_expr_evaluator(
+    expr: &Expr,
+    column_names: &[String],
+) -> Result<ExprEvaluator, DbError> {
+    let evaluator: ExprEvaluator = match expr {
+        Expr::Column(name) => column_evaluator(name, column_names)?,
+        Expr::Aggregate(aggregate) => {
+            column_evaluator(&aggregate.get_canonical_name(), column_names)?
+        }
+        Expr::Literal(value) => {
+            let value = value.clone();
+            Box::new(move |_| Ok(value.clone()))
+        }
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => {
+            let left = make_expr_evaluator(left, column_names)?;
+            let right = make_expr_evaluator(right, column_names)?;
+            let operator = *operator;
+            Box::new(move |row| apply(operator, &left(row)?, &right(row)?))
+        }
+        Expr::Negate(expr) => {
+            let expr = make_expr_evaluator(expr, column_names)?;
+            Box::new(move |row| negate(&expr(row)?))
+        }
+        Expr::Function {
+            function,
+            arguments,
+        } => {
+            function.check_arity(arguments.len())?;
+            let function = Rc::clone(function);
+            let arguments = arguments
+                .iter()
+                .map(|argument| make_expr_evaluator(argument, column_names))
+                .collect::<Result<Vec<ExprEvaluator>, DbError>>()?;
+            Box::new(move |row| {
+                let values = arguments
+                    .iter()
+                    .map(|argument| argument(row))
+                    .collect::<Result<Vec<DbType>, EvaluationError>>()?;
+                function.call(&values)
+            })
+        }
+        Expr::Case {
+            branches,
+            otherwise,
+        } => {
+            let branches = branches
+                .iter()
+                .map(|(condition, result)| {
+                    Ok((
+                        make_truth_evaluator(condition, column_names)?,
+                        make_expr_evaluator(result, column_names)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, DbError>>()?;
+            let otherwise = otherwise
+                .as_ref()
+                .map(|otherwise| make_expr_evaluator(otherwise, column_names))
+                .transpose()?;
+            Box::new(move |row| {
+                for (condition, result) in &branches {
+                    if condition(row)? == Some(true) {
+                        return result(row);
+                    }
+                }
+                otherwise
+                    .as_ref()
+                    .map_or(Ok(DbType::Null), |otherwise| otherwise(row))
+            })
+        }
+        Expr::Cast { expr, target } | Expr::Coerce { expr, target } => {
+            let expr = make_expr_evaluator(expr, column_names)?;
+            let target = target.clone();
+            Box::new(move |row| Ok(cast(&expr(row)?, &target)?))
+        }
+    };
+    Ok(evaluator)
+}
+
+fn column_evaluator(name: &str, column_names: &[String]) -> Result<ExprEvaluator, DbError> {
+    let index =
+        resolve_column_index(column_names, name).ok_or_else(|| DbError::column_not_found(name))?;
+    Ok(Box::new(move |row| {
+        Ok(row.get_values().get(index).cloned().unwrap_or(DbType::Null))
+    }))
+}
+
+/// The type of `left operator right`:
+///
+/// | operands                        | result                          |
+/// |---------------------------------|---------------------------------|
+/// | INT and INT                     | INT                             |
+/// | INT or BIGINT and INT or BIGINT | BIGINT                          |
+/// | FLOAT and any number            | FLOAT                           |
+/// | DECIMAL and an exact number     | DECIMAL                         |
+/// | DATE `+` or `-` an integer      | DATE, moved by that many days   |
+/// | DATE `-` DATE                   | INT, the days between them      |
+/// | anything `\|\|` anything        | TEXT                            |
+pub(crate) fn binary_type(
+    operator: BinaryOperator,
+    left: &ColumnType,
+    right: &ColumnType,
+) -> Result<ColumnType, TypeError> {
+    let result = match (operator, left, right) {
+        (BinaryOperator::Concat, _, _) => Some(ColumnType::Text { max_length: None }),
+        (_, ColumnType::Int, ColumnType::Int) => Some(ColumnType::Int),
+        (_, ColumnType::Int | ColumnType::BigInt, ColumnType::Int | ColumnType::BigInt) => {
+            Some(ColumnType::BigInt)
+        }
+        (_, ColumnType::Float, other) | (_, other, ColumnType::Float) if other.is_numeric() => {
+            Some(ColumnType::Float)
+        }
+        (_, left, right) if left.is_numeric() && right.is_numeric() => Some(ColumnType::Decimal {
+            precision: MAX_DECIMAL_PRECISION,
+            scale: decimal_scale(left).max(decimal_scale(right)),
+        }),
+        (
+            BinaryOperator::Add | BinaryOperator::Subtract,
+            ColumnType::Date,
+            ColumnType::Int | ColumnType::BigInt,
+        )
+        | (BinaryOperator::Add, ColumnType::Int | ColumnType::BigInt, ColumnType::Date) => {
+            Some(ColumnType::Date)
+        }
+        (BinaryOperator::Subtract, ColumnType::Date, ColumnType::Date) => Some(ColumnType::Int),
+        _ => None,
+    };
+    result.ok_or_else(|| {
+        TypeError::new(&format!(
+            "operator {operator} cannot be applied to {left} and {right}"
+        ))
+    })
+}
+
+fn negate_type(column_type: &ColumnType) -> Result<ColumnType, TypeError> {
+    if column_type.is_numeric() {
+        Ok(column_type.clone())
+    } else {
+        Err(TypeError::new(&format!(
+            "operator - cannot be applied to {column_type}"
+        )))
+    }
+}
+
+/// Applies a binary operator with the typing rules of `binary_type`. NULL on
+/// either side gives NULL; integer division truncates, and dividing by zero
+/// or leaving the range of the result type is an error.
+pub(crate) fn apply(
+    operator: BinaryOperator,
+    left: &DbType,
+    right: &DbType,
+) -> Result<DbType, EvaluationError> {
+    let (Some(left_type), Some(right_type)) = (ColumnType::of(left), ColumnType::of(right)) else {
+        return Ok(DbType::Null);
+    };
+    let result_type = binary_type(operator, &left_type, &right_type)?;
+    if matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo)
+        && right.is_numeric()
+        && compare(right, &DbType::Int(0))? == Some(Ordering::Equal)
+    {
+        return Err(EvaluationError::new("division by zero"));
+    }
+    let result = match (operator, left, right) {
+        (BinaryOperator::Concat, left, right) => Some(DbType::Text(format!("{left}{right}"))),
+        (_, DbType::Date(left), DbType::Date(right)) => left
+            .get_days()
+            .checked_sub(right.get_days())
+            .map(DbType::Int),
+        (_, DbType::Date(date), days) | (_, days, DbType::Date(date)) => {
+            let days = i32::try_from(to_i64(days)).ok();
+            days.and_then(|days| match operator {
+                BinaryOperator::Subtract => date.get_days().checked_sub(days),
+                _ => date.get_days().checked_add(days),
+            })
+            .map(|days| DbType::Date(Date::from_days(days)))
+        }
+        _ => match result_type {
+            ColumnType::Int => integer_arithmetic(operator, to_i64(left), to_i64(right))
+                .and_then(|value| i32::try_from(value).ok())
+                .map(DbType::Int),
+            ColumnType::BigInt => {
+                integer_arithmetic(operator, to_i64(left), to_i64(right)).map(DbType::BigInt)
+            }
+            ColumnType::Float => Some(DbType::Float(float_arithmetic(
+                operator,
+                as_f64(left),
+                as_f64(right),
+            ))),
+            _ => decimal_arithmetic(operator, as_decimal(left), as_decimal(right))
+                .map(DbType::Decimal),
+        },
+    };
+    result.ok_or_else(|| EvaluationError::new(&format!("{result_type} out of range")))
+}
+
+pub(crate) fn negate(value: &DbType) -> Result<DbType, EvaluationError> {
+    let Some(value_type) = ColumnType::of(value) else {
+        return Ok(DbType::Null);
+    };
+    let result_type = negate_type(&value_type)?;
+    let result = match value {
+        DbType::Int(value) => value.checked_neg().map(DbType::Int),
+        DbType::BigInt(value) => value.checked_neg().map(DbType::BigInt),
+        DbType::Float(value) => Some(DbType::Float(-value)),
+        DbType::Decimal(value) => value
+            .get_units()
+            .checked_neg()
+            .map(|units| DbType::Decimal(Decimal::new(units, value.get_scale()))),
+        _ => unreachable!("negate_type only accepts numbers"),
+    };
+    result.ok_or_else(|| EvaluationError::new(&format!("{result_type} out of range")))
+}
+
+fn to_i64(value: &DbType) -> i64 {
+    match value {
+        DbType::Int(value) => i64::from(*value),
+        DbType::BigInt(value) => *value,
+        other => unreachable!("{} is not an integer", other.type_name()),
+    }
+}
+
+fn integer_arithmetic(operator: BinaryOperator, left: i64, right: i64) -> Option<i64> {
+    match operator {
+        BinaryOperator::Add => left.checked_add(right),
+        BinaryOperator::Subtract => left.checked_sub(right),
+        BinaryOperator::Multiply => left.checked_mul(right),
+        BinaryOperator::Divide => left.checked_div(right),
+        BinaryOperator::Modulo => left.checked_rem(right),
+        BinaryOperator::Concat => unreachable!("concatenation is not arithmetic"),
+    }
+}
+
+fn float_arithmetic(operator: BinaryOperator, left: f64, right: f64) -> f64 {
+    match operator {
+        BinaryOperator::Add => left + right,
+        BinaryOperator::Subtract => left - right,
+        BinaryOperator::Multiply => left * right,
+        BinaryOperator::Divide => left / right,
+        BinaryOperator::Modulo => left % right,
+        BinaryOperator::Concat => unreachable!("concatenation is not arithmetic"),
+    }
+}
+
+fn decimal_arithmetic(operator: BinaryOperator, left: Decimal, right: Decimal) -> Option<Decimal> {
+    match operator {
+        BinaryOperator::Add => left.checked_add(right),
+        BinaryOperator::Subtract => left.checked_sub(right),
+        BinaryOperator::Multiply => left.checked_mul(right),
+        BinaryOperator::Divide => left.checked_div(right),
+        BinaryOperator::Modulo => left.checked_rem(right),
+        BinaryOperator::Concat => unreachable!("concatenation is not arithmetic"),
+    }
+}
+
+#[derive(Debug)]
+pub struct EvaluationError {
+    pub(crate) message: String,
+}
+
+impl EvaluationError {
+    #[must_use]
+    pub fn new(message: &str) -> Self {
+        EvaluationError {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<TypeError> for EvaluationError {
+    fn from(error: TypeError) -> Self {
+        EvaluationError {
+            message: error.message,
+        }
+    }
+}
+
+impl Error for EvaluationError {}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Evaluation Error: {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Operator;
+    use crate::function_registry::FunctionRegistry;
+
+    fn evaluate(expr: &Expr, values: Vec<DbType>) -> Result<DbType, EvaluationError> {
+        let column_names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let evaluator = make_expr_evaluator(expr, &column_names)
+            .map_err(|error| EvaluationError::new(error.message()))?;
+        evaluator(&TableRow::new(values))
+    }
+
+    fn literal(value: DbType) -> Expr {
+        Expr::Literal(value)
+    }
+
+    #[test]
+    fn test_arithmetic_follows_operand_types() {
+        let sum = Expr::binary(Expr::column("a"), BinaryOperator::Add, Expr::column("b"));
+        let row = |a, b| vec![a, b, DbType::Null];
+        assert_eq!(
+            evaluate(&sum, row(DbType::Int(2), DbType::Int(3))).unwrap(),
+            DbType::Int(5)
+        );
+        assert_eq!(
+            evaluate(&sum, row(DbType::Int(2), DbType::BigInt(3))).unwrap(),
+            DbType::BigInt(5)
+        );
+        assert_eq!(
+            evaluate(&sum, row(DbType::Int(2), DbType::Float(0.5))).unwrap(),
+            DbType::Float(2.5)
+        );
+        assert_eq!(
+            evaluate(
+                &sum,
+                row(
+                    DbType::Int(2),
+                    DbType::Decimal(Decimal::parse("0.25").unwrap())
+                )
+            )
+            .unwrap(),
+            DbType::Decimal(Decimal::parse("2.25").unwrap())
+        );
+        assert_eq!(
+            evaluate(&sum, row(DbType::Int(2), DbType::Null)).unwrap(),
+            DbType::Null
+        );
+        let error = evaluate(&sum, row(DbType::Int(i32::MAX), DbType::Int(1))).unwrap_err();
+        assert_eq!(error.to_string(), "Evaluation Error: INT out of range");
+        let error = evaluate(&sum, row(DbType::Text("x".to_string()), DbType::Int(1)))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.message,
+            "operator + cannot be applied to TEXT and INT"
+        );
+    }
+
+    #[test]
+    fn test_division_truncates_and_rejects_zero() {
+        let divide = |operator| Expr::binary(Expr::column("a"), operator, Expr::column("b"));
+        let values = vec![DbType::Int(-7), DbType::Int(2), DbType::Null];
+        assert_eq!(
+            evaluate(&divide(BinaryOperator::Divide), values.clone()).unwrap(),
+            DbType::Int(-3)
+        );
+        assert_eq!(
+            evaluate(&divide(BinaryOperator::Modulo), values).unwrap(),
+            DbType::Int(-1)
+        );
+        let error = evaluate(
+            &divide(BinaryOperator::Divide),
+            vec![DbType::Float(1.0), DbType::Int(0), DbType::Null],
+        )
+        .unwrap_err();
+        assert_eq!(error.message, "division by zero");
+    }
+
+    #[test]
+    fn test_concat_and_date_arithmetic() {
+        let concat = Expr::binary(Expr::column("a"), BinaryOperator::Concat, Expr::column("b"));
+        assert_eq!(
+            evaluate(
+                &concat,
+                vec![
+                    DbType::Text("id-".to_string()),
+                    DbType::Int(7),
+                    DbType::Null
+                ]
+            )
+            .unwrap(),
+            DbType::Text("id-7".to_string())
+        );
+        let day = |text| DbType::Date(Date::parse(text).unwrap());
+        let later = Expr::binary(
+            Expr::column("a"),
+            BinaryOperator::Add,
+            literal(DbType::Int(30)),
+        );
+        assert_eq!(
+            evaluate(&later, vec![day("2024-02-15"), DbType::Null, DbType::Null]).unwrap(),
+            day("2024-03-16")
+        );
+        let between = Expr::binary(
+            Expr::column("a"),
+            BinaryOperator::Subtract,
+            Expr::column("b"),
+        );
+        assert_eq!(
+            evaluate(
+                &between,
+                vec![day("2024-03-01"), day("2024-02-01"), DbType::Null]
+            )
+            .unwrap(),
+            DbType::Int(29)
+        );
+    }
+
+    #[test]
+    fn test_case_functions_and_cast() {
+        let case = Expr::Case {
+            branches: vec![(
+                Condition::Simple {
+                    field: "a".to_string(),
+                    operator: Operator::GreaterThan,
+                    value: DbType::Int(10),
+                },
+                Expr::Function {
+                    function: FunctionRegistry::new().scalar("UPPER").unwrap(),
+                    arguments: vec![Expr::column("b")],
+                },
+            )],
+            otherwise: Some(Box::new(Expr::Cast {
+                expr: Box::new(Expr::Negate(Box::new(Expr::column("a")))),
+                target: ColumnType::Text { max_length: None },
+            })),
+        };
+        let row = |a| {
+            vec![
+                DbType::Int(a),
+                DbType::Text("big".to_string()),
+                DbType::Null,
+            ]
+        };
+        assert_eq!(
+            evaluate(&case, row(11)).unwrap(),
+            DbType::Text("BIG".to_string())
+        );
+        assert_eq!(
+            evaluate(&case, row(3)).unwrap(),
+            DbType::Text("-3".to_string())
+        );
+        assert_eq!(
+            case.to_string(),
+            "CASE WHEN a > 10 THEN UPPER(b) ELSE CAST(-a AS TEXT) END"
+        );
+        assert_eq!(
+            make_expr_evaluator(&Expr::column("missing"), &[]).err(),
+            Some(DbError::column_not_found("missing"))
+        );
+    }
+
+    #[test]
+    fn test_infer_type() {
+        let types = HashMap::from([
+            (
+                "price".to_string(),
+                ColumnType::Decimal {
+                    precision: 8,
+                    scale: 2,
+                },
+            ),
+            ("qty".to_string(), ColumnType::Int),
+            ("name".to_string(), ColumnType::Text { max_length: None }),
+        ]);
+        let total = Expr::binary(
+            Expr::column("price"),
+            BinaryOperator::Multiply,
+            Expr::column("qty"),
+        );
+        assert_eq!(
+            total.infer_type(&types).unwrap(),
+            Some(ColumnType::Decimal {
+                precision: MAX_DECIMAL_PRECISION,
+                scale: 2
+            })
+        );
+        assert_eq!(total.to_string(), "price * qty");
+        let nested = Expr::binary(total, BinaryOperator::Subtract, Expr::column("name"));
+        assert_eq!(nested.to_string(), "(price * qty) - name");
+        assert_eq!(
+            nested.infer_type(&types).unwrap_err().message,
+            "operator - cannot be applied to DECIMAL(38, 2) and TEXT"
+        );
+        let average = |column: &str| {
+            Expr::Aggregate(AggregateExpr::new(AggregateFunction::Avg, Some(column)))
+        };
+        assert_eq!(
+            average("qty").infer_type(&types).unwrap(),
+            Some(ColumnType::Float)
+        );
+        assert_eq!(
+            average("price").infer_type(&types).unwrap(),
+            Some(ColumnType::Decimal {
+                precision: MAX_DECIMAL_PRECISION,
+                scale: 6
+            })
+        );
+        let aggregate = |function: AggregateFunction, column: Option<&str>| {
+            Expr::Aggregate(AggregateExpr::new(function, column))
+        };
+        assert_eq!(
+            aggregate(AggregateFunction::Sum, Some("qty"))
+                .infer_type(&types)
+                .unwrap(),
+            Some(ColumnType::BigInt)
+        );
+        assert_eq!(
+            aggregate(AggregateFunction::Count, None)
+                .infer_type(&types)
+                .unwrap(),
+            Some(ColumnType::BigInt)
+        );
+    }
+}
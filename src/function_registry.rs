@@ -0,0 +1,71 @@
+use crate::error::DbError;
+use crate::rational_algebra::aggregation::{AggregateFunction, CustomAggregate, UserAggregate};
+use crate::scalar_function::{builtin_functions, ScalarFunction};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The functions queries can call: the built-in ones and those registered
+/// from Rust. Names are case-insensitive and unique across scalar and
+/// aggregate functions.
+pub(crate) struct FunctionRegistry {
+    scalars: HashMap<String, Rc<ScalarFunction>>,
+    aggregates: HashMap<String, UserAggregate>,
+}
+
+impl FunctionRegistry {
+    pub(crate) fn new() -> Self {
+        let scalars = builtin_functions()
+            .into_iter()
+            .map(|function| (function.get_name().to_string(), Rc::new(function)))
+            .collect();
+        FunctionRegistry {
+            scalars,
+            aggregates: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn register_scalar(&mut self, function: ScalarFunction) -> Result<(), DbError> {
+        let name = self.check_free(function.get_name())?;
+        self.scalars.insert(name, Rc::new(function));
+        Ok(())
+    }
+
+    pub(crate) fn register_aggregate<A>(&mut self, name: &str, aggregate: A) -> Result<(), DbError>
+    where
+        A: CustomAggregate + 'static,
+    {
+        let name = self.check_free(name)?;
+        self.aggregates
+            .insert(name.clone(), UserAggregate::new(&name, aggregate));
+        Ok(())
+    }
+
+    pub(crate) fn scalar(&self, name: &str) -> Result<Rc<ScalarFunction>, DbError> {
+        self.scalars
+            .get(&name.to_ascii_uppercase())
+            .cloned()
+            .ok_or_else(|| DbError::NotFound(format!("unknown function '{name}'")))
+    }
+
+    /// Looks up a built-in or registered aggregate function.
+    pub(crate) fn aggregate(&self, name: &str) -> Option<AggregateFunction> {
+        AggregateFunction::from_name(name).or_else(|| {
+            self.aggregates
+                .get(&name.to_ascii_uppercase())
+                .cloned()
+                .map(AggregateFunction::Custom)
+        })
+    }
+
+    /// Returns the normalized name when no function is known by it yet.
+    fn check_free(&self, name: &str) -> Result<String, DbError> {
+        let normalized = name.to_ascii_uppercase();
+        if self.scalars.contains_key(&normalized) || self.aggregate(&normalized).is_some() {
+            Err(DbError::Schema(format!(
+                "function '{normalized}' already exists"
+            )))
+        } else {
+            Ok(normalized)
+        }
+    }
+}
@@ -4,18 +4,48 @@
 use crate::table_row::TableRow;
 
 mod bloom_filter;
+mod coercion;
 mod condition;
+mod counting_bloom_filter;
+mod database;
+mod date_time;
 mod db_type;
+mod decimal;
+mod error;
+mod expression;
+mod function_registry;
 mod helper_function;
+mod like_pattern;
 mod metadata;
 mod query_result;
 mod rational_algebra;
+mod scalar_function;
 mod schema_struct;
+mod secondary_index;
+mod sql;
 mod table_row;
 mod table_struct;
 mod test_to_rational_algebra_operation;
 
+pub use crate::database::{Database, ExecutionResult};
+pub use crate::date_time::{Date, Timestamp};
+pub use crate::db_type::DbType;
+pub use crate::decimal::Decimal;
+pub use crate::error::DbError;
+pub use crate::expression::EvaluationError;
+pub use crate::query_result::QueryResult;
+pub use crate::rational_algebra::aggregation::CustomAggregate;
+pub use crate::scalar_function::{ParameterKind, ScalarFunction, Signature};
+pub use crate::schema_struct::ColumnType;
+pub use crate::sql::parser::ParseError;
+pub use crate::sql::SqlError;
+
 pub(crate) trait ManipulateTable {
     fn get_column_names(&self) -> &Vec<String>;
     fn get_data(&self) -> &Vec<TableRow>;
+    /// The declared type of each column, or `None` where the source has no
+    /// schema, as for query results.
+    fn get_column_types(&self) -> Vec<Option<ColumnType>> {
+        vec![None; self.get_column_names().len()]
+    }
 }
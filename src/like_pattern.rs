@@ -0,0 +1,150 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Literal(char),
+    AnyCharacter,
+    AnyString,
+}
+
+/// A compiled LIKE pattern: `%` matches any run of characters, `_` matches a
+/// single character and a backslash makes the next character literal.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LikePattern {
+    source: String,
+    tokens: Vec<Token>,
+    case_insensitive: bool,
+}
+
+impl LikePattern {
+    pub(crate) fn new(source: &str, case_insensitive: bool) -> Self {
+        let mut tokens = Vec::new();
+        let mut characters = source.chars();
+        while let Some(character) = characters.next() {
+            let token = match character {
+                '%' if tokens.last() == Some(&Token::AnyString) => continue,
+                '%' => Token::AnyString,
+                '_' => Token::AnyCharacter,
+                '\\' => Token::Literal(characters.next().unwrap_or('\\')),
+                character => Token::Literal(character),
+            };
+            tokens.push(token);
+        }
+        if case_insensitive {
+            for token in &mut tokens {
+                if let Token::Literal(character) = token {
+                    *character = fold_case(*character);
+                }
+            }
+        }
+        LikePattern {
+            source: source.to_string(),
+            tokens,
+            case_insensitive,
+        }
+    }
+
+    pub(crate) fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// The literal text every match starts with, if the pattern is anchored
+    /// by one.
+    pub(crate) fn get_prefix(&self) -> String {
+        self.tokens
+            .iter()
+            .map_while(|token| match token {
+                Token::Literal(character) => Some(*character),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = if self.case_insensitive {
+            text.chars().map(fold_case).collect()
+        } else {
+            text.chars().collect()
+        };
+        // Greedy matching that backtracks to the most recent `%`, which is
+        // enough because earlier `%` tokens can never need to match more.
+        let (mut token, mut position) = (0, 0);
+        let mut backtrack: Option<(usize, usize)> = None;
+        while position < text.len() {
+            match self.tokens.get(token) {
+                Some(Token::AnyString) => {
+                    backtrack = Some((token, position));
+                    token += 1;
+                    continue;
+                }
+                Some(Token::AnyCharacter) => {
+                    token += 1;
+                    position += 1;
+                    continue;
+                }
+                Some(Token::Literal(character)) if *character == text[position] => {
+                    token += 1;
+                    position += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star, start)) => {
+                    token = star + 1;
+                    position = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            }
+        }
+        self.tokens[token..]
+            .iter()
+            .all(|token| *token == Token::AnyString)
+    }
+}
+
+fn fold_case(character: char) -> char {
+    character.to_lowercase().next().unwrap_or(character)
+}
+
+impl fmt::Display for LikePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keyword = if self.case_insensitive {
+            "ILIKE"
+        } else {
+            "LIKE"
+        };
+        write!(f, "{keyword} '{}'", self.source.replace('\'', "''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_wildcards() {
+        let pattern = LikePattern::new("A%e_", false);
+        assert!(pattern.matches("Alice!"));
+        assert!(pattern.matches("Ae?"));
+        assert!(!pattern.matches("Alice"));
+        assert!(!pattern.matches("alice!"));
+        assert!(LikePattern::new("%b%b%", false).matches("abcbd"));
+        assert!(!LikePattern::new("%b%b%", false).matches("abcd"));
+        assert!(LikePattern::new("", false).matches(""));
+        assert!(LikePattern::new("%", false).matches(""));
+        assert!(!LikePattern::new("_", false).matches(""));
+    }
+
+    #[test]
+    fn test_like_escape_and_case() {
+        let pattern = LikePattern::new("100\\%", false);
+        assert!(pattern.matches("100%"));
+        assert!(!pattern.matches("1000"));
+        assert_eq!(pattern.get_prefix(), "100%");
+        let pattern = LikePattern::new("al%", true);
+        assert!(pattern.matches("ALICE"));
+        assert_eq!(pattern.to_string(), "ILIKE 'al%'");
+    }
+}
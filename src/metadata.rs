@@ -1,21 +1,84 @@
-use crate::bloom_filter::BloomFilter;
+use crate::bloom_filter::{BloomFilter, MembershipFilter};
+use crate::coercion::normalize_key;
+use crate::counting_bloom_filter::CountingBloomFilter;
 use crate::db_type::DbType;
+use crate::error::DbError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The layout of serialized `MetaData`. The filter inside carries its own
+/// version on top of this one.
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// The membership filter that guards the primary-key index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum FilterKind {
+    /// One bit per slot. Deleted keys stay in the filter until it is rebuilt.
+    #[default]
+    Bloom,
+    /// A counter per slot instead of a bit, in exchange for forgetting
+    /// deleted keys.
+    CountingBloom,
+}
+
+impl FilterKind {
+    fn build(self, n: usize, error_percent: f32) -> Box<dyn MembershipFilter<Vec<DbType>>> {
+        match self {
+            FilterKind::Bloom => Box::new(BloomFilter::new(n, error_percent)),
+            FilterKind::CountingBloom => Box::new(CountingBloomFilter::new(n, error_percent)),
+        }
+    }
+
+    fn load(self, bytes: &[u8]) -> Result<Box<dyn MembershipFilter<Vec<DbType>>>, DbError> {
+        Ok(match self {
+            FilterKind::Bloom => Box::new(BloomFilter::from_bytes(bytes)?),
+            FilterKind::CountingBloom => Box::new(CountingBloomFilter::from_bytes(bytes)?),
+        })
+    }
+}
+
+/// The fields of serialized `MetaData`, with the filter in its own encoding.
+#[derive(Serialize, Deserialize)]
+struct MetaDataData {
+    version: u32,
+    primary_key: Vec<usize>,
+    filter_kind: FilterKind,
+    pk_filter: Vec<u8>,
+    pk_index: HashMap<Vec<DbType>, usize>,
+    table_capacity: usize,
+    error_percent: f32,
+}
 
 pub(crate) struct MetaData {
     primary_key: Vec<usize>,
-    pk_filter: BloomFilter<Vec<DbType>>,
+    filter_kind: FilterKind,
+    pk_filter: Box<dyn MembershipFilter<Vec<DbType>>>,
+    pk_index: HashMap<Vec<DbType>, usize>,
     table_capacity: usize,
+    error_percent: f32,
 }
 impl MetaData {
     pub(crate) fn new(table_capacity: usize, error_percent: f32) -> Self {
         MetaData {
             primary_key: vec![],
-            pk_filter: BloomFilter::new(table_capacity, error_percent),
+            filter_kind: FilterKind::default(),
+            pk_filter: FilterKind::default().build(table_capacity, error_percent),
+            pk_index: HashMap::new(),
             table_capacity,
+            error_percent,
         }
     }
+    /// Guards the primary key with the given kind of filter. Keys that were
+    /// already added are not carried over, so call this before inserting.
+    #[must_use]
+    #[allow(dead_code)]
+    pub(crate) fn with_filter(mut self, filter_kind: FilterKind) -> Self {
+        self.filter_kind = filter_kind;
+        self.pk_filter = filter_kind.build(self.table_capacity, self.error_percent);
+        self
+    }
     pub(crate) fn set_pk(&mut self, indexes: &[usize]) {
-        self.primary_key = indexes.to_owned();
+        indexes.clone_into(&mut self.primary_key);
     }
     pub(crate) fn get_pk(&self) -> &Vec<usize> {
         &self.primary_key
@@ -23,7 +86,161 @@ impl MetaData {
     pub(crate) fn get_table_capacity(&self) -> usize {
         self.table_capacity
     }
-    pub(crate) fn get_filter(&mut self) -> &mut BloomFilter<Vec<DbType>> {
-        &mut self.pk_filter
+    #[allow(dead_code)]
+    pub(crate) fn get_filter_kind(&self) -> FilterKind {
+        self.filter_kind
+    }
+    #[allow(dead_code)]
+    pub(crate) fn get_filter(&mut self) -> &mut dyn MembershipFilter<Vec<DbType>> {
+        self.pk_filter.as_mut()
+    }
+    /// The position of the row with this primary key. The filter answers
+    /// most misses without touching the index, which then rules out the
+    /// filter's false positives.
+    pub(crate) fn find_key(&self, key: &Vec<DbType>) -> Option<usize> {
+        if self.pk_filter.check(&filter_key(key)) {
+            self.pk_index.get(key).copied()
+        } else {
+            None
+        }
+    }
+    #[allow(dead_code)]
+    pub(crate) fn contains_key(&self, key: &Vec<DbType>) -> bool {
+        self.find_key(key).is_some()
+    }
+    /// Records that the row at `position` holds the primary key `key`.
+    pub(crate) fn add_key(&mut self, key: Vec<DbType>, position: usize) {
+        self.pk_filter.add(&filter_key(&key));
+        self.pk_index.insert(key, position);
+    }
+    /// Forgets a key. A plain Bloom filter keeps reporting it, which only
+    /// costs an index probe when the key is looked up again.
+    pub(crate) fn remove_key(&mut self, key: &Vec<DbType>) {
+        if self.pk_index.remove(key).is_some() {
+            self.pk_filter.remove(&filter_key(key));
+        }
+    }
+    /// Serializes the primary key, its index and its filter, so that they
+    /// can be saved alongside the table's rows and loaded without
+    /// re-adding every key. Saving the rows and schema is up to the caller.
+    #[allow(dead_code)]
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, DbError> {
+        let data = MetaDataData {
+            version: FORMAT_VERSION,
+            primary_key: self.primary_key.clone(),
+            filter_kind: self.filter_kind,
+            pk_filter: self.pk_filter.to_bytes()?,
+            pk_index: self.pk_index.clone(),
+            table_capacity: self.table_capacity,
+            error_percent: self.error_percent,
+        };
+        bincode::serialize(&data).map_err(DbError::from)
+    }
+    /// Loads metadata saved by `to_bytes`, rejecting other format versions
+    /// of it or of its filter.
+    #[allow(dead_code)]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, DbError> {
+        let data: MetaDataData = bincode::deserialize(bytes)?;
+        if data.version != FORMAT_VERSION {
+            return Err(DbError::Parse(format!(
+                "metadata format version {} is not supported, expected {FORMAT_VERSION}",
+                data.version
+            )));
+        }
+        Ok(MetaData {
+            primary_key: data.primary_key,
+            filter_kind: data.filter_kind,
+            pk_filter: data.filter_kind.load(&data.pk_filter)?,
+            pk_index: data.pk_index,
+            table_capacity: data.table_capacity,
+            error_percent: data.error_percent,
+        })
+    }
+    /// Forgets every key, resetting the filter along with the index.
+    pub(crate) fn clear_keys(&mut self) {
+        self.pk_index.clear();
+        self.pk_filter = self
+            .filter_kind
+            .build(self.table_capacity, self.error_percent);
+    }
+}
+
+/// The form of a key the filter hashes. The filter hashes serialized bytes,
+/// which tell `3.00` from `3`, so keys that are equal must be brought to
+/// one form first.
+fn filter_key(key: &[DbType]) -> Vec<DbType> {
+    key.iter().map(normalize_key).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::Decimal;
+
+    #[test]
+    fn test_filter_false_positive_is_not_a_duplicate() {
+        // A single bit: once anything is added, the filter reports every key.
+        let mut meta_data = MetaData::new(1, 0.99);
+        meta_data.add_key(vec![DbType::Int(1)], 0);
+        assert!(meta_data.get_filter().check(&vec![DbType::Int(2)]));
+        assert!(meta_data.contains_key(&vec![DbType::Int(1)]));
+        assert!(!meta_data.contains_key(&vec![DbType::Int(2)]));
+    }
+
+    #[test]
+    fn test_counting_filter_forgets_removed_keys() {
+        let key = vec![DbType::Int(1)];
+        let mut bloom = MetaData::new(100, 0.01);
+        let mut counting = MetaData::new(100, 0.01).with_filter(FilterKind::CountingBloom);
+        assert_eq!(counting.get_filter_kind(), FilterKind::CountingBloom);
+        for meta_data in [&mut bloom, &mut counting] {
+            meta_data.add_key(key.clone(), 0);
+            meta_data.remove_key(&key);
+            assert!(!meta_data.contains_key(&key));
+            // Removing a key that is not there leaves the filter alone.
+            meta_data.remove_key(&key);
+        }
+        assert!(bloom.get_filter().check(&filter_key(&key)));
+        assert!(!counting.get_filter().check(&filter_key(&key)));
+        counting.add_key(key.clone(), 0);
+        assert!(counting.contains_key(&key));
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        for filter_kind in [FilterKind::Bloom, FilterKind::CountingBloom] {
+            let mut meta_data = MetaData::new(100, 0.01).with_filter(filter_kind);
+            meta_data.set_pk(&[0]);
+            for (position, id) in (0..20).enumerate() {
+                meta_data.add_key(vec![DbType::Int(id)], position);
+            }
+            meta_data.remove_key(&vec![DbType::Int(3)]);
+            let mut loaded = MetaData::from_bytes(&meta_data.to_bytes().unwrap()).unwrap();
+            assert_eq!(loaded.get_pk(), &vec![0]);
+            assert_eq!(loaded.get_filter_kind(), filter_kind);
+            assert_eq!(loaded.find_key(&vec![DbType::Int(7)]), Some(7));
+            assert!(!loaded.contains_key(&vec![DbType::Int(3)]));
+            assert_eq!(
+                loaded.get_filter().check(&filter_key(&[DbType::Int(3)])),
+                filter_kind == FilterKind::Bloom
+            );
+        }
+        let bytes = MetaData::new(100, 0.01).to_bytes().unwrap();
+        assert!(matches!(
+            MetaData::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DbError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_keys_of_different_scale_pass_the_filter() {
+        let decimal = |text: &str| DbType::Decimal(Decimal::parse(text).unwrap());
+        for filter_kind in [FilterKind::Bloom, FilterKind::CountingBloom] {
+            let mut meta_data = MetaData::new(100, 0.01).with_filter(filter_kind);
+            meta_data.add_key(vec![decimal("3.00")], 0);
+            assert_eq!(meta_data.find_key(&vec![decimal("3")]), Some(0));
+            meta_data.remove_key(&vec![decimal("3.0")]);
+            assert_eq!(meta_data.find_key(&vec![decimal("3.00")]), None);
+        }
     }
 }
@@ -1,7 +1,10 @@
+use crate::db_type::DbType;
+use crate::error::DbError;
 use crate::table_row::TableRow;
 use crate::ManipulateTable;
 
-pub(crate) struct QueryResult {
+/// The rows a query returns, under the names of its output columns.
+pub struct QueryResult {
     data: Vec<TableRow>,
     column_names: Vec<String>,
 }
@@ -12,6 +15,31 @@ impl QueryResult {
             column_names: column,
         }
     }
+    pub(crate) fn into_data(self) -> Vec<TableRow> {
+        self.data
+    }
+    pub(crate) fn rename_columns(&mut self, column_names: Vec<String>) -> Result<(), DbError> {
+        if self.column_names.len() != column_names.len() {
+            return Err(DbError::Schema(format!(
+                "expected {} column names, got {}",
+                self.column_names.len(),
+                column_names.len()
+            )));
+        }
+        self.column_names = column_names;
+        Ok(())
+    }
+
+    /// The names of the output columns, in order.
+    #[must_use]
+    pub fn column_names(&self) -> &[String] {
+        &self.column_names
+    }
+
+    /// The values of each row, in the order of `column_names`.
+    pub fn rows(&self) -> impl Iterator<Item = &[DbType]> {
+        self.data.iter().map(|row| row.get_values().as_slice())
+    }
 }
 
 impl ManipulateTable for QueryResult {
@@ -23,3 +51,30 @@ impl ManipulateTable for QueryResult {
         &self.data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_columns() {
+        let mut result = QueryResult::new(
+            vec![TableRow::new(vec![DbType::Int(1), DbType::Null])],
+            vec!["id".to_string(), "name".to_string()],
+        );
+        assert_eq!(
+            result.rename_columns(vec!["id".to_string()]),
+            Err(DbError::Schema(
+                "expected 2 column names, got 1".to_string()
+            ))
+        );
+        result
+            .rename_columns(vec!["key".to_string(), "label".to_string()])
+            .unwrap();
+        assert_eq!(result.column_names(), ["key", "label"]);
+        assert_eq!(
+            result.rows().collect::<Vec<_>>(),
+            vec![[DbType::Int(1), DbType::Null].as_slice()]
+        );
+    }
+}
@@ -1,18 +1,1010 @@
-fn sum() {
-    todo!()
+use crate::condition::Condition;
+use crate::db_type::DbType;
+use crate::decimal::Decimal;
+use crate::error::DbError;
+use crate::query_result::QueryResult;
+use crate::rational_algebra::executor::{Executor, TableScan};
+use crate::rational_algebra::helper_functions::resolve_column_index;
+use crate::rational_algebra::selection::make_condition_evaluator;
+use crate::schema_struct::ColumnType;
+use crate::table_row::TableRow;
+use crate::ManipulateTable;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum AggregateFunction {
+    Sum,
+    Min,
+    Max,
+    Count,
+    Avg,
+    Custom(UserAggregate),
 }
-fn min() {
-    todo!()
+
+impl AggregateFunction {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "SUM" => Some(AggregateFunction::Sum),
+            "MIN" => Some(AggregateFunction::Min),
+            "MAX" => Some(AggregateFunction::Max),
+            "COUNT" => Some(AggregateFunction::Count),
+            "AVG" => Some(AggregateFunction::Avg),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Custom(aggregate) => &aggregate.name,
+        }
+    }
 }
-fn max() {
-    todo!()
+
+/// An aggregate defined in Rust. Each group starts from `init`, every
+/// non-NULL argument is folded in with `accumulate` and `finalize` turns the
+/// state into the group's value. `merge` combines two partial states of the
+/// same group, as if their rows had been accumulated into one.
+pub trait CustomAggregate {
+    type State;
+
+    fn init(&self) -> Self::State;
+
+    /// # Errors
+    ///
+    /// An error fails the query, for example on a value of the wrong type.
+    fn accumulate(&self, state: &mut Self::State, value: &DbType) -> Result<(), DbError>;
+
+    /// # Errors
+    ///
+    /// An error fails the query, for example when the result overflows.
+    fn merge(&self, state: &mut Self::State, other: Self::State) -> Result<(), DbError>;
+
+    /// # Errors
+    ///
+    /// An error fails the query.
+    fn finalize(&self, state: Self::State) -> Result<DbType, DbError>;
+
+    /// The type of the result for an argument column of the given type, if
+    /// it is known before the query runs.
+    fn return_type(&self, _argument: Option<&ColumnType>) -> Option<ColumnType> {
+        None
+    }
 }
-fn count() {
-    todo!()
+
+/// `CustomAggregate` with the state type erased, so aggregates with
+/// different states can be stored side by side.
+trait ErasedAggregate {
+    fn init_state(&self) -> Box<dyn Any>;
+    fn accumulate_state(&self, state: &mut dyn Any, value: &DbType) -> Result<(), DbError>;
+    fn merge_states(&self, state: &mut dyn Any, other: Box<dyn Any>) -> Result<(), DbError>;
+    fn finalize_state(&self, state: Box<dyn Any>) -> Result<DbType, DbError>;
+    fn result_type(&self, argument: Option<&ColumnType>) -> Option<ColumnType>;
 }
-fn avg() {
-    todo!()
+
+impl<A> ErasedAggregate for A
+where
+    A: CustomAggregate,
+    A::State: 'static,
+{
+    fn init_state(&self) -> Box<dyn Any> {
+        Box::new(self.init())
+    }
+
+    fn accumulate_state(&self, state: &mut dyn Any, value: &DbType) -> Result<(), DbError> {
+        self.accumulate(downcast_mut(state), value)
+    }
+
+    fn merge_states(&self, state: &mut dyn Any, other: Box<dyn Any>) -> Result<(), DbError> {
+        let other = *other
+            .downcast::<A::State>()
+            .expect("states come from the same aggregate");
+        self.merge(downcast_mut(state), other)
+    }
+
+    fn finalize_state(&self, state: Box<dyn Any>) -> Result<DbType, DbError> {
+        let state = *state
+            .downcast::<A::State>()
+            .expect("states come from the same aggregate");
+        self.finalize(state)
+    }
+
+    fn result_type(&self, argument: Option<&ColumnType>) -> Option<ColumnType> {
+        self.return_type(argument)
+    }
 }
-fn aggregation() {
-    todo!()
+
+fn downcast_mut<S: 'static>(state: &mut dyn Any) -> &mut S {
+    state
+        .downcast_mut()
+        .expect("states come from the same aggregate")
+}
+
+/// A registered `CustomAggregate` under its name. Two are equal when their
+/// names are.
+#[derive(Clone)]
+pub(crate) struct UserAggregate {
+    name: String,
+    implementation: Rc<dyn ErasedAggregate>,
+}
+
+impl UserAggregate {
+    pub(crate) fn new<A>(name: &str, aggregate: A) -> Self
+    where
+        A: CustomAggregate + 'static,
+    {
+        UserAggregate {
+            name: name.to_ascii_uppercase(),
+            implementation: Rc::new(aggregate),
+        }
+    }
+
+    pub(crate) fn return_type(&self, argument: Option<&ColumnType>) -> Option<ColumnType> {
+        self.implementation.result_type(argument)
+    }
+}
+
+impl PartialEq for UserAggregate {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for UserAggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("UserAggregate").field(&self.name).finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AggregateExpr {
+    pub(crate) function: AggregateFunction,
+    pub(crate) column: Option<String>,
+    pub(crate) alias: Option<String>,
+}
+
+impl AggregateExpr {
+    pub(crate) fn new(function: AggregateFunction, column: Option<&str>) -> Self {
+        AggregateExpr {
+            function,
+            column: column.map(ToString::to_string),
+            alias: None,
+        }
+    }
+
+    pub(crate) fn with_alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    pub(crate) fn get_output_name(&self) -> String {
+        match &self.alias {
+            Some(alias) => alias.clone(),
+            None => self.get_canonical_name(),
+        }
+    }
+
+    pub(crate) fn get_canonical_name(&self) -> String {
+        format!(
+            "{}({})",
+            self.function.name(),
+            self.column.as_deref().unwrap_or("*")
+        )
+    }
+
+    fn without_alias(&self) -> AggregateExpr {
+        AggregateExpr::new(self.function.clone(), self.column.as_deref())
+    }
+}
+
+#[allow(dead_code)]
+pub(crate) fn aggregation<T>(
+    table: &T,
+    group_by: &[String],
+    aggregates: &[AggregateExpr],
+) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+{
+    aggregate(&mut TableScan::new(table), group_by, aggregates, None)
+}
+
+#[allow(dead_code)]
+pub(crate) fn aggregation_having<T>(
+    table: &T,
+    group_by: &[String],
+    aggregates: &[AggregateExpr],
+    having: &Condition,
+) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+{
+    aggregate(
+        &mut TableScan::new(table),
+        group_by,
+        aggregates,
+        Some(having),
+    )
+}
+
+pub(crate) fn aggregate<E>(
+    input: &mut E,
+    group_by: &[String],
+    aggregates: &[AggregateExpr],
+    having: Option<&Condition>,
+) -> Result<QueryResult, DbError>
+where
+    E: Executor + ?Sized,
+{
+    let having_aggregates = having.map(Condition::get_aggregates).unwrap_or_default();
+    let mut computed: Vec<AggregateExpr> = Vec::new();
+    for aggregate in aggregates.iter().chain(having_aggregates) {
+        let canonical = aggregate.without_alias();
+        if !computed.contains(&canonical) {
+            computed.push(canonical);
+        }
+    }
+    let grouped = compute_groups(input, group_by, &computed)?;
+    let group_width = group_by.len();
+    let grouped_names = grouped.get_column_names().clone();
+    let mut output_names = grouped_names[..group_width].to_vec();
+    output_names.extend(aggregates.iter().map(AggregateExpr::get_output_name));
+
+    let mut rows = grouped.into_data();
+    if let Some(condition) = having {
+        if let Some(field) = condition
+            .get_fields()
+            .into_iter()
+            .find(|field| resolve_column_index(&grouped_names, field).is_none())
+        {
+            return Err(DbError::Query(format!(
+                "column '{field}' in HAVING is neither grouped nor aggregated"
+            )));
+        }
+        let evaluator = make_condition_evaluator(condition, &grouped_names)?;
+        let mut kept = Vec::with_capacity(rows.len());
+        for row in rows {
+            if evaluator(&row)? {
+                kept.push(row);
+            }
+        }
+        rows = kept;
+    }
+    let positions: Vec<usize> = aggregates
+        .iter()
+        .filter_map(|aggregate| {
+            let canonical = aggregate.without_alias();
+            computed.iter().position(|c| *c == canonical)
+        })
+        .map(|position| group_width + position)
+        .collect();
+    let data = rows
+        .into_iter()
+        .map(|row| {
+            let values = row.get_values();
+            let mut output = values[..group_width].to_vec();
+            output.extend(positions.iter().map(|&position| values[position].clone()));
+            TableRow::new(output)
+        })
+        .collect();
+    Ok(QueryResult::new(data, output_names))
+}
+
+fn compute_groups<E>(
+    input: &mut E,
+    group_by: &[String],
+    aggregates: &[AggregateExpr],
+) -> Result<QueryResult, DbError>
+where
+    E: Executor + ?Sized,
+{
+    let column_names = input.get_column_names().clone();
+    let group_indexes = group_by
+        .iter()
+        .map(|name| find_column(&column_names, name))
+        .collect::<Result<Vec<usize>, DbError>>()?;
+    let argument_indexes = aggregates
+        .iter()
+        .map(|aggregate| match (&aggregate.column, &aggregate.function) {
+            (Some(name), _) => find_column(&column_names, name).map(Some),
+            (None, AggregateFunction::Count) => Ok(None),
+            (None, function) => Err(DbError::Query(format!(
+                "{} requires a column argument",
+                function.name()
+            ))),
+        })
+        .collect::<Result<Vec<Option<usize>>, DbError>>()?;
+
+    let mut totals = Groups::default();
+    let mut batch = Groups::default();
+    let mut batch_rows = 0;
+    input.open()?;
+    while let Some(row) = input.next()? {
+        let values = row.get_values();
+        let key: Vec<DbType> = group_indexes
+            .iter()
+            .map(|&index| values[index].clone())
+            .collect();
+        let accumulators = batch.accumulators(key, aggregates);
+        for (accumulator, argument) in accumulators.iter_mut().zip(&argument_indexes) {
+            match argument {
+                Some(index) => accumulator.accumulate(&values[*index])?,
+                None => accumulator.accumulate_row(),
+            }
+        }
+        batch_rows += 1;
+        if batch_rows == BATCH_ROWS {
+            totals.merge(std::mem::take(&mut batch), aggregates)?;
+            batch_rows = 0;
+        }
+    }
+    input.close();
+    totals.merge(batch, aggregates)?;
+    let mut groups = totals.groups;
+    if groups.is_empty() && group_indexes.is_empty() {
+        groups.push((Vec::new(), new_accumulators(aggregates)));
+    }
+
+    let data = groups
+        .into_iter()
+        .map(|(mut key, accumulators)| {
+            for accumulator in accumulators {
+                key.push(accumulator.finish()?);
+            }
+            Ok(TableRow::new(key))
+        })
+        .collect::<Result<Vec<TableRow>, DbError>>()?;
+    let mut output_names: Vec<String> = group_indexes
+        .iter()
+        .map(|&index| column_names[index].clone())
+        .collect();
+    output_names.extend(aggregates.iter().map(AggregateExpr::get_output_name));
+    Ok(QueryResult::new(data, output_names))
+}
+
+/// The number of rows aggregated into partial states before they are merged
+/// into the totals, which keeps the groups of one batch small.
+const BATCH_ROWS: usize = 1024;
+
+/// Groups in the order they were first seen, each with one accumulator per
+/// aggregate.
+#[derive(Default)]
+struct Groups {
+    positions: HashMap<Vec<DbType>, usize>,
+    groups: Vec<(Vec<DbType>, Vec<Accumulator>)>,
+}
+
+impl Groups {
+    fn accumulators(
+        &mut self,
+        key: Vec<DbType>,
+        aggregates: &[AggregateExpr],
+    ) -> &mut [Accumulator] {
+        let position = match self.positions.get(&key) {
+            Some(&position) => position,
+            None => self.insert(key, new_accumulators(aggregates)),
+        };
+        &mut self.groups[position].1
+    }
+
+    fn insert(&mut self, key: Vec<DbType>, accumulators: Vec<Accumulator>) -> usize {
+        self.positions.insert(key.clone(), self.groups.len());
+        self.groups.push((key, accumulators));
+        self.groups.len() - 1
+    }
+
+    /// Merges the partial states of `other` into the groups they belong to.
+    fn merge(&mut self, other: Groups, aggregates: &[AggregateExpr]) -> Result<(), DbError> {
+        for (key, partials) in other.groups {
+            if self.positions.contains_key(&key) {
+                let accumulators = self.accumulators(key, aggregates);
+                for (accumulator, partial) in accumulators.iter_mut().zip(partials) {
+                    accumulator.merge(partial)?;
+                }
+            } else {
+                self.insert(key, partials);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find_column(column_names: &[String], name: &str) -> Result<usize, DbError> {
+    resolve_column_index(column_names, name).ok_or_else(|| DbError::column_not_found(name))
+}
+
+fn new_accumulators(aggregates: &[AggregateExpr]) -> Vec<Accumulator> {
+    aggregates
+        .iter()
+        .map(|aggregate| Accumulator::new(&aggregate.function))
+        .collect()
+}
+
+enum Accumulator {
+    Sum(Option<DbType>),
+    Min(Option<DbType>),
+    Max(Option<DbType>),
+    Count(i64),
+    Avg {
+        total: Option<DbType>,
+        count: i64,
+    },
+    Custom {
+        aggregate: Rc<dyn ErasedAggregate>,
+        state: Box<dyn Any>,
+    },
+}
+
+impl Accumulator {
+    fn new(function: &AggregateFunction) -> Self {
+        match function {
+            AggregateFunction::Sum => Accumulator::Sum(None),
+            AggregateFunction::Min => Accumulator::Min(None),
+            AggregateFunction::Max => Accumulator::Max(None),
+            AggregateFunction::Count => Accumulator::Count(0),
+            AggregateFunction::Avg => Accumulator::Avg {
+                total: None,
+                count: 0,
+            },
+            AggregateFunction::Custom(user) => Accumulator::Custom {
+                aggregate: Rc::clone(&user.implementation),
+                state: user.implementation.init_state(),
+            },
+        }
+    }
+
+    fn accumulate_row(&mut self) {
+        if let Accumulator::Count(count) = self {
+            *count += 1;
+        }
+    }
+
+    fn accumulate(&mut self, value: &DbType) -> Result<(), DbError> {
+        if *value == DbType::Null {
+            return Ok(());
+        }
+        match self {
+            Accumulator::Sum(total) => {
+                *total = Some(sum(total.take(), &widen(value), "SUM")?);
+            }
+            Accumulator::Min(current) => *current = Some(min(current.take(), value)),
+            Accumulator::Max(current) => *current = Some(max(current.take(), value)),
+            Accumulator::Count(count) => *count += 1,
+            Accumulator::Avg { total, count } => {
+                *total = Some(sum(total.take(), &widen(value), "AVG")?);
+                *count += 1;
+            }
+            Accumulator::Custom { aggregate, state } => {
+                aggregate.accumulate_state(state.as_mut(), value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Combines the partial state of the same aggregate over other rows.
+    fn merge(&mut self, other: Accumulator) -> Result<(), DbError> {
+        match (self, other) {
+            (Accumulator::Sum(total), Accumulator::Sum(Some(other))) => {
+                *total = Some(sum(total.take(), &other, "SUM")?);
+            }
+            (Accumulator::Min(current), Accumulator::Min(Some(other))) => {
+                *current = Some(min(current.take(), &other));
+            }
+            (Accumulator::Max(current), Accumulator::Max(Some(other))) => {
+                *current = Some(max(current.take(), &other));
+            }
+            (Accumulator::Count(count), Accumulator::Count(other)) => *count += other,
+            (
+                Accumulator::Avg { total, count },
+                Accumulator::Avg {
+                    total: Some(other_total),
+                    count: other_count,
+                },
+            ) => {
+                *total = Some(sum(total.take(), &other_total, "AVG")?);
+                *count += other_count;
+            }
+            (
+                Accumulator::Custom { aggregate, state },
+                Accumulator::Custom { state: other, .. },
+            ) => aggregate.merge_states(state.as_mut(), other)?,
+            // A partial that saw no values leaves the state as it is.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn finish(self) -> Result<DbType, DbError> {
+        match self {
+            Accumulator::Sum(value) | Accumulator::Min(value) | Accumulator::Max(value) => {
+                Ok(value.unwrap_or(DbType::Null))
+            }
+            Accumulator::Count(count) => Ok(DbType::BigInt(count)),
+            Accumulator::Custom { aggregate, state } => aggregate.finalize_state(state),
+            Accumulator::Avg { count: 0, .. } | Accumulator::Avg { total: None, .. } => {
+                Ok(DbType::Null)
+            }
+            Accumulator::Avg {
+                total: Some(DbType::Decimal(total)),
+                count,
+            } => total
+                .checked_div(Decimal::from(count))
+                .map(DbType::Decimal)
+                .ok_or_else(|| DbError::Type("decimal overflow in AVG".to_string())),
+            Accumulator::Avg {
+                total: Some(total),
+                count,
+            } => Ok(DbType::Float(avg(as_float(&total, "AVG")?, count as f64))),
+        }
+    }
+}
+
+/// Sums INT values as BIGINT, as SQL does, so that SUM and AVG of an INT
+/// column only overflow past the BIGINT range.
+fn widen(value: &DbType) -> DbType {
+    match value {
+        DbType::Int(value) => DbType::BigInt(i64::from(*value)),
+        other => other.clone(),
+    }
+}
+
+/// Adds two numeric values in the narrowest type that holds both: integers
+/// give BIGINT, integers and DECIMAL give an exact DECIMAL, and anything
+/// involving FLOAT gives FLOAT.
+fn sum(total: Option<DbType>, value: &DbType, function: &str) -> Result<DbType, DbError> {
+    let Some(total) = total else {
+        return if value.is_numeric() {
+            Ok(value.clone())
+        } else {
+            Err(not_numeric(function, value))
+        };
+    };
+    if let (Some(a), Some(b)) = (integer_value(&total), integer_value(value)) {
+        return a
+            .checked_add(b)
+            .map(DbType::BigInt)
+            .ok_or_else(|| DbError::Type(format!("integer overflow in {function}")));
+    }
+    if let (Some(a), Some(b)) = (decimal_value(&total), decimal_value(value)) {
+        return a
+            .checked_add(b)
+            .map(DbType::Decimal)
+            .ok_or_else(|| DbError::Type(format!("decimal overflow in {function}")));
+    }
+    Ok(DbType::Float(
+        as_float(&total, function)? + as_float(value, function)?,
+    ))
+}
+
+fn integer_value(value: &DbType) -> Option<i64> {
+    match value {
+        DbType::Int(value) => Some(i64::from(*value)),
+        DbType::BigInt(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn decimal_value(value: &DbType) -> Option<Decimal> {
+    match value {
+        DbType::Decimal(value) => Some(*value),
+        other => integer_value(other).map(Decimal::from),
+    }
+}
+
+fn min(current: Option<DbType>, value: &DbType) -> DbType {
+    match current {
+        Some(current) if current <= *value => current,
+        _ => value.clone(),
+    }
+}
+
+fn max(current: Option<DbType>, value: &DbType) -> DbType {
+    match current {
+        Some(current) if current >= *value => current,
+        _ => value.clone(),
+    }
+}
+
+fn avg(total: f64, count: f64) -> f64 {
+    total / count
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn as_float(value: &DbType, function: &str) -> Result<f64, DbError> {
+    match value {
+        DbType::Int(value) => Ok(f64::from(*value)),
+        DbType::BigInt(value) => Ok(*value as f64),
+        DbType::Float(value) => Ok(*value),
+        DbType::Decimal(value) => Ok(value.to_f64()),
+        other => Err(not_numeric(function, other)),
+    }
+}
+
+fn not_numeric(function: &str, value: &DbType) -> DbError {
+    DbError::Type(format!("{function} is not defined for value {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Operator;
+
+    struct MockTable {
+        column_names: Vec<String>,
+        data: Vec<TableRow>,
+    }
+
+    impl ManipulateTable for MockTable {
+        fn get_column_names(&self) -> &Vec<String> {
+            &self.column_names
+        }
+
+        fn get_data(&self) -> &Vec<TableRow> {
+            &self.data
+        }
+    }
+
+    fn set_up_staff() -> MockTable {
+        let row = |dept: &str, salary: DbType| {
+            TableRow::new(vec![DbType::Text(dept.to_string()), salary])
+        };
+        MockTable {
+            column_names: vec!["dept".to_string(), "salary".to_string()],
+            data: vec![
+                row("eng", DbType::Int(10)),
+                row("ops", DbType::Int(7)),
+                row("eng", DbType::Int(5)),
+                row("eng", DbType::Null),
+                row("ops", DbType::Int(4)),
+            ],
+        }
+    }
+
+    fn all_aggregates() -> Vec<AggregateExpr> {
+        vec![
+            AggregateExpr::new(AggregateFunction::Count, None),
+            AggregateExpr::new(AggregateFunction::Count, Some("salary")),
+            AggregateExpr::new(AggregateFunction::Sum, Some("salary")),
+            AggregateExpr::new(AggregateFunction::Min, Some("salary")),
+            AggregateExpr::new(AggregateFunction::Max, Some("salary")),
+            AggregateExpr::new(AggregateFunction::Avg, Some("salary")).with_alias("average"),
+        ]
+    }
+
+    #[test]
+    fn test_group_by_with_all_aggregates() {
+        let table = set_up_staff();
+        let result = aggregation(&table, &["dept".to_string()], &all_aggregates()).unwrap();
+        assert_eq!(
+            result.get_column_names(),
+            &vec![
+                "dept".to_string(),
+                "COUNT(*)".to_string(),
+                "COUNT(salary)".to_string(),
+                "SUM(salary)".to_string(),
+                "MIN(salary)".to_string(),
+                "MAX(salary)".to_string(),
+                "average".to_string(),
+            ]
+        );
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::Text("eng".to_string()),
+                DbType::BigInt(3),
+                DbType::BigInt(2),
+                DbType::BigInt(15),
+                DbType::Int(5),
+                DbType::Int(10),
+                DbType::Float(7.5),
+            ]
+        );
+        assert_eq!(result.get_data()[1].get_values()[6], DbType::Float(5.5));
+    }
+
+    #[test]
+    fn test_aggregation_without_group_by() {
+        let table = set_up_staff();
+        let result = aggregation(&table, &[], &all_aggregates()).unwrap();
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::BigInt(5),
+                DbType::BigInt(4),
+                DbType::BigInt(26),
+                DbType::Int(4),
+                DbType::Int(10),
+                DbType::Float(6.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregation_of_empty_input() {
+        let table = MockTable {
+            column_names: vec!["dept".to_string(), "salary".to_string()],
+            data: vec![],
+        };
+        let result = aggregation(&table, &[], &all_aggregates()).unwrap();
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::BigInt(0),
+                DbType::BigInt(0),
+                DbType::Null,
+                DbType::Null,
+                DbType::Null,
+                DbType::Null,
+            ]
+        );
+        let grouped = aggregation(&table, &["dept".to_string()], &all_aggregates()).unwrap();
+        assert!(grouped.get_data().is_empty());
+    }
+
+    #[test]
+    fn test_sum_of_text_is_an_error() {
+        let table = set_up_staff();
+        let aggregates = vec![AggregateExpr::new(AggregateFunction::Sum, Some("dept"))];
+        assert!(aggregation(&table, &[], &aggregates).is_err());
+    }
+
+    #[test]
+    fn test_sum_and_avg_of_bigint_and_decimal() {
+        let price = |text: &str| DbType::Decimal(Decimal::parse(text).unwrap());
+        let table = MockTable {
+            column_names: vec!["views".to_string(), "price".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::BigInt(i64::from(i32::MAX)), price("0.10")]),
+                TableRow::new(vec![DbType::BigInt(i64::from(i32::MAX)), price("0.20")]),
+                TableRow::new(vec![DbType::Null, price("1.00")]),
+            ],
+        };
+        let aggregates = vec![
+            AggregateExpr::new(AggregateFunction::Sum, Some("views")),
+            AggregateExpr::new(AggregateFunction::Sum, Some("price")),
+            AggregateExpr::new(AggregateFunction::Avg, Some("price")),
+        ];
+        let result = aggregation(&table, &[], &aggregates).unwrap();
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![
+                DbType::BigInt(2 * i64::from(i32::MAX)),
+                price("1.3"),
+                price("0.433333"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sum_of_int_widens_to_bigint() {
+        let table = MockTable {
+            column_names: vec!["n".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(i32::MAX)]),
+                TableRow::new(vec![DbType::Int(1)]),
+            ],
+        };
+        let aggregates = vec![
+            AggregateExpr::new(AggregateFunction::Sum, Some("n")),
+            AggregateExpr::new(AggregateFunction::Count, Some("n")),
+        ];
+        let result = aggregation(&table, &[], &aggregates).unwrap();
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::BigInt(i64::from(i32::MAX) + 1), DbType::BigInt(2)]
+        );
+        let table = MockTable {
+            column_names: vec!["n".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::BigInt(i64::MAX)]),
+                TableRow::new(vec![DbType::Int(1)]),
+            ],
+        };
+        let aggregates = vec![AggregateExpr::new(AggregateFunction::Sum, Some("n"))];
+        assert_eq!(
+            aggregation(&table, &[], &aggregates).err(),
+            Some(DbError::Type("integer overflow in SUM".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_group_column_is_an_error() {
+        let table = set_up_staff();
+        let result = aggregation(&table, &["team".to_string()], &all_aggregates());
+        assert_eq!(result.err(), Some(DbError::column_not_found("team")));
+    }
+
+    #[test]
+    fn test_having_filters_on_hidden_aggregate() {
+        let table = set_up_staff();
+        let having = Condition::Aggregate {
+            aggregate: AggregateExpr::new(AggregateFunction::Count, None),
+            operator: Operator::GreaterThan,
+            value: DbType::Int(2),
+        };
+        let aggregates = vec![AggregateExpr::new(AggregateFunction::Sum, Some("salary"))];
+        let result =
+            aggregation_having(&table, &["dept".to_string()], &aggregates, &having).unwrap();
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["dept".to_string(), "SUM(salary)".to_string()]
+        );
+        assert_eq!(
+            result
+                .get_data()
+                .iter()
+                .map(|row| row.get_values().clone())
+                .collect::<Vec<_>>(),
+            vec![vec![DbType::Text("eng".to_string()), DbType::BigInt(15)]]
+        );
+    }
+
+    #[test]
+    fn test_having_on_aliased_aggregate_and_group_column() {
+        let table = set_up_staff();
+        let having = Condition::And(
+            Box::new(Condition::Aggregate {
+                aggregate: AggregateExpr::new(AggregateFunction::Avg, Some("salary")),
+                operator: Operator::LessThan,
+                value: DbType::Float(6.0),
+            }),
+            Box::new(Condition::Simple {
+                field: "dept".to_string(),
+                operator: Operator::NotEquals,
+                value: DbType::Text("eng".to_string()),
+            }),
+        );
+        let aggregates =
+            vec![AggregateExpr::new(AggregateFunction::Avg, Some("salary")).with_alias("mean")];
+        let result =
+            aggregation_having(&table, &["dept".to_string()], &aggregates, &having).unwrap();
+        assert_eq!(result.get_column_names()[1], "mean");
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(result.get_data()[0].get_values()[1], DbType::Float(5.5));
+    }
+
+    #[test]
+    fn test_having_on_ungrouped_column_is_an_error() {
+        let table = set_up_staff();
+        let having = Condition::Simple {
+            field: "salary".to_string(),
+            operator: Operator::GreaterThan,
+            value: DbType::Int(1),
+        };
+        let result = aggregation_having(&table, &["dept".to_string()], &[], &having);
+        assert!(result.is_err());
+    }
+
+    /// Multiplies the integers of a group, or NULL when there are none.
+    struct Product;
+
+    impl CustomAggregate for Product {
+        type State = Option<i64>;
+
+        fn init(&self) -> Self::State {
+            None
+        }
+
+        fn accumulate(&self, state: &mut Self::State, value: &DbType) -> Result<(), DbError> {
+            let DbType::Int(value) = value else {
+                return Err(not_numeric("PRODUCT", value));
+            };
+            *state = Some(state.unwrap_or(1) * i64::from(*value));
+            Ok(())
+        }
+
+        fn merge(&self, state: &mut Self::State, other: Self::State) -> Result<(), DbError> {
+            if let Some(other) = other {
+                *state = Some(state.unwrap_or(1) * other);
+            }
+            Ok(())
+        }
+
+        fn finalize(&self, state: Self::State) -> Result<DbType, DbError> {
+            Ok(state.map_or(DbType::Null, DbType::BigInt))
+        }
+    }
+
+    #[test]
+    fn test_custom_aggregate() {
+        let table = set_up_staff();
+        let product = AggregateFunction::Custom(UserAggregate::new("product", Product));
+        let aggregates = vec![AggregateExpr::new(product, Some("salary"))];
+        let result = aggregation(&table, &["dept".to_string()], &aggregates).unwrap();
+        assert_eq!(result.get_column_names()[1], "PRODUCT(salary)");
+        assert_eq!(result.get_data()[0].get_values()[1], DbType::BigInt(50));
+        assert_eq!(result.get_data()[1].get_values()[1], DbType::BigInt(28));
+        let errors = aggregation(
+            &table,
+            &[],
+            &[AggregateExpr::new(
+                AggregateFunction::Custom(UserAggregate::new("product", Product)),
+                Some("dept"),
+            )],
+        );
+        assert!(errors.is_err());
+    }
+
+    #[test]
+    fn test_custom_aggregate_merges_partial_states() {
+        let mut left = Product.init();
+        let mut right = Product.init();
+        Product.accumulate(&mut left, &DbType::Int(3)).unwrap();
+        Product.accumulate(&mut right, &DbType::Int(4)).unwrap();
+        Product.merge(&mut left, right).unwrap();
+        Product.merge(&mut left, Product.init()).unwrap();
+        assert_eq!(Product.finalize(left).unwrap(), DbType::BigInt(12));
+    }
+
+    #[test]
+    fn test_groups_spanning_batches_are_merged() {
+        let row = |dept: &str, salary: DbType| {
+            TableRow::new(vec![DbType::Text(dept.to_string()), salary])
+        };
+        let mut data: Vec<TableRow> = (0..3 * BATCH_ROWS)
+            .map(|index| match index % 2 {
+                0 if index % BATCH_ROWS == 0 => row("eng", DbType::Int(2)),
+                0 => row("eng", DbType::Int(1)),
+                _ if index == BATCH_ROWS + 1 => row("ops", DbType::Null),
+                _ => row("ops", DbType::Int(1)),
+            })
+            .collect();
+        data.push(row("hr", DbType::Int(5)));
+        let table = MockTable {
+            column_names: vec!["dept".to_string(), "salary".to_string()],
+            data,
+        };
+        let product = AggregateFunction::Custom(UserAggregate::new("product", Product));
+        let mut aggregates = all_aggregates();
+        aggregates.push(AggregateExpr::new(product, Some("salary")));
+        let result = aggregation(&table, &["dept".to_string()], &aggregates).unwrap();
+        assert_eq!(
+            result
+                .get_data()
+                .iter()
+                .map(|row| row.get_values().clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    DbType::Text("eng".to_string()),
+                    DbType::BigInt(1536),
+                    DbType::BigInt(1536),
+                    DbType::BigInt(1539),
+                    DbType::Int(1),
+                    DbType::Int(2),
+                    DbType::Float(1539.0 / 1536.0),
+                    DbType::BigInt(8),
+                ],
+                vec![
+                    DbType::Text("ops".to_string()),
+                    DbType::BigInt(1536),
+                    DbType::BigInt(1535),
+                    DbType::BigInt(1535),
+                    DbType::Int(1),
+                    DbType::Int(1),
+                    DbType::Float(1.0),
+                    DbType::BigInt(1),
+                ],
+                vec![
+                    DbType::Text("hr".to_string()),
+                    DbType::BigInt(1),
+                    DbType::BigInt(1),
+                    DbType::BigInt(5),
+                    DbType::Int(5),
+                    DbType::Int(5),
+                    DbType::Float(5.0),
+                    DbType::BigInt(5),
+                ],
+            ]
+        );
+    }
 }
@@ -1,28 +1,66 @@
 use crate::db_type::DbType;
+use crate::error::DbError;
 use crate::query_result::QueryResult;
-use crate::rational_algebra::helper_functions::find_indexes;
+use crate::rational_algebra::executor::{collect, Executor, TableScan};
+use crate::rational_algebra::helper_functions::find_column_indexes;
+use crate::table_row::TableRow;
 use crate::ManipulateTable;
 use std::collections::HashSet;
 
-fn distinct<T>(table: &T, columns: &[String]) -> QueryResult
+#[allow(dead_code)]
+pub(crate) fn distinct<T>(table: &T, columns: &[String]) -> Result<QueryResult, DbError>
 where
     T: ManipulateTable,
 {
-    let indexes = find_indexes(table, columns);
-    let mut seen: HashSet<Vec<DbType>> = HashSet::new();
-    let result = table
-        .get_data()
-        .iter()
-        .filter(|row| {
-            let values = indexes
+    let mut executor = DistinctExecutor::new(Box::new(TableScan::new(table)), columns);
+    collect(&mut executor)
+}
+
+pub(crate) struct DistinctExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    indexes: Vec<usize>,
+    seen: HashSet<Vec<DbType>>,
+}
+
+impl<'a> DistinctExecutor<'a> {
+    pub(crate) fn new(input: Box<dyn Executor + 'a>, columns: &[String]) -> Self {
+        let indexes = find_column_indexes(input.get_column_names(), columns);
+        DistinctExecutor {
+            input,
+            indexes,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl Executor for DistinctExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.seen.clear();
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        while let Some(row) = self.input.next()? {
+            let values = self
+                .indexes
                 .iter()
                 .filter_map(|&index| row.get_values().get(index).cloned())
                 .collect();
-            seen.insert(values)
-        })
-        .cloned()
-        .collect();
-    QueryResult::new(result, table.get_column_names().clone())
+            if self.seen.insert(values) {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) {
+        self.seen.clear();
+        self.input.close();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.input.get_column_names()
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -76,7 +114,7 @@ mod tests {
     fn test_distinct_with_duplicates() {
         let table = set_up_table_with_duplicates();
         let columns = vec!["id".to_string(), "name".to_string()];
-        let result = distinct(&table, &columns);
+        let result = distinct(&table, &columns).unwrap();
         assert_eq!(result.get_data().len(), 2); // Expecting 2 unique rows
     }
 
@@ -84,7 +122,22 @@ mod tests {
     fn test_distinct_no_duplicates() {
         let table = set_up_table_no_duplicates();
         let columns = vec!["id".to_string(), "name".to_string()];
-        let result = distinct(&table, &columns);
+        let result = distinct(&table, &columns).unwrap();
         assert_eq!(result.get_data().len(), 2); // Expecting 2 rows, as there are no duplicates
     }
+
+    #[test]
+    fn test_distinct_treats_nulls_as_equal() {
+        let table = MockTable::new(
+            vec!["id".to_string(), "name".to_string()],
+            vec![
+                TableRow::new(vec![DbType::Int(1), DbType::Null]),
+                TableRow::new(vec![DbType::Int(1), DbType::Null]),
+                TableRow::new(vec![DbType::Null, DbType::Null]),
+            ],
+        );
+        let columns = vec!["id".to_string(), "name".to_string()];
+        let result = distinct(&table, &columns).unwrap();
+        assert_eq!(result.get_data().len(), 2);
+    }
 }
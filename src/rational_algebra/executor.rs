@@ -0,0 +1,210 @@
+use crate::error::DbError;
+use crate::query_result::QueryResult;
+use crate::table_row::TableRow;
+use crate::ManipulateTable;
+
+/// A pull-based operator. `open` may already read its whole input, as a
+/// sort does, so both it and `next` report the errors evaluation runs into.
+pub(crate) trait Executor {
+    fn open(&mut self) -> Result<(), DbError>;
+    fn next(&mut self) -> Result<Option<TableRow>, DbError>;
+    fn close(&mut self);
+    fn get_column_names(&self) -> &Vec<String>;
+}
+
+/// Runs an executor to completion. It is closed even when a row fails.
+pub(crate) fn collect<E>(executor: &mut E) -> Result<QueryResult, DbError>
+where
+    E: Executor + ?Sized,
+{
+    let data = read_all(executor);
+    executor.close();
+    Ok(QueryResult::new(data?, executor.get_column_names().clone()))
+}
+
+fn read_all<E>(executor: &mut E) -> Result<Vec<TableRow>, DbError>
+where
+    E: Executor + ?Sized,
+{
+    executor.open()?;
+    let mut data = Vec::new();
+    while let Some(row) = executor.next()? {
+        data.push(row);
+    }
+    Ok(data)
+}
+
+pub(crate) struct TableScan<'a, T> {
+    table: &'a T,
+    position: usize,
+}
+
+impl<'a, T> TableScan<'a, T>
+where
+    T: ManipulateTable,
+{
+    pub(crate) fn new(table: &'a T) -> Self {
+        TableScan { table, position: 0 }
+    }
+}
+
+impl<T> Executor for TableScan<'_, T>
+where
+    T: ManipulateTable,
+{
+    fn open(&mut self) -> Result<(), DbError> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        let row = self.table.get_data().get(self.position).cloned();
+        self.position += usize::from(row.is_some());
+        Ok(row)
+    }
+
+    fn close(&mut self) {
+        self.position = self.table.get_data().len();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.table.get_column_names()
+    }
+}
+
+pub(crate) struct AliasExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    column_names: Vec<String>,
+}
+
+impl<'a> AliasExecutor<'a> {
+    pub(crate) fn new(input: Box<dyn Executor + 'a>, alias: &str) -> Self {
+        let column_names = input
+            .get_column_names()
+            .iter()
+            .map(|name| {
+                if name.contains('.') {
+                    name.clone()
+                } else {
+                    format!("{alias}.{name}")
+                }
+            })
+            .collect();
+        AliasExecutor {
+            input,
+            column_names,
+        }
+    }
+}
+
+impl Executor for AliasExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        self.input.next()
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        &self.column_names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{Condition, Operator};
+    use crate::db_type::DbType;
+    use crate::rational_algebra::distinct::DistinctExecutor;
+    use crate::rational_algebra::join::{JoinExecutor, JoinKind};
+    use crate::rational_algebra::projection::ProjectionExecutor;
+    use crate::rational_algebra::selection::SelectionExecutor;
+
+    struct MockTable {
+        column_names: Vec<String>,
+        data: Vec<TableRow>,
+    }
+
+    impl ManipulateTable for MockTable {
+        fn get_column_names(&self) -> &Vec<String> {
+            &self.column_names
+        }
+
+        fn get_data(&self) -> &Vec<TableRow> {
+            &self.data
+        }
+    }
+
+    fn set_up_people() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "name".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(1), DbType::Text("Alice".to_string())]),
+                TableRow::new(vec![DbType::Int(2), DbType::Text("Bob".to_string())]),
+                TableRow::new(vec![DbType::Int(3), DbType::Text("Bob".to_string())]),
+            ],
+        }
+    }
+
+    fn set_up_ages() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "age".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(1), DbType::Int(30)]),
+                TableRow::new(vec![DbType::Int(2), DbType::Int(25)]),
+                TableRow::new(vec![DbType::Int(3), DbType::Int(25)]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_scan_is_lazy_and_reopenable() {
+        let table = set_up_people();
+        let mut scan = TableScan::new(&table);
+        scan.open().unwrap();
+        assert_eq!(
+            scan.next().unwrap().unwrap().get_values()[0],
+            DbType::Int(1)
+        );
+        scan.open().unwrap();
+        assert_eq!(
+            scan.next().unwrap().unwrap().get_values()[0],
+            DbType::Int(1)
+        );
+        scan.close();
+        assert!(scan.next().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_pipeline_composes() {
+        let people = set_up_people();
+        let ages = set_up_ages();
+        let condition = Condition::Simple {
+            field: "age".to_string(),
+            operator: Operator::LessThan,
+            value: DbType::Int(28),
+        };
+        let join = JoinExecutor::new(
+            Box::new(TableScan::new(&people)),
+            Box::new(TableScan::new(&ages)),
+            "id",
+            JoinKind::Inner,
+        )
+        .unwrap();
+        let filtered = SelectionExecutor::new(Box::new(join), &condition).unwrap();
+        let projected = ProjectionExecutor::new(Box::new(filtered), &["name".to_string()]).unwrap();
+        let mut distinct = DistinctExecutor::new(Box::new(projected), &["name".to_string()]);
+        let result = collect(&mut distinct).unwrap();
+        assert_eq!(result.get_column_names(), &vec!["name".to_string()]);
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(
+            result.get_data()[0].get_values(),
+            &vec![DbType::Text("Bob".to_string())]
+        );
+    }
+}
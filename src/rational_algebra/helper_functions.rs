@@ -1,13 +1,44 @@
-use crate::ManipulateTable;
-
-pub(crate) fn find_indexes<T>(table: &T, columns: &[String]) -> Vec<usize>
-where
-    T: ManipulateTable,
-{
-    let column_names: &Vec<String> = table.get_column_names();
+pub(crate) fn find_column_indexes(column_names: &[String], columns: &[String]) -> Vec<usize> {
     let indexes: Vec<usize> = columns
         .iter()
         .filter_map(|name| column_names.iter().position(|c| c == name))
         .collect();
     indexes
 }
+
+pub(crate) fn resolve_column_index(column_names: &[String], name: &str) -> Option<usize> {
+    if let Some(index) = column_names.iter().position(|c| c == name) {
+        return Some(index);
+    }
+    let mut candidates = column_names.iter().enumerate().filter(|(_, column)| {
+        column
+            .rsplit_once('.')
+            .is_some_and(|(_, unqualified)| unqualified == name)
+    });
+    let (index, _) = candidates.next()?;
+    if candidates.next().is_some() {
+        None
+    } else {
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_column_index() {
+        let column_names = vec![
+            "a.id".to_string(),
+            "a.name".to_string(),
+            "b.id".to_string(),
+            "total".to_string(),
+        ];
+        assert_eq!(resolve_column_index(&column_names, "b.id"), Some(2));
+        assert_eq!(resolve_column_index(&column_names, "name"), Some(1));
+        assert_eq!(resolve_column_index(&column_names, "total"), Some(3));
+        assert_eq!(resolve_column_index(&column_names, "id"), None);
+        assert_eq!(resolve_column_index(&column_names, "c.id"), None);
+    }
+}
@@ -1,63 +1,532 @@
+use crate::coercion::normalize_key;
+use crate::condition::{Condition, Operator};
+use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::expression::EvaluationError;
 use crate::query_result::QueryResult;
+use crate::rational_algebra::executor::{collect, AliasExecutor, Executor, TableScan};
+use crate::rational_algebra::helper_functions::resolve_column_index;
+use crate::rational_algebra::selection::{make_condition_evaluator, RowPredicate};
 use crate::table_row::TableRow;
 use crate::ManipulateTable;
-use std::error::Error;
-use std::fmt;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
-fn inner_join<T>(table1: &T, table2: &T, by: &str) -> Result<QueryResult, JoinError>
+const NESTED_LOOP_MAX_PAIRS: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+    #[allow(dead_code)]
+    Semi,
+    Anti,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum JoinAlgorithm {
+    NestedLoop,
+    Hash,
+    SortMerge,
+}
+
+/// The join key of each row, normalized so that equal numbers of different
+/// types match, or `None` where a key column is NULL.
+type Keys = [Option<Vec<DbType>>];
+
+pub(crate) fn choose_join_algorithm(keys_1: &Keys, keys_2: &Keys) -> JoinAlgorithm {
+    if keys_1.len().saturating_mul(keys_2.len()) <= NESTED_LOOP_MAX_PAIRS {
+        JoinAlgorithm::NestedLoop
+    } else if is_sorted(keys_1) && is_sorted(keys_2) {
+        JoinAlgorithm::SortMerge
+    } else {
+        JoinAlgorithm::Hash
+    }
+}
+
+fn is_sorted(keys: &Keys) -> bool {
+    keys.iter().flatten().is_sorted()
+}
+
+/// For each key of `keys_1`, the positions of the equal keys in `keys_2` in
+/// ascending order, whichever algorithm finds them.
+fn match_keys(algorithm: JoinAlgorithm, keys_1: &Keys, keys_2: &Keys) -> Vec<Vec<usize>> {
+    match algorithm {
+        JoinAlgorithm::NestedLoop => nested_loop_matches(keys_1, keys_2),
+        JoinAlgorithm::Hash => hash_matches(keys_1, keys_2),
+        JoinAlgorithm::SortMerge => sort_merge_matches(keys_1, keys_2),
+    }
+}
+
+fn nested_loop_matches(keys_1: &Keys, keys_2: &Keys) -> Vec<Vec<usize>> {
+    keys_1
+        .iter()
+        .map(|key_1| {
+            keys_2
+                .iter()
+                .enumerate()
+                .filter(|(_, key_2)| key_1.is_some() && key_1 == *key_2)
+                .map(|(position, _)| position)
+                .collect()
+        })
+        .collect()
+}
+
+fn hash_matches(keys_1: &Keys, keys_2: &Keys) -> Vec<Vec<usize>> {
+    let mut matches = vec![Vec::new(); keys_1.len()];
+    if keys_2.len() <= keys_1.len() {
+        let build = build_hash_table(keys_2);
+        for (position_1, key) in keys_1.iter().enumerate() {
+            if let Some(positions) = key.as_ref().and_then(|key| build.get(key)) {
+                matches[position_1].clone_from(positions);
+            }
+        }
+    } else {
+        let build = build_hash_table(keys_1);
+        for (position_2, key) in keys_2.iter().enumerate() {
+            let positions = key.as_ref().and_then(|key| build.get(key));
+            for &position_1 in positions.into_iter().flatten() {
+                matches[position_1].push(position_2);
+            }
+        }
+    }
+    matches
+}
+
+fn build_hash_table(keys: &Keys) -> HashMap<&Vec<DbType>, Vec<usize>> {
+    let mut table: HashMap<&Vec<DbType>, Vec<usize>> = HashMap::new();
+    for (position, key) in keys.iter().enumerate() {
+        if let Some(key) = key {
+            table.entry(key).or_default().push(position);
+        }
+    }
+    table
+}
+
+fn sort_merge_matches(keys_1: &Keys, keys_2: &Keys) -> Vec<Vec<usize>> {
+    let left = sorted_by_key(keys_1);
+    let right = sorted_by_key(keys_2);
+    let mut matches = vec![Vec::new(); keys_1.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        let (left_key, right_key) = (left[i].0, right[j].0);
+        match left_key.cmp(right_key) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                let i_end = i + left[i..]
+                    .iter()
+                    .take_while(|(key, _)| *key == left_key)
+                    .count();
+                let j_end = j + right[j..]
+                    .iter()
+                    .take_while(|(key, _)| *key == right_key)
+                    .count();
+                for &(_, position_1) in &left[i..i_end] {
+                    matches[position_1]
+                        .extend(right[j..j_end].iter().map(|&(_, position)| position));
+                }
+                i = i_end;
+                j = j_end;
+            }
+        }
+    }
+    matches
+}
+
+/// The non-NULL keys with their positions, ordered by key and then position.
+fn sorted_by_key(keys: &Keys) -> Vec<(&Vec<DbType>, usize)> {
+    let mut keyed: Vec<(&Vec<DbType>, usize)> = keys
+        .iter()
+        .enumerate()
+        .filter_map(|(position, key)| key.as_ref().map(|key| (key, position)))
+        .collect();
+    if !is_sorted(keys) {
+        keyed.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    keyed
+}
+
+#[allow(dead_code)]
+pub(crate) fn join<T, U>(
+    table1: &T,
+    table2: &U,
+    by: &str,
+    kind: JoinKind,
+) -> Result<QueryResult, DbError>
 where
     T: ManipulateTable,
+    U: ManipulateTable,
 {
-    let (index_1, index_2) = find_indexes(table1, table2, by)?;
-    let (data_1, data_2) = (table1.get_data(), table2.get_data());
-    let result = perform_inner_join(data_1, data_2, index_1, index_2);
-    let column_names_result = find_column_names(table1, table2, index_2);
-    Ok(QueryResult::new(result, column_names_result))
-}
-fn perform_inner_join(
-    data_1: &Vec<TableRow>,
-    data_2: &Vec<TableRow>,
-    index_1: usize,
-    index_2: usize,
-) -> Vec<TableRow> {
-    let mut result: Vec<TableRow> = Vec::new();
-    for row1 in data_1 {
-        for row2 in data_2 {
-            if let Some(value1) = row1.get_values().get(index_1) {
-                if let Some(value2) = row2.get_values().get(index_2) {
-                    if value1 == value2 {
-                        result.push(create_row(&row1, &row2, index_2))
+    let mut executor = JoinExecutor::new(
+        Box::new(TableScan::new(table1)),
+        Box::new(TableScan::new(table2)),
+        by,
+        kind,
+    )?;
+    collect(&mut executor)
+}
+
+#[allow(dead_code)]
+pub(crate) fn join_on<T, U>(
+    (table1, name1): (&T, &str),
+    (table2, name2): (&U, &str),
+    condition: &Condition,
+    kind: JoinKind,
+) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+    U: ManipulateTable,
+{
+    let mut executor = JoinExecutor::on(
+        Box::new(AliasExecutor::new(Box::new(TableScan::new(table1)), name1)),
+        Box::new(AliasExecutor::new(Box::new(TableScan::new(table2)), name2)),
+        condition,
+        kind,
+    )?;
+    collect(&mut executor)
+}
+
+pub(crate) struct JoinExecutor<'a> {
+    left: Box<dyn Executor + 'a>,
+    right: Box<dyn Executor + 'a>,
+    kind: JoinKind,
+    left_keys: Vec<usize>,
+    right_keys: Vec<usize>,
+    merged_key: Option<(usize, usize)>,
+    residual: Option<RowPredicate>,
+    left_width: usize,
+    right_width: usize,
+    column_names: Vec<String>,
+    left_rows: std::vec::IntoIter<TableRow>,
+    left_position: usize,
+    right_rows: Vec<TableRow>,
+    /// The right rows with the same key as each left row.
+    key_matches: Vec<Vec<usize>>,
+    right_matched: Vec<bool>,
+    current_left: Option<TableRow>,
+    current_matches: Vec<usize>,
+    match_position: usize,
+    unmatched_position: Option<usize>,
+}
+
+impl<'a> JoinExecutor<'a> {
+    pub(crate) fn new(
+        left: Box<dyn Executor + 'a>,
+        right: Box<dyn Executor + 'a>,
+        by: &str,
+        kind: JoinKind,
+    ) -> Result<Self, DbError> {
+        let (index_1, index_2) =
+            find_indexes(left.get_column_names(), right.get_column_names(), by)?;
+        let column_names = match kind {
+            JoinKind::Semi | JoinKind::Anti => left.get_column_names().clone(),
+            _ => find_column_names(left.get_column_names(), right.get_column_names(), index_2),
+        };
+        Ok(Self::build(
+            left,
+            right,
+            kind,
+            (vec![index_1], vec![index_2]),
+            Some((index_1, index_2)),
+            None,
+            column_names,
+        ))
+    }
+
+    pub(crate) fn on(
+        left: Box<dyn Executor + 'a>,
+        right: Box<dyn Executor + 'a>,
+        condition: &Condition,
+        kind: JoinKind,
+    ) -> Result<Self, DbError> {
+        let left_names = left.get_column_names();
+        let right_names = right.get_column_names();
+        let mut combined_names = left_names.clone();
+        combined_names.extend_from_slice(right_names);
+        if let Some(field) = condition
+            .get_fields()
+            .into_iter()
+            .find(|field| resolve_column_index(&combined_names, field).is_none())
+        {
+            return Err(DbError::NotFound(format!(
+                "Column '{field}' not found or ambiguous in join condition"
+            )));
+        }
+
+        let mut keys = (Vec::new(), Vec::new());
+        let mut residual: Option<Condition> = None;
+        for conjunct in condition.get_conjuncts() {
+            if let Some((left_key, right_key)) = equi_key(conjunct, left_names, right_names) {
+                keys.0.push(left_key);
+                keys.1.push(right_key);
+            } else {
+                residual = Some(match residual {
+                    Some(previous) => {
+                        Condition::And(Box::new(previous), Box::new(conjunct.clone()))
+                    }
+                    None => conjunct.clone(),
+                });
+            }
+        }
+        let residual = residual
+            .map(|residual| make_condition_evaluator(&residual, &combined_names))
+            .transpose()?;
+        let column_names = match kind {
+            JoinKind::Semi | JoinKind::Anti => left_names.clone(),
+            _ => combined_names,
+        };
+        Ok(Self::build(
+            left,
+            right,
+            kind,
+            keys,
+            None,
+            residual,
+            column_names,
+        ))
+    }
+
+    fn build(
+        left: Box<dyn Executor + 'a>,
+        right: Box<dyn Executor + 'a>,
+        kind: JoinKind,
+        (left_keys, right_keys): (Vec<usize>, Vec<usize>),
+        merged_key: Option<(usize, usize)>,
+        residual: Option<RowPredicate>,
+        column_names: Vec<String>,
+    ) -> Self {
+        let left_width = left.get_column_names().len();
+        let right_width = right.get_column_names().len();
+        JoinExecutor {
+            left,
+            right,
+            kind,
+            left_keys,
+            right_keys,
+            merged_key,
+            residual,
+            left_width,
+            right_width,
+            column_names,
+            left_rows: Vec::new().into_iter(),
+            left_position: 0,
+            right_rows: Vec::new(),
+            key_matches: Vec::new(),
+            right_matched: Vec::new(),
+            current_left: None,
+            current_matches: Vec::new(),
+            match_position: 0,
+            unmatched_position: None,
+        }
+    }
+
+    fn combine(&self, row1: &TableRow, row2: &TableRow) -> TableRow {
+        match self.merged_key {
+            Some((_, index_2)) => create_row(row1, row2, index_2),
+            None => concatenate(row1, row2),
+        }
+    }
+
+    fn find_matches(
+        &mut self,
+        position: usize,
+        row1: &TableRow,
+    ) -> Result<Vec<usize>, EvaluationError> {
+        let candidates: Vec<usize> = if self.left_keys.is_empty() {
+            (0..self.right_rows.len()).collect()
+        } else {
+            std::mem::take(&mut self.key_matches[position])
+        };
+        let Some(residual) = &self.residual else {
+            return Ok(candidates);
+        };
+        let mut matches = Vec::new();
+        for position in candidates {
+            if residual(&concatenate(row1, &self.right_rows[position]))? {
+                matches.push(position);
+            }
+        }
+        Ok(matches)
+    }
+
+    fn next_unmatched_right(&mut self, mut position: usize) -> Option<TableRow> {
+        while position < self.right_rows.len() {
+            let current = position;
+            position += 1;
+            if !self.right_matched[current] {
+                self.unmatched_position = Some(position);
+                let right_row = &self.right_rows[current];
+                let mut left_values = vec![DbType::Null; self.left_width];
+                if let Some((index_1, index_2)) = self.merged_key {
+                    if let Some(key) = right_row.get_values().get(index_2) {
+                        left_values[index_1] = key.clone();
                     }
                 }
+                return Some(self.combine(&TableRow::new(left_values), right_row));
             }
         }
+        self.unmatched_position = Some(position);
+        None
     }
-    result
 }
-fn find_indexes<T>(table1: &T, table2: &T, by: &str) -> Result<(usize, usize), JoinError>
-where
-    T: ManipulateTable,
-{
-    let index_1 = table1.get_column_names().iter().position(|name| name == by);
-    let index_2 = table2.get_column_names().iter().position(|name| name == by);
+
+impl Executor for JoinExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        let left_rows = collect(self.left.as_mut())?.into_data();
+        self.right_rows = collect(self.right.as_mut())?.into_data();
+        self.key_matches = if self.left_keys.is_empty() {
+            Vec::new()
+        } else {
+            let keys_1 = keys(&left_rows, &self.left_keys);
+            let keys_2 = keys(&self.right_rows, &self.right_keys);
+            match_keys(choose_join_algorithm(&keys_1, &keys_2), &keys_1, &keys_2)
+        };
+        self.left_rows = left_rows.into_iter();
+        self.left_position = 0;
+        self.right_matched = vec![false; self.right_rows.len()];
+        self.current_left = None;
+        self.current_matches.clear();
+        self.match_position = 0;
+        self.unmatched_position = None;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        loop {
+            if let Some(position) = self.unmatched_position {
+                return Ok(self.next_unmatched_right(position));
+            }
+            if self.current_left.is_none() {
+                match self.left_rows.next() {
+                    Some(row) => {
+                        self.current_matches = self.find_matches(self.left_position, &row)?;
+                        self.left_position += 1;
+                        self.current_left = Some(row);
+                    }
+                    None if matches!(self.kind, JoinKind::Right | JoinKind::Full) => {
+                        self.unmatched_position = Some(0);
+                        continue;
+                    }
+                    None => return Ok(None),
+                }
+                self.match_position = 0;
+            }
+            let Some(row1) = self.current_left.take() else {
+                continue;
+            };
+            match self.kind {
+                JoinKind::Semi | JoinKind::Anti => {
+                    if self.current_matches.is_empty() == (self.kind == JoinKind::Anti) {
+                        return Ok(Some(row1));
+                    }
+                }
+                _ => {
+                    if let Some(&position) = self.current_matches.get(self.match_position) {
+                        self.match_position += 1;
+                        self.right_matched[position] = true;
+                        let row = self.combine(&row1, &self.right_rows[position]);
+                        self.current_left = Some(row1);
+                        return Ok(Some(row));
+                    }
+                    if self.current_matches.is_empty()
+                        && matches!(self.kind, JoinKind::Left | JoinKind::Full)
+                    {
+                        let padding = TableRow::new(vec![DbType::Null; self.right_width]);
+                        return Ok(Some(self.combine(&row1, &padding)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.left_rows = Vec::new().into_iter();
+        self.right_rows.clear();
+        self.key_matches.clear();
+        self.right_matched.clear();
+        self.current_left = None;
+        self.current_matches.clear();
+        self.unmatched_position = None;
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        &self.column_names
+    }
+}
+
+fn equi_key(
+    condition: &Condition,
+    left_names: &[String],
+    right_names: &[String],
+) -> Option<(usize, usize)> {
+    let Condition::Comparison {
+        left_field,
+        operator: Operator::Equals,
+        right_field,
+    } = condition
+    else {
+        return None;
+    };
+    let sides = |first: &str, second: &str| {
+        Some((
+            resolve_column_index(left_names, first)?,
+            resolve_column_index(right_names, second)?,
+        ))
+    };
+    sides(left_field, right_field).or_else(|| sides(right_field, left_field))
+}
+
+fn keys(rows: &[TableRow], indexes: &[usize]) -> Vec<Option<Vec<DbType>>> {
+    rows.iter().map(|row| join_key(row, indexes)).collect()
+}
+
+fn join_key(row: &TableRow, indexes: &[usize]) -> Option<Vec<DbType>> {
+    indexes
+        .iter()
+        .map(|&index| {
+            row.get_values()
+                .get(index)
+                .filter(|value| !value.is_null())
+                .map(normalize_key)
+        })
+        .collect()
+}
+
+fn concatenate(row1: &TableRow, row2: &TableRow) -> TableRow {
+    let mut values = row1.get_values().clone();
+    values.extend_from_slice(row2.get_values());
+    TableRow::new(values)
+}
+
+fn find_indexes(
+    column_names_1: &[String],
+    column_names_2: &[String],
+    by: &str,
+) -> Result<(usize, usize), DbError> {
+    let index_1 = resolve_column_index(column_names_1, by);
+    let index_2 = resolve_column_index(column_names_2, by);
     if index_1.is_none() || index_2.is_none() {
-        return Err(JoinError {
-            message: format!("Column '{}' not found in one of the tables", by),
-        });
+        return Err(DbError::NotFound(format!(
+            "Column '{by}' not found in one of the tables"
+        )));
     }
     let index_1 = index_1.unwrap();
     let index_2 = index_2.unwrap();
-    return Ok((index_1, index_2));
+    Ok((index_1, index_2))
 }
 
 #[inline]
-fn find_column_names<T>(table1: &T, table2: &T, index_2: usize) -> Vec<String>
-where
-    T: ManipulateTable,
-{
+fn find_column_names(
+    column_names_1: &[String],
+    column_names_2: &[String],
+    index_2: usize,
+) -> Vec<String> {
     let mut column_names_result: Vec<String> = Vec::new();
-    column_names_result.extend_from_slice(table1.get_column_names());
-    for (index, value) in table2.get_column_names().iter().enumerate() {
+    column_names_result.extend_from_slice(column_names_1);
+    for (index, value) in column_names_2.iter().enumerate() {
         if index != index_2 {
             column_names_result.push(value.clone());
         }
@@ -76,23 +545,11 @@ fn create_row(row1: &TableRow, row2: &TableRow, index2: usize) -> TableRow {
     TableRow::new(values)
 }
 
-#[derive(Debug)]
-struct JoinError {
-    message: String,
-}
-
-impl Error for JoinError {}
-
-impl fmt::Display for JoinError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Join Error: {}", self.message)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db_type::DbType;
+    use crate::decimal::Decimal;
+    use crate::rational_algebra::executor::{collect, TableScan};
     struct MockTable {
         column_names: Vec<String>,
         data: Vec<TableRow>,
@@ -139,8 +596,8 @@ mod tests {
         let table1 = set_up_table1();
         let table2 = set_up_table2();
 
-        let result = inner_join(&table1, &table2, "id").unwrap();
-        assert_eq!(result.get_data().len(), 2); // This assumes QueryResult has a method `get_rows`
+        let result = join(&table1, &table2, "id", JoinKind::Inner).unwrap();
+        assert_eq!(result.get_data().len(), 2);
     }
 
     #[test]
@@ -154,10 +611,445 @@ mod tests {
             ],
         };
 
-        let result = inner_join(&table1, &table2, "id");
+        let result = join(&table1, &table2, "id", JoinKind::Inner);
         assert!(result.is_err());
         if let Err(e) = result {
-            assert_eq!(e.message, "Column 'id' not found in one of the tables");
+            assert_eq!(
+                e,
+                DbError::NotFound("Column 'id' not found in one of the tables".to_string())
+            );
+        }
+    }
+
+    fn key_table(column_names: &[&str], rows: &[(i32, &str)]) -> MockTable {
+        MockTable::new(
+            column_names.iter().map(ToString::to_string).collect(),
+            rows.iter()
+                .map(|&(key, value)| {
+                    TableRow::new(vec![DbType::Int(key), DbType::Text(value.to_string())])
+                })
+                .collect(),
+        )
+    }
+
+    fn sorted_values(rows: Vec<TableRow>) -> Vec<Vec<DbType>> {
+        let mut values: Vec<Vec<DbType>> = rows
+            .into_iter()
+            .map(|row| row.get_values().clone())
+            .collect();
+        values.sort();
+        values
+    }
+
+    fn key_column(table: &MockTable) -> Vec<Option<Vec<DbType>>> {
+        keys(table.get_data(), &[0])
+    }
+
+    fn assert_algorithms_agree(table1: &MockTable, table2: &MockTable) {
+        let (keys_1, keys_2) = (key_column(table1), key_column(table2));
+        let expected = nested_loop_matches(&keys_1, &keys_2);
+        for algorithm in [JoinAlgorithm::Hash, JoinAlgorithm::SortMerge] {
+            assert_eq!(match_keys(algorithm, &keys_1, &keys_2), expected);
+            assert_eq!(
+                match_keys(algorithm, &keys_2, &keys_1),
+                nested_loop_matches(&keys_2, &keys_1)
+            );
+        }
+        let expected_rows: Vec<TableRow> = table1
+            .get_data()
+            .iter()
+            .zip(&expected)
+            .flat_map(|(row1, positions)| {
+                positions
+                    .iter()
+                    .map(|&position| create_row(row1, &table2.get_data()[position], 0))
+            })
+            .collect();
+        let executor_result = collect(
+            &mut JoinExecutor::new(
+                Box::new(TableScan::new(table1)),
+                Box::new(TableScan::new(table2)),
+                "key",
+                JoinKind::Inner,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            sorted_values(executor_result.get_data().clone()),
+            sorted_values(expected_rows)
+        );
+    }
+
+    #[test]
+    fn test_join_algorithms_with_duplicate_keys() {
+        let table1 = key_table(
+            &["key", "left"],
+            &[(3, "c"), (1, "a"), (2, "b"), (1, "a2"), (5, "e")],
+        );
+        let table2 = key_table(
+            &["key", "right"],
+            &[(1, "x"), (1, "y"), (2, "z"), (4, "w"), (3, "v")],
+        );
+        assert_algorithms_agree(&table1, &table2);
+    }
+
+    #[test]
+    fn test_join_algorithms_without_matches() {
+        let table1 = key_table(&["key", "left"], &[(1, "a"), (2, "b")]);
+        let table2 = key_table(&["key", "right"], &[(3, "c"), (4, "d")]);
+        assert_algorithms_agree(&table1, &table2);
+    }
+
+    #[test]
+    fn test_join_algorithms_with_empty_input() {
+        let table1 = key_table(&["key", "left"], &[]);
+        let table2 = key_table(&["key", "right"], &[(1, "x")]);
+        assert_algorithms_agree(&table1, &table2);
+        assert_algorithms_agree(&table2, &table1);
+    }
+
+    #[test]
+    fn test_join_algorithms_on_sorted_input() {
+        let table1 = key_table(&["key", "left"], &[(1, "a"), (2, "b"), (2, "c"), (7, "d")]);
+        let table2 = key_table(&["key", "right"], &[(2, "x"), (3, "y"), (7, "z"), (7, "q")]);
+        assert_algorithms_agree(&table1, &table2);
+    }
+
+    #[test]
+    fn test_join_algorithms_never_match_null_keys() {
+        let mut table1 = key_table(&["key", "left"], &[(1, "a")]);
+        let mut table2 = key_table(&["key", "right"], &[(1, "x")]);
+        table1.data.push(TableRow::new(vec![
+            DbType::Null,
+            DbType::Text("b".to_string()),
+        ]));
+        table2.data.push(TableRow::new(vec![
+            DbType::Null,
+            DbType::Text("y".to_string()),
+        ]));
+        assert_algorithms_agree(&table1, &table2);
+        assert_eq!(
+            nested_loop_matches(&key_column(&table1), &key_column(&table2)),
+            vec![vec![0], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_choose_join_algorithm() {
+        let small = key_column(&key_table(&["key", "value"], &[(2, "a"), (1, "b")]));
+        assert_eq!(
+            choose_join_algorithm(&small, &small),
+            JoinAlgorithm::NestedLoop
+        );
+        let rows: Vec<(i32, &str)> = (0..100).map(|key| (key, "v")).collect();
+        let sorted = key_column(&key_table(&["key", "value"], &rows));
+        assert_eq!(
+            choose_join_algorithm(&sorted, &sorted),
+            JoinAlgorithm::SortMerge
+        );
+        let reversed: Vec<(i32, &str)> = rows.iter().rev().copied().collect();
+        let unsorted = key_column(&key_table(&["key", "value"], &reversed));
+        assert_eq!(
+            choose_join_algorithm(&unsorted, &sorted),
+            JoinAlgorithm::Hash
+        );
+    }
+
+    #[test]
+    fn test_inner_join_large_inputs_use_same_rows() {
+        let rows: Vec<(i32, &str)> = (0..200).map(|key| (key % 50, "v")).collect();
+        let table1 = key_table(&["key", "left"], &rows);
+        let table2 = key_table(&["key", "right"], &rows);
+        let result = join(&table1, &table2, "key", JoinKind::Inner).unwrap();
+        assert_eq!(result.get_data().len(), 800);
+        assert_algorithms_agree(&table1, &table2);
+    }
+
+    #[test]
+    fn test_join_algorithms_match_numbers_of_different_types() {
+        let ints: Vec<(i32, &str)> = (0..100).map(|key| (key, "v")).collect();
+        let table1 = key_table(&["key", "left"], &ints);
+        let wide = |key: i32| match key % 3 {
+            0 => DbType::BigInt(i64::from(key)),
+            1 => DbType::Decimal(Decimal::new(i128::from(key) * 100, 2)),
+            _ => DbType::Int(key),
+        };
+        let table2 = MockTable::new(
+            vec!["key".to_string(), "right".to_string()],
+            (0..100)
+                .map(|key| TableRow::new(vec![wide(key), DbType::Text("w".to_string())]))
+                .collect(),
+        );
+        let (keys_1, keys_2) = (key_column(&table1), key_column(&table2));
+        assert_eq!(
+            choose_join_algorithm(&keys_1, &keys_2),
+            JoinAlgorithm::SortMerge
+        );
+        let expected: Vec<Vec<usize>> = (0..100).map(|position| vec![position]).collect();
+        for algorithm in [
+            JoinAlgorithm::NestedLoop,
+            JoinAlgorithm::Hash,
+            JoinAlgorithm::SortMerge,
+        ] {
+            assert_eq!(match_keys(algorithm, &keys_1, &keys_2), expected);
+        }
+        let result = join(&table1, &table2, "key", JoinKind::Inner).unwrap();
+        assert_eq!(result.get_data().len(), 100);
+        assert_eq!(result.get_data()[1].get_values()[0], DbType::Int(1));
+    }
+
+    fn set_up_customers() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "name".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(1), DbType::Text("Alice".to_string())]),
+                TableRow::new(vec![DbType::Int(2), DbType::Text("Bob".to_string())]),
+                TableRow::new(vec![DbType::Int(3), DbType::Text("Carol".to_string())]),
+            ],
+        }
+    }
+
+    fn set_up_orders() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "item".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(1), DbType::Text("book".to_string())]),
+                TableRow::new(vec![DbType::Int(1), DbType::Text("pen".to_string())]),
+                TableRow::new(vec![DbType::Int(4), DbType::Text("lamp".to_string())]),
+            ],
         }
     }
+
+    fn values(result: &QueryResult) -> Vec<Vec<DbType>> {
+        result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values().clone())
+            .collect()
+    }
+
+    fn text(value: &str) -> DbType {
+        DbType::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_left_join_pads_unmatched_rows() {
+        let result = join(&set_up_customers(), &set_up_orders(), "id", JoinKind::Left).unwrap();
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["id".to_string(), "name".to_string(), "item".to_string()]
+        );
+        assert_eq!(
+            values(&result),
+            vec![
+                vec![DbType::Int(1), text("Alice"), text("book")],
+                vec![DbType::Int(1), text("Alice"), text("pen")],
+                vec![DbType::Int(2), text("Bob"), DbType::Null],
+                vec![DbType::Int(3), text("Carol"), DbType::Null],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_right_join_keeps_unmatched_right_rows() {
+        let result = join(&set_up_customers(), &set_up_orders(), "id", JoinKind::Right).unwrap();
+        assert_eq!(
+            values(&result),
+            vec![
+                vec![DbType::Int(1), text("Alice"), text("book")],
+                vec![DbType::Int(1), text("Alice"), text("pen")],
+                vec![DbType::Int(4), DbType::Null, text("lamp")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_join_keeps_both_sides() {
+        let result = join(&set_up_customers(), &set_up_orders(), "id", JoinKind::Full).unwrap();
+        assert_eq!(result.get_data().len(), 5);
+        assert_eq!(
+            values(&result).last().unwrap(),
+            &vec![DbType::Int(4), DbType::Null, text("lamp")]
+        );
+    }
+
+    #[test]
+    fn test_semi_and_anti_join() {
+        let semi = join(&set_up_customers(), &set_up_orders(), "id", JoinKind::Semi).unwrap();
+        assert_eq!(semi.get_column_names().len(), 2);
+        assert_eq!(values(&semi), vec![vec![DbType::Int(1), text("Alice")]]);
+
+        let anti = join(&set_up_customers(), &set_up_orders(), "id", JoinKind::Anti).unwrap();
+        assert_eq!(
+            values(&anti),
+            vec![
+                vec![DbType::Int(2), text("Bob")],
+                vec![DbType::Int(3), text("Carol")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_null_keys_never_match() {
+        let mut customers = set_up_customers();
+        customers
+            .data
+            .push(TableRow::new(vec![DbType::Null, text("Dave")]));
+        let mut orders = set_up_orders();
+        orders
+            .data
+            .push(TableRow::new(vec![DbType::Null, text("cup")]));
+        let left = join(&customers, &orders, "id", JoinKind::Left).unwrap();
+        assert_eq!(
+            values(&left).last().unwrap(),
+            &vec![DbType::Null, text("Dave"), DbType::Null]
+        );
+        let anti = join(&customers, &orders, "id", JoinKind::Anti).unwrap();
+        assert_eq!(anti.get_data().len(), 3);
+    }
+
+    #[test]
+    fn test_outer_join_executor_reopens() {
+        let customers = set_up_customers();
+        let orders = set_up_orders();
+        let mut executor = JoinExecutor::new(
+            Box::new(TableScan::new(&customers)),
+            Box::new(TableScan::new(&orders)),
+            "id",
+            JoinKind::Full,
+        )
+        .unwrap();
+        assert_eq!(collect(&mut executor).unwrap().get_data().len(), 5);
+        assert_eq!(collect(&mut executor).unwrap().get_data().len(), 5);
+    }
+
+    fn set_up_prices() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "region".to_string(), "limit".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(1), text("eu"), DbType::Int(10)]),
+                TableRow::new(vec![DbType::Int(1), text("us"), DbType::Int(20)]),
+                TableRow::new(vec![DbType::Int(2), text("eu"), DbType::Int(5)]),
+            ],
+        }
+    }
+
+    fn set_up_purchases() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "region".to_string(), "amount".to_string()],
+            data: vec![
+                TableRow::new(vec![DbType::Int(1), text("eu"), DbType::Int(8)]),
+                TableRow::new(vec![DbType::Int(1), text("us"), DbType::Int(30)]),
+                TableRow::new(vec![DbType::Int(2), text("eu"), DbType::Int(4)]),
+                TableRow::new(vec![DbType::Int(3), text("eu"), DbType::Int(1)]),
+            ],
+        }
+    }
+
+    fn column_comparison(left: &str, operator: Operator, right: &str) -> Condition {
+        Condition::Comparison {
+            left_field: left.to_string(),
+            operator,
+            right_field: right.to_string(),
+        }
+    }
+
+    fn composite_range_condition() -> Condition {
+        Condition::And(
+            Box::new(Condition::And(
+                Box::new(column_comparison("a.id", Operator::Equals, "b.id")),
+                Box::new(column_comparison("b.region", Operator::Equals, "a.region")),
+            )),
+            Box::new(column_comparison("a.amount", Operator::LessThan, "b.limit")),
+        )
+    }
+
+    #[test]
+    fn test_join_on_composite_key_with_range_predicate() {
+        let result = join_on(
+            (&set_up_purchases(), "a"),
+            (&set_up_prices(), "b"),
+            &composite_range_condition(),
+            JoinKind::Inner,
+        )
+        .unwrap();
+        assert_eq!(result.get_column_names().len(), 6);
+        assert_eq!(result.get_column_names()[3], "b.id");
+        assert_eq!(
+            values(&result),
+            vec![
+                vec![
+                    DbType::Int(1),
+                    text("eu"),
+                    DbType::Int(8),
+                    DbType::Int(1),
+                    text("eu"),
+                    DbType::Int(10)
+                ],
+                vec![
+                    DbType::Int(2),
+                    text("eu"),
+                    DbType::Int(4),
+                    DbType::Int(2),
+                    text("eu"),
+                    DbType::Int(5)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_on_left_and_anti() {
+        let condition = composite_range_condition();
+        let left = join_on(
+            (&set_up_purchases(), "a"),
+            (&set_up_prices(), "b"),
+            &condition,
+            JoinKind::Left,
+        )
+        .unwrap();
+        assert_eq!(left.get_data().len(), 4);
+        assert_eq!(left.get_data()[1].get_values()[3], DbType::Null);
+
+        let anti = join_on(
+            (&set_up_purchases(), "a"),
+            (&set_up_prices(), "b"),
+            &condition,
+            JoinKind::Anti,
+        )
+        .unwrap();
+        assert_eq!(
+            values(&anti),
+            vec![
+                vec![DbType::Int(1), text("us"), DbType::Int(30)],
+                vec![DbType::Int(3), text("eu"), DbType::Int(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_on_without_equality_uses_nested_loop() {
+        let condition = column_comparison("amount", Operator::GreaterThan, "limit");
+        let result = join_on(
+            (&set_up_purchases(), "a"),
+            (&set_up_prices(), "b"),
+            &condition,
+            JoinKind::Inner,
+        )
+        .unwrap();
+        assert_eq!(result.get_data().len(), 4);
+    }
+
+    #[test]
+    fn test_join_on_unknown_column() {
+        let condition = column_comparison("a.id", Operator::Equals, "b.missing");
+        let result = join_on(
+            (&set_up_purchases(), "a"),
+            (&set_up_prices(), "b"),
+            &condition,
+            JoinKind::Inner,
+        );
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,136 @@
+use crate::error::DbError;
+use crate::query_result::QueryResult;
+use crate::rational_algebra::executor::{collect, Executor, TableScan};
+use crate::table_row::TableRow;
+use crate::ManipulateTable;
+
+#[allow(dead_code)]
+pub(crate) fn limit<T>(
+    table: &T,
+    limit: Option<usize>,
+    offset: usize,
+) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+{
+    let mut executor = LimitExecutor::new(Box::new(TableScan::new(table)), limit, offset);
+    collect(&mut executor)
+}
+
+/// Skips the first `offset` rows of its input and then yields at most `limit`
+/// rows, without pulling anything from the input once the limit is reached.
+pub(crate) struct LimitExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    limit: Option<usize>,
+    offset: usize,
+    skipped: usize,
+    returned: usize,
+}
+
+impl<'a> LimitExecutor<'a> {
+    pub(crate) fn new(input: Box<dyn Executor + 'a>, limit: Option<usize>, offset: usize) -> Self {
+        LimitExecutor {
+            input,
+            limit,
+            offset,
+            skipped: 0,
+            returned: 0,
+        }
+    }
+}
+
+impl Executor for LimitExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.skipped = 0;
+        self.returned = 0;
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        if self.limit.is_some_and(|limit| self.returned >= limit) {
+            return Ok(None);
+        }
+        while self.skipped < self.offset {
+            if self.input.next()?.is_none() {
+                return Ok(None);
+            }
+            self.skipped += 1;
+        }
+        let row = self.input.next()?;
+        self.returned += usize::from(row.is_some());
+        Ok(row)
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.input.get_column_names()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_type::DbType;
+
+    struct MockTable {
+        column_names: Vec<String>,
+        data: Vec<TableRow>,
+    }
+
+    impl ManipulateTable for MockTable {
+        fn get_column_names(&self) -> &Vec<String> {
+            &self.column_names
+        }
+
+        fn get_data(&self) -> &Vec<TableRow> {
+            &self.data
+        }
+    }
+
+    fn set_up_table() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string()],
+            data: (1..=5)
+                .map(|id| TableRow::new(vec![DbType::Int(id)]))
+                .collect(),
+        }
+    }
+
+    fn ids(result: &QueryResult) -> Vec<DbType> {
+        result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values()[0].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_limit() {
+        let table = set_up_table();
+        let result = limit(&table, Some(2), 0).unwrap();
+        assert_eq!(ids(&result), vec![DbType::Int(1), DbType::Int(2)]);
+    }
+
+    #[test]
+    fn test_limit_with_offset() {
+        let table = set_up_table();
+        let result = limit(&table, Some(2), 3).unwrap();
+        assert_eq!(ids(&result), vec![DbType::Int(4), DbType::Int(5)]);
+    }
+
+    #[test]
+    fn test_offset_without_limit() {
+        let table = set_up_table();
+        assert_eq!(limit(&table, None, 4).unwrap().get_data().len(), 1);
+        assert!(limit(&table, None, 10).unwrap().get_data().is_empty());
+    }
+
+    #[test]
+    fn test_limit_zero() {
+        let table = set_up_table();
+        assert!(limit(&table, Some(0), 0).unwrap().get_data().is_empty());
+    }
+}
@@ -1,6 +1,10 @@
-mod aggregation;
-mod distinct;
-mod helper_functions;
+pub mod aggregation;
+pub mod distinct;
+pub mod executor;
+pub mod helper_functions;
 pub mod join;
+pub mod limit;
 pub mod projection;
 pub mod selection;
+pub mod set_operation;
+pub mod sort;
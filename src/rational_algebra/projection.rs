@@ -1,31 +1,241 @@
 use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::expression::{make_expr_evaluator, Expr, ExprEvaluator};
 use crate::query_result::QueryResult;
-use crate::rational_algebra::helper_functions::find_indexes;
+use crate::rational_algebra::executor::{collect, Executor, TableScan};
 use crate::table_row::TableRow;
 use crate::ManipulateTable;
 
-pub(crate) fn projection<T>(table: &T, columns: &[String]) -> QueryResult
+#[allow(dead_code)]
+pub(crate) fn projection<T>(table: &T, columns: &[String]) -> Result<QueryResult, DbError>
 where
     T: ManipulateTable,
 {
-    assert!(table.get_column_names().len() >= columns.len());
-    let column_names: &Vec<String> = table.get_column_names();
-    let indexes = find_indexes(table, columns);
-    let result: Vec<TableRow> = table
-        .get_data()
-        .iter()
-        .map(|row| {
-            let projected_row: Vec<DbType> = indexes
-                .iter()
-                .filter_map(|&index| row.get_values().get(index))
-                .cloned()
-                .collect();
-            TableRow::new(projected_row)
+    let mut executor = ProjectionExecutor::new(Box::new(TableScan::new(table)), columns)?;
+    collect(&mut executor)
+}
+
+pub(crate) struct ProjectionExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    indexes: Vec<usize>,
+    column_names: Vec<String>,
+}
+
+impl<'a> ProjectionExecutor<'a> {
+    /// Fails when one of `columns` is not produced by `input`.
+    pub(crate) fn new(input: Box<dyn Executor + 'a>, columns: &[String]) -> Result<Self, DbError> {
+        let input_column_names = input.get_column_names();
+        let indexes = columns
+            .iter()
+            .map(|name| {
+                input_column_names
+                    .iter()
+                    .position(|column| column == name)
+                    .ok_or_else(|| DbError::NotFound(format!("column '{name}' not found")))
+            })
+            .collect::<Result<Vec<usize>, DbError>>()?;
+        Ok(ProjectionExecutor {
+            input,
+            indexes,
+            column_names: columns.to_vec(),
+        })
+    }
+}
+
+impl Executor for ProjectionExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        let Some(row) = self.input.next()? else {
+            return Ok(None);
+        };
+        let projected_row: Vec<DbType> = self
+            .indexes
+            .iter()
+            .filter_map(|&index| row.get_values().get(index))
+            .cloned()
+            .collect();
+        Ok(Some(TableRow::new(projected_row)))
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        &self.column_names
+    }
+}
+
+/// Projects computed expressions, such as `price * qty AS total`, naming each
+/// result column after the string paired with its expression.
+#[allow(dead_code)]
+pub(crate) fn expression_projection<T>(
+    table: &T,
+    expressions: &[(Expr, String)],
+) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+{
+    let computed = ComputeExecutor::new(Box::new(TableScan::new(table)), expressions)?;
+    let names: Vec<String> = expressions.iter().map(|(_, name)| name.clone()).collect();
+    let mut executor = ProjectionExecutor::new(Box::new(computed), &names)?;
+    collect(&mut executor)
+}
+
+/// Appends one computed column per expression to every input row, so later
+/// operators can filter, sort or project on the results. An expression that
+/// fails to evaluate, say on division by zero, fails the row with a
+/// `DbError::Type`.
+pub(crate) struct ComputeExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    evaluators: Vec<ExprEvaluator>,
+    column_names: Vec<String>,
+}
+
+impl<'a> ComputeExecutor<'a> {
+    pub(crate) fn new(
+        input: Box<dyn Executor + 'a>,
+        expressions: &[(Expr, String)],
+    ) -> Result<Self, DbError> {
+        let evaluators = expressions
+            .iter()
+            .map(|(expr, _)| make_expr_evaluator(expr, input.get_column_names()))
+            .collect::<Result<Vec<ExprEvaluator>, DbError>>()?;
+        let mut column_names = input.get_column_names().clone();
+        column_names.extend(expressions.iter().map(|(_, name)| name.clone()));
+        Ok(ComputeExecutor {
+            input,
+            evaluators,
+            column_names,
         })
-        .collect();
-    let result_column_names: Vec<String> = indexes
-        .iter()
-        .map(|&index| column_names[index].clone())
-        .collect();
-    QueryResult::new(result, result_column_names)
+    }
+}
+
+impl Executor for ComputeExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        let Some(row) = self.input.next()? else {
+            return Ok(None);
+        };
+        let computed = self
+            .evaluators
+            .iter()
+            .map(|evaluator| evaluator(&row))
+            .collect::<Result<Vec<DbType>, _>>()?;
+        let mut values = row.get_values().clone();
+        values.extend(computed);
+        Ok(Some(TableRow::new(values)))
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        &self.column_names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::BinaryOperator;
+    use crate::schema_struct::ColumnType;
+
+    struct MockTable {
+        column_names: Vec<String>,
+        data: Vec<TableRow>,
+    }
+
+    impl ManipulateTable for MockTable {
+        fn get_column_names(&self) -> &Vec<String> {
+            &self.column_names
+        }
+
+        fn get_data(&self) -> &Vec<TableRow> {
+            &self.data
+        }
+    }
+
+    fn set_up_table(codes: &[&str]) -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "code".to_string()],
+            data: codes
+                .iter()
+                .zip(1..)
+                .map(|(code, id)| {
+                    TableRow::new(vec![DbType::Int(id), DbType::Text((*code).to_string())])
+                })
+                .collect(),
+        }
+    }
+
+    fn cast_code(target: ColumnType) -> Expr {
+        Expr::Cast {
+            expr: Box::new(Expr::column("code")),
+            target,
+        }
+    }
+
+    #[test]
+    fn test_expression_projection() {
+        let table = set_up_table(&["10", " 7 "]);
+        let expressions = vec![
+            (
+                Expr::binary(
+                    Expr::column("id"),
+                    BinaryOperator::Multiply,
+                    cast_code(ColumnType::Int),
+                ),
+                "total".to_string(),
+            ),
+            (Expr::column("id"), "id".to_string()),
+        ];
+        let result = expression_projection(&table, &expressions).unwrap();
+        assert_eq!(
+            result.get_column_names(),
+            &vec!["total".to_string(), "id".to_string()]
+        );
+        assert_eq!(
+            result.get_data()[1].get_values(),
+            &vec![DbType::Int(14), DbType::Int(2)]
+        );
+        let missing = vec![(Expr::column("missing"), "m".to_string())];
+        assert!(expression_projection(&table, &missing).is_err());
+    }
+
+    #[test]
+    fn test_compute_executor_appends_column() {
+        let table = set_up_table(&["1.5"]);
+        let mut executor = ComputeExecutor::new(
+            Box::new(TableScan::new(&table)),
+            &[(cast_code(ColumnType::Float), "as_float".to_string())],
+        )
+        .unwrap();
+        let result = collect(&mut executor).unwrap();
+        assert_eq!(result.get_column_names()[2], "as_float");
+        assert_eq!(result.get_data()[0].get_values()[2], DbType::Float(1.5));
+    }
+
+    #[test]
+    fn test_compute_executor_fails_on_failed_cast() {
+        let table = set_up_table(&["10", "ten"]);
+        let mut executor = ComputeExecutor::new(
+            Box::new(TableScan::new(&table)),
+            &[(cast_code(ColumnType::Int), "as_int".to_string())],
+        )
+        .unwrap();
+        assert_eq!(
+            collect(&mut executor).err(),
+            Some(DbError::Type(
+                "cannot cast TEXT value 'ten' to INT".to_string()
+            ))
+        );
+    }
 }
@@ -1,67 +1,486 @@
+use crate::coercion::compare;
 use crate::condition::{Condition, Operator};
 use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::expression::{make_expr_evaluator, EvaluationError, Expr};
+use crate::like_pattern::LikePattern;
 use crate::query_result::QueryResult;
+use crate::rational_algebra::executor::{collect, Executor, TableScan};
+use crate::rational_algebra::helper_functions::resolve_column_index;
+use crate::secondary_index::KeyRange;
 use crate::table_row::TableRow;
+use crate::table_struct::Table;
 use crate::ManipulateTable;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::ops::Bound;
 
-pub(crate) fn selection<T>(table: &T, condition: &Condition) -> QueryResult
+#[allow(dead_code)]
+pub(crate) fn selection<T>(table: &T, condition: &Condition) -> Result<QueryResult, DbError>
 where
     T: ManipulateTable,
 {
-    let column_names = table.get_column_names();
-    let index_key: HashMap<String, usize> = column_names
-        .iter()
-        .enumerate()
-        .map(|(index, name)| (name.clone(), index))
-        .collect();
-    let evaluator = make_condition_evaluator(condition, &index_key);
+    let mut executor = SelectionExecutor::new(Box::new(TableScan::new(table)), condition)?;
+    collect(&mut executor)
+}
 
-    let result = table
-        .get_data()
-        .iter()
-        .filter(|row| evaluator(row))
-        .cloned()
-        .collect();
-    QueryResult::new(result, table.get_column_names().clone())
+/// Filters a table like `SelectionExecutor` does, but reads only the row
+/// the primary-key index points to when the condition fixes every key
+/// column with `column = value`. The condition is checked against the
+/// column types first, so comparing a TEXT column with an INT fails here.
+pub(crate) fn table_selection<'a>(
+    table: &'a Table,
+    condition: &Condition,
+) -> Result<SelectionExecutor<'a>, DbError> {
+    condition.check_types(&table.get_schema().get_column_types())?;
+    let input: Box<dyn Executor + 'a> = if let Some(key) = primary_key_values(table, condition) {
+        Box::new(PrimaryKeyLookup::new(table, key))
+    } else if let Some(positions) = index_positions(table, condition) {
+        Box::new(IndexScan::new(table, positions))
+    } else {
+        Box::new(TableScan::new(table))
+    };
+    SelectionExecutor::new(input, condition)
 }
 
-fn make_condition_evaluator<'a>(
-    condition: &'a Condition,
-    index_key: &'a HashMap<String, usize>,
-) -> Box<dyn Fn(&TableRow) -> bool + 'a> {
+/// The rows a secondary index narrows the condition down to, taking the
+/// conjunct whose index scan returns the fewest. An index is only used when
+/// the compared values have the column's own type, so that its order is
+/// the order `compare` gives.
+fn index_positions(table: &Table, condition: &Condition) -> Option<Vec<usize>> {
+    let columns = table.get_schema().get_columns();
+    condition
+        .get_conjuncts()
+        .into_iter()
+        .filter_map(key_range)
+        .filter_map(|(field, range)| {
+            let column = resolve_column_index(table.get_column_names(), field)?;
+            let fits = range
+                .get_values()
+                .iter()
+                .all(|value| !value.is_null() && columns[column].get_type().check(value).is_ok());
+            let index = table
+                .get_indexes()
+                .iter()
+                .find(|index| index.get_columns()[0] == column);
+            match index {
+                Some(index) if fits => Some(index.scan(&range)),
+                _ => None,
+            }
+        })
+        .min_by_key(Vec::len)
+}
+
+/// The column a conjunct restricts and the values it allows.
+fn key_range(condition: &Condition) -> Option<(&str, KeyRange)> {
     match condition {
         Condition::Simple {
             field,
             operator,
             value,
         } => {
-            let field_index = *index_key.get(field).unwrap();
+            let value = value.clone();
+            let range = match operator {
+                Operator::Equals => KeyRange::equal_to(value),
+                Operator::LessThan => KeyRange::Between(Bound::Unbounded, Bound::Excluded(value)),
+                Operator::LessThanOrEquals => {
+                    KeyRange::Between(Bound::Unbounded, Bound::Included(value))
+                }
+                Operator::GreaterThan => {
+                    KeyRange::Between(Bound::Excluded(value), Bound::Unbounded)
+                }
+                Operator::GreaterThanOrEquals => {
+                    KeyRange::Between(Bound::Included(value), Bound::Unbounded)
+                }
+                Operator::NotEquals => return None,
+            };
+            Some((field, range))
+        }
+        Condition::Between {
+            operand: Expr::Column(field),
+            low: Expr::Literal(low),
+            high: Expr::Literal(high),
+        } => Some((
+            field,
+            KeyRange::Between(Bound::Included(low.clone()), Bound::Included(high.clone())),
+        )),
+        Condition::Like {
+            operand: Expr::Column(field),
+            pattern,
+        } if !pattern.is_case_insensitive() && !pattern.get_prefix().is_empty() => {
+            Some((field, KeyRange::Prefix(pattern.get_prefix())))
+        }
+        _ => None,
+    }
+}
+
+/// Yields the rows of a table at the given positions.
+pub(crate) struct IndexScan<'a> {
+    table: &'a Table,
+    positions: Vec<usize>,
+    cursor: usize,
+}
+
+impl<'a> IndexScan<'a> {
+    pub(crate) fn new(table: &'a Table, positions: Vec<usize>) -> Self {
+        IndexScan {
+            table,
+            positions,
+            cursor: 0,
+        }
+    }
+}
+
+impl Executor for IndexScan<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.cursor = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        let Some(&position) = self.positions.get(self.cursor) else {
+            return Ok(None);
+        };
+        self.cursor += 1;
+        Ok(self.table.get_data().get(position).cloned())
+    }
+
+    fn close(&mut self) {
+        self.cursor = self.positions.len();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.table.get_column_names()
+    }
+}
+
+/// The key values a condition's top-level conjuncts require, if they cover
+/// the whole primary key.
+fn primary_key_values(table: &Table, condition: &Condition) -> Option<Vec<DbType>> {
+    let key = table.get_primary_key();
+    if key.is_empty() {
+        return None;
+    }
+    let conjuncts = condition.get_conjuncts();
+    key.iter()
+        .map(|column| {
+            conjuncts.iter().find_map(|conjunct| match conjunct {
+                Condition::Simple {
+                    field,
+                    operator: Operator::Equals,
+                    value,
+                } if field == *column => Some(value.clone()),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// Yields the row with the given primary key, if there is one.
+pub(crate) struct PrimaryKeyLookup<'a> {
+    table: &'a Table,
+    key: Vec<DbType>,
+    done: bool,
+}
+
+impl<'a> PrimaryKeyLookup<'a> {
+    pub(crate) fn new(table: &'a Table, key: Vec<DbType>) -> Self {
+        PrimaryKeyLookup {
+            table,
+            key,
+            done: false,
+        }
+    }
+}
+
+impl Executor for PrimaryKeyLookup<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.done = false;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        Ok(self.table.get_by_pk(&self.key).cloned())
+    }
+
+    fn close(&mut self) {
+        self.done = true;
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.table.get_column_names()
+    }
+}
+
+/// Decides whether a row is kept. Fails when a value in the row cannot be
+/// compared or computed, such as on division by zero.
+pub(crate) type RowPredicate = Box<dyn Fn(&TableRow) -> Result<bool, EvaluationError>>;
+
+pub(crate) struct SelectionExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    evaluator: RowPredicate,
+}
+
+impl<'a> SelectionExecutor<'a> {
+    pub(crate) fn new(
+        input: Box<dyn Executor + 'a>,
+        condition: &Condition,
+    ) -> Result<Self, DbError> {
+        let evaluator = make_condition_evaluator(condition, input.get_column_names())?;
+        Ok(SelectionExecutor { input, evaluator })
+    }
+}
+
+impl Executor for SelectionExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        while let Some(row) = self.input.next()? {
+            if (self.evaluator)(&row)? {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.input.get_column_names()
+    }
+}
+
+/// Builds a predicate that keeps a row only when the condition is TRUE. Under
+/// SQL three-valued logic a comparison involving NULL is UNKNOWN, and UNKNOWN
+/// rows are filtered out just like FALSE ones. Fails when the condition
+/// refers to a column that is missing from `column_names`.
+pub(crate) fn make_condition_evaluator(
+    condition: &Condition,
+    column_names: &[String],
+) -> Result<RowPredicate, DbError> {
+    let evaluator = make_truth_evaluator(condition, column_names)?;
+    Ok(Box::new(move |row| Ok(evaluator(row)? == Some(true))))
+}
+
+/// Evaluates to `Some(true)`, `Some(false)` or `None` for UNKNOWN.
+pub(crate) type TruthPredicate = Box<dyn Fn(&TableRow) -> Result<Option<bool>, EvaluationError>>;
+
+pub(crate) fn make_truth_evaluator(
+    condition: &Condition,
+    column_names: &[String],
+) -> Result<TruthPredicate, DbError> {
+    let evaluator: TruthPredicate = match condition {
+        Condition::Simple {
+            field,
+            operator,
+            value,
+        } => {
+            let field_index = column_index(column_names, field)?;
+            let operator = *operator;
+            let value = value.clone();
             let elevator = move |row: &TableRow| match row.get_values().get(field_index) {
-                Some(row_value) => evaluate(row_value, operator, value),
-                None => false,
+                Some(row_value) => evaluate(row_value, operator, &value),
+                None => Ok(Some(false)),
             };
             Box::new(elevator)
         }
+        Condition::Aggregate {
+            aggregate,
+            operator,
+            value,
+        } => {
+            let simple = Condition::Simple {
+                field: aggregate.get_canonical_name(),
+                operator: *operator,
+                value: value.clone(),
+            };
+            make_truth_evaluator(&simple, column_names)?
+        }
+        Condition::Comparison {
+            left_field,
+            operator,
+            right_field,
+        } => {
+            let left_index = column_index(column_names, left_field)?;
+            let right_index = column_index(column_names, right_field)?;
+            let operator = *operator;
+            Box::new(move |row: &TableRow| {
+                let values = row.get_values();
+                match (values.get(left_index), values.get(right_index)) {
+                    (Some(left_value), Some(right_value)) => {
+                        evaluate(left_value, operator, right_value)
+                    }
+                    _ => Ok(Some(false)),
+                }
+            })
+        }
+        Condition::Expression {
+            left,
+            operator,
+            right,
+        } => {
+            let left = make_expr_evaluator(left, column_names)?;
+            let right = make_expr_evaluator(right, column_names)?;
+            let operator = *operator;
+            Box::new(move |row: &TableRow| evaluate(&left(row)?, operator, &right(row)?))
+        }
+        Condition::In { operand, list } => make_in_evaluator(operand, list, column_names)?,
+        Condition::Between { operand, low, high } => {
+            make_between_evaluator(operand, low, high, column_names)?
+        }
+        Condition::Like { operand, pattern } => {
+            make_like_evaluator(operand, pattern, column_names)?
+        }
+        Condition::IsNull { field } | Condition::IsNotNull { field } => {
+            let wants_null = matches!(condition, Condition::IsNull { .. });
+            make_null_check(field, wants_null, column_names)?
+        }
+        Condition::Not(condition) => {
+            let operand = make_truth_evaluator(condition, column_names)?;
+            Box::new(move |row| Ok(operand(row)?.map(|value| !value)))
+        }
         Condition::And(lhs, rhs) => {
-            let left_operand = make_condition_evaluator(lhs, index_key);
-            let right_operand = make_condition_evaluator(rhs, index_key);
-            Box::new(move |row| left_operand(row) && right_operand(row))
+            let left_operand = make_truth_evaluator(lhs, column_names)?;
+            let right_operand = make_truth_evaluator(rhs, column_names)?;
+            Box::new(move |row| {
+                Ok(match left_operand(row)? {
+                    Some(false) => Some(false),
+                    left => match (left, right_operand(row)?) {
+                        (_, Some(false)) => Some(false),
+                        (Some(true), Some(true)) => Some(true),
+                        _ => None,
+                    },
+                })
+            })
         }
         Condition::Or(lhs, rhs) => {
-            let left_operand = make_condition_evaluator(lhs, index_key);
-            let right_operand = make_condition_evaluator(rhs, index_key);
-            Box::new(move |row| left_operand(row) || right_operand(row))
+            let left_operand = make_truth_evaluator(lhs, column_names)?;
+            let right_operand = make_truth_evaluator(rhs, column_names)?;
+            Box::new(move |row| {
+                Ok(match left_operand(row)? {
+                    Some(true) => Some(true),
+                    left => match (left, right_operand(row)?) {
+                        (_, Some(true)) => Some(true),
+                        (Some(false), Some(false)) => Some(false),
+                        _ => None,
+                    },
+                })
+            })
         }
-    }
+    };
+    Ok(evaluator)
+}
+
+fn make_null_check(
+    field: &str,
+    wants_null: bool,
+    column_names: &[String],
+) -> Result<TruthPredicate, DbError> {
+    let field_index = column_index(column_names, field)?;
+    Ok(Box::new(move |row: &TableRow| {
+        Ok(row
+            .get_values()
+            .get(field_index)
+            .map(|value| value.is_null() == wants_null))
+    }))
+}
+
+fn column_index(column_names: &[String], name: &str) -> Result<usize, DbError> {
+    resolve_column_index(column_names, name).ok_or_else(|| DbError::column_not_found(name))
+}
+
+/// TRUE when any item is equal, otherwise UNKNOWN if some item could not be
+/// compared because of a NULL.
+fn make_in_evaluator(
+    operand: &Expr,
+    list: &[Expr],
+    column_names: &[String],
+) -> Result<TruthPredicate, DbError> {
+    let operand = make_expr_evaluator(operand, column_names)?;
+    let list = list
+        .iter()
+        .map(|item| make_expr_evaluator(item, column_names))
+        .collect::<Result<Vec<_>, DbError>>()?;
+    Ok(Box::new(move |row: &TableRow| {
+        let value = operand(row)?;
+        let mut result = Some(false);
+        for item in &list {
+            match evaluate(&value, Operator::Equals, &item(row)?)? {
+                Some(true) => return Ok(Some(true)),
+                Some(false) => {}
+                None => result = None,
+            }
+        }
+        Ok(result)
+    }))
+}
+
+fn make_between_evaluator(
+    operand: &Expr,
+    low: &Expr,
+    high: &Expr,
+    column_names: &[String],
+) -> Result<TruthPredicate, DbError> {
+    let operand = make_expr_evaluator(operand, column_names)?;
+    let low = make_expr_evaluator(low, column_names)?;
+    let high = make_expr_evaluator(high, column_names)?;
+    Ok(Box::new(move |row: &TableRow| {
+        let value = operand(row)?;
+        Ok(
+            match (
+                evaluate(&value, Operator::GreaterThanOrEquals, &low(row)?)?,
+                evaluate(&value, Operator::LessThanOrEquals, &high(row)?)?,
+            ) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+        )
+    }))
 }
 
+fn make_like_evaluator(
+    operand: &Expr,
+    pattern: &LikePattern,
+    column_names: &[String],
+) -> Result<TruthPredicate, DbError> {
+    let operand = make_expr_evaluator(operand, column_names)?;
+    let pattern = pattern.clone();
+    Ok(Box::new(move |row: &TableRow| match operand(row)? {
+        DbType::Null => Ok(None),
+        DbType::Text(text) => Ok(Some(pattern.matches(&text))),
+        value => Err(EvaluationError::new(&format!(
+            "LIKE cannot be applied to {}",
+            value.type_name()
+        ))),
+    }))
+}
+
+/// Compares with the coercion rules of `coercion::compare`. Values that have
+/// no common type are an error; `Condition::check_types` reports such
+/// conditions before any row is read when the column types are known.
 #[inline]
-fn evaluate(row_value: &DbType, operator: &Operator, value: &DbType) -> bool {
-    match operator {
-        Operator::Equals => row_value == value,
-        Operator::LessThan => row_value < value,
-        Operator::GreaterThan => row_value > value,
-        Operator::NotEquals => row_value != value,
-    }
+fn evaluate(
+    row_value: &DbType,
+    operator: Operator,
+    value: &DbType,
+) -> Result<Option<bool>, EvaluationError> {
+    let Some(ordering) = compare(row_value, value)? else {
+        return Ok(None);
+    };
+    Ok(Some(match operator {
+        Operator::Equals => ordering == Ordering::Equal,
+        Operator::LessThan => ordering == Ordering::Less,
+        Operator::GreaterThan => ordering == Ordering::Greater,
+        Operator::NotEquals => ordering != Ordering::Equal,
+        Operator::LessThanOrEquals => ordering != Ordering::Greater,
+        Operator::GreaterThanOrEquals => ordering != Ordering::Less,
+    }))
 }
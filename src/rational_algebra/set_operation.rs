@@ -0,0 +1,410 @@
+use crate::coercion::{check_comparable, normalize_key};
+use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::query_result::QueryResult;
+use crate::rational_algebra::executor::{collect, Executor, TableScan};
+use crate::schema_struct::ColumnType;
+use crate::table_row::TableRow;
+use crate::ManipulateTable;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl SetOperator {
+    fn name(self) -> &'static str {
+        match self {
+            SetOperator::Union => "UNION",
+            SetOperator::Intersect => "INTERSECT",
+            SetOperator::Except => "EXCEPT",
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub(crate) fn union<T, U>(table1: &T, table2: &U, all: bool) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+    U: ManipulateTable,
+{
+    set_operation(table1, table2, SetOperator::Union, all)
+}
+
+#[allow(dead_code)]
+pub(crate) fn intersect<T, U>(table1: &T, table2: &U, all: bool) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+    U: ManipulateTable,
+{
+    set_operation(table1, table2, SetOperator::Intersect, all)
+}
+
+#[allow(dead_code)]
+pub(crate) fn except<T, U>(table1: &T, table2: &U, all: bool) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+    U: ManipulateTable,
+{
+    set_operation(table1, table2, SetOperator::Except, all)
+}
+
+/// Runs a set operation over two tables after checking that every column has
+/// comparable types on both sides, inferring the type of a column from its
+/// values where the input does not declare one. The output keeps the column
+/// names of `table1`, and rows come out in the order they are first seen.
+pub(crate) fn set_operation<T, U>(
+    table1: &T,
+    table2: &U,
+    operator: SetOperator,
+    all: bool,
+) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+    U: ManipulateTable,
+{
+    check_column_types(&column_types(table1), &column_types(table2), operator)?;
+    let mut executor = SetOperationExecutor::new(
+        Box::new(TableScan::new(table1)),
+        Box::new(TableScan::new(table2)),
+        operator,
+        all,
+    )?;
+    collect(&mut executor)
+}
+
+/// Streams the left input and combines it with the right one. UNION streams
+/// both sides; INTERSECT and EXCEPT first count the rows of the right input.
+/// Without ALL, each distinct row is returned at most once. Rows are matched
+/// on their `normalize_key` form, as join keys are, so INT `1` equals BIGINT
+/// `1` and FLOAT `1.0`.
+pub(crate) struct SetOperationExecutor<'a> {
+    left: Box<dyn Executor + 'a>,
+    right: Box<dyn Executor + 'a>,
+    operator: SetOperator,
+    all: bool,
+    left_exhausted: bool,
+    right_counts: HashMap<Vec<DbType>, usize>,
+    seen: HashSet<Vec<DbType>>,
+}
+
+impl<'a> SetOperationExecutor<'a> {
+    pub(crate) fn new(
+        left: Box<dyn Executor + 'a>,
+        right: Box<dyn Executor + 'a>,
+        operator: SetOperator,
+        all: bool,
+    ) -> Result<Self, DbError> {
+        let left_width = left.get_column_names().len();
+        let right_width = right.get_column_names().len();
+        if left_width != right_width {
+            return Err(DbError::Schema(format!(
+                "each {} input must have the same number of columns, found {left_width} and {right_width}",
+                operator.name()
+            )));
+        }
+        Ok(SetOperationExecutor {
+            left,
+            right,
+            operator,
+            all,
+            left_exhausted: false,
+            right_counts: HashMap::new(),
+            seen: HashSet::new(),
+        })
+    }
+
+    fn next_union(&mut self) -> Result<Option<TableRow>, DbError> {
+        if !self.left_exhausted {
+            if let Some(row) = self.left.next()? {
+                return Ok(Some(row));
+            }
+            self.left_exhausted = true;
+            self.right.open()?;
+        }
+        self.right.next()
+    }
+
+    fn keep(&mut self, key: &[DbType]) -> bool {
+        let in_right = match self.right_counts.get_mut(key) {
+            Some(count) if *count > 0 => {
+                if self.all {
+                    *count -= 1;
+                }
+                true
+            }
+            _ => false,
+        };
+        match self.operator {
+            SetOperator::Union => true,
+            SetOperator::Intersect => in_right,
+            SetOperator::Except => !in_right,
+        }
+    }
+}
+
+impl Executor for SetOperationExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.left_exhausted = false;
+        self.right_counts.clear();
+        self.seen.clear();
+        self.left.open()?;
+        if self.operator != SetOperator::Union {
+            self.right.open()?;
+            while let Some(row) = self.right.next()? {
+                *self.right_counts.entry(normalized(&row)).or_insert(0) += 1;
+            }
+            self.right.close();
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        loop {
+            let row = match self.operator {
+                SetOperator::Union => self.next_union()?,
+                _ => self.left.next()?,
+            };
+            let Some(row) = row else {
+                return Ok(None);
+            };
+            let key = normalized(&row);
+            if !self.keep(&key) {
+                continue;
+            }
+            if self.all || self.seen.insert(key) {
+                return Ok(Some(row));
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.right_counts.clear();
+        self.seen.clear();
+        self.left.close();
+        if self.operator == SetOperator::Union && self.left_exhausted {
+            self.right.close();
+        }
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.left.get_column_names()
+    }
+}
+
+fn normalized(row: &TableRow) -> Vec<DbType> {
+    row.get_values().iter().map(normalize_key).collect()
+}
+
+/// The declared type of each column or else the type of its first non-NULL
+/// value, which is all a query result tells about its columns.
+fn column_types<T: ManipulateTable>(table: &T) -> Vec<Option<ColumnType>> {
+    table
+        .get_column_types()
+        .into_iter()
+        .enumerate()
+        .map(|(column, declared)| {
+            declared.or_else(|| {
+                table
+                    .get_data()
+                    .iter()
+                    .find_map(|row| row.get_values().get(column).and_then(ColumnType::of))
+            })
+        })
+        .collect()
+}
+
+/// Checks that the types of each column can be compared across the
+/// two sides. Columns whose type is not known on either side are not checked.
+fn check_column_types(
+    left: &[Option<ColumnType>],
+    right: &[Option<ColumnType>],
+    operator: SetOperator,
+) -> Result<(), DbError> {
+    for (column, types) in left.iter().zip(right).enumerate() {
+        if let (Some(left_type), Some(right_type)) = types {
+            if check_comparable(left_type, right_type).is_err() {
+                return Err(DbError::Type(format!(
+                    "{} column {} has incompatible types {left_type} and {right_type}",
+                    operator.name(),
+                    column + 1
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockTable {
+        column_names: Vec<String>,
+        column_types: Vec<Option<ColumnType>>,
+        data: Vec<TableRow>,
+    }
+
+    impl ManipulateTable for MockTable {
+        fn get_column_names(&self) -> &Vec<String> {
+            &self.column_names
+        }
+
+        fn get_data(&self) -> &Vec<TableRow> {
+            &self.data
+        }
+
+        fn get_column_types(&self) -> Vec<Option<ColumnType>> {
+            self.column_types.clone()
+        }
+    }
+
+    fn ids_table(name: &str, ids: &[i32]) -> MockTable {
+        MockTable {
+            column_names: vec![name.to_string()],
+            column_types: vec![Some(ColumnType::Int)],
+            data: ids
+                .iter()
+                .map(|&id| TableRow::new(vec![DbType::Int(id)]))
+                .collect(),
+        }
+    }
+
+    fn ids(result: &QueryResult) -> Vec<DbType> {
+        result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values()[0].clone())
+            .collect()
+    }
+
+    fn int_ids(values: &[i32]) -> Vec<DbType> {
+        values.iter().map(|&value| DbType::Int(value)).collect()
+    }
+
+    #[test]
+    fn test_union() {
+        let left = ids_table("id", &[1, 2, 2, 3]);
+        let right = ids_table("user_id", &[3, 4, 4]);
+        let result = union(&left, &right, false).unwrap();
+        assert_eq!(result.get_column_names(), &vec!["id".to_string()]);
+        assert_eq!(ids(&result), int_ids(&[1, 2, 3, 4]));
+        let result = union(&left, &right, true).unwrap();
+        assert_eq!(ids(&result), int_ids(&[1, 2, 2, 3, 3, 4, 4]));
+    }
+
+    #[test]
+    fn test_intersect() {
+        let left = ids_table("id", &[1, 2, 2, 2, 3]);
+        let right = ids_table("id", &[2, 2, 3, 5]);
+        assert_eq!(
+            ids(&intersect(&left, &right, false).unwrap()),
+            int_ids(&[2, 3])
+        );
+        assert_eq!(
+            ids(&intersect(&left, &right, true).unwrap()),
+            int_ids(&[2, 2, 3])
+        );
+    }
+
+    #[test]
+    fn test_except() {
+        let left = ids_table("id", &[1, 1, 2, 2, 2, 3]);
+        let right = ids_table("id", &[2, 3]);
+        assert_eq!(ids(&except(&left, &right, false).unwrap()), int_ids(&[1]));
+        assert_eq!(
+            ids(&except(&left, &right, true).unwrap()),
+            int_ids(&[1, 1, 2, 2])
+        );
+    }
+
+    #[test]
+    fn test_column_count_mismatch() {
+        let left = ids_table("id", &[1]);
+        let right = MockTable {
+            column_names: vec!["id".to_string(), "name".to_string()],
+            column_types: vec![None, None],
+            data: vec![],
+        };
+        let error = union(&left, &right, false).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Schema Error: each UNION input must have the same number of columns, found 1 and 2"
+        );
+    }
+
+    #[test]
+    fn test_incompatible_column_types() {
+        let left = ids_table("id", &[1]);
+        // The right side holds no value to infer a type from.
+        let right = MockTable {
+            column_names: vec!["name".to_string()],
+            column_types: vec![Some(ColumnType::Text { max_length: None })],
+            data: vec![TableRow::new(vec![DbType::Null])],
+        };
+        let error = except(&left, &right, false).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: EXCEPT column 1 has incompatible types INT and TEXT"
+        );
+        let empty = MockTable {
+            data: Vec::new(),
+            ..right
+        };
+        assert!(union(&left, &empty, false).is_err());
+
+        let floats = MockTable {
+            column_names: vec!["score".to_string()],
+            column_types: vec![Some(ColumnType::Float)],
+            data: vec![TableRow::new(vec![DbType::Float(1.0)])],
+        };
+        assert!(union(&left, &floats, false).is_ok());
+    }
+
+    #[test]
+    fn test_column_types_of_query_results_are_inferred() {
+        let result = |values: Vec<DbType>| {
+            QueryResult::new(
+                values
+                    .into_iter()
+                    .map(|value| TableRow::new(vec![value]))
+                    .collect(),
+                vec!["value".to_string()],
+            )
+        };
+        let ids = result(vec![DbType::Null, DbType::Int(1)]);
+        let names = result(vec![DbType::Text("one".to_string())]);
+        let error = intersect(&ids, &names, false).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Type Error: INTERSECT column 1 has incompatible types INT and TEXT"
+        );
+        assert!(union(&ids, &result(vec![DbType::BigInt(1)]), false).is_ok());
+        assert!(union(&ids, &result(vec![DbType::Null]), false).is_ok());
+    }
+
+    #[test]
+    fn test_rows_match_across_numeric_types() {
+        let left = ids_table("id", &[1, 2, 3]);
+        let right = MockTable {
+            column_names: vec!["id".to_string()],
+            column_types: vec![Some(ColumnType::BigInt)],
+            data: vec![
+                TableRow::new(vec![DbType::BigInt(1)]),
+                TableRow::new(vec![DbType::BigInt(3)]),
+            ],
+        };
+        assert_eq!(
+            ids(&intersect(&left, &right, false).unwrap()),
+            int_ids(&[1, 3])
+        );
+        assert_eq!(ids(&except(&left, &right, false).unwrap()), int_ids(&[2]));
+        assert_eq!(
+            ids(&union(&left, &right, false).unwrap()),
+            int_ids(&[1, 2, 3])
+        );
+    }
+}
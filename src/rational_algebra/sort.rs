@@ -0,0 +1,640 @@
+use crate::coercion::normalize_key;
+use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::query_result::QueryResult;
+use crate::rational_algebra::executor::{collect, Executor, TableScan};
+use crate::rational_algebra::helper_functions::resolve_column_index;
+use crate::table_row::TableRow;
+use crate::ManipulateTable;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::iter;
+use std::mem;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+pub(crate) const DEFAULT_SORT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+/// How many spilled runs are merged at once by default.
+pub(crate) const DEFAULT_MERGE_FAN_IN: usize = 64;
+
+static NEXT_RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SortKey {
+    pub(crate) column: String,
+    pub(crate) ascending: bool,
+}
+
+impl SortKey {
+    #[allow(dead_code)]
+    pub(crate) fn asc(column: &str) -> Self {
+        SortKey {
+            column: column.to_string(),
+            ascending: true,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn desc(column: &str) -> Self {
+        SortKey {
+            column: column.to_string(),
+            ascending: false,
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub(crate) fn sort<T>(table: &T, keys: &[SortKey]) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+{
+    let mut executor = SortExecutor::new(Box::new(TableScan::new(table)), keys)?;
+    collect(&mut executor)
+}
+
+/// Sorts its input by the given keys, keeping equal rows in input order.
+/// Values are ordered by their `normalize_key` form, so numbers of different
+/// types, or DATE and TIMESTAMP, interleave by value rather than by type.
+///
+/// Rows are buffered until their estimated size exceeds the memory budget, at
+/// which point the buffer is sorted and spilled to a temporary file as a run.
+/// If any run was spilled, the output is a k-way merge of all runs. With more
+/// runs than the merge fan-in, consecutive runs are first merged into longer
+/// ones, pass after pass, so no more than that many files are read at once.
+pub(crate) struct SortExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    keys: Vec<(usize, bool)>,
+    memory_budget: usize,
+    fan_in: usize,
+    output: SortOutput,
+}
+
+enum SortOutput {
+    Empty,
+    Memory(std::vec::IntoIter<TableRow>),
+    Merge(RunMerge),
+}
+
+impl<'a> SortExecutor<'a> {
+    pub(crate) fn new(input: Box<dyn Executor + 'a>, keys: &[SortKey]) -> Result<Self, DbError> {
+        let keys = resolve_keys(input.get_column_names(), keys)?;
+        Ok(SortExecutor {
+            input,
+            keys,
+            memory_budget: DEFAULT_SORT_MEMORY_BUDGET,
+            fan_in: DEFAULT_MERGE_FAN_IN,
+            output: SortOutput::Empty,
+        })
+    }
+
+    /// Sets the approximate number of bytes of rows kept in memory before a
+    /// sorted run is spilled to disk.
+    pub(crate) fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    /// Sets how many runs are merged at once, at least two.
+    #[allow(dead_code)]
+    pub(crate) fn with_merge_fan_in(mut self, runs: usize) -> Self {
+        self.fan_in = runs.max(2);
+        self
+    }
+
+    fn sort_rows(&self, rows: &mut [TableRow]) {
+        rows.sort_by_cached_key(|row| sort_key(row, &self.keys));
+    }
+
+    /// Merges each group of `fan_in` consecutive runs into one, keeping the
+    /// runs in order so ties still go to the earlier row.
+    fn merge_pass(&self, runs: Vec<SpilledRun>) -> Result<Vec<SpilledRun>, DbError> {
+        let mut merged = Vec::with_capacity(runs.len().div_ceil(self.fan_in));
+        let mut runs = runs.into_iter();
+        loop {
+            let mut group: Vec<SpilledRun> = runs.by_ref().take(self.fan_in).collect();
+            match group.len() {
+                0 => return Ok(merged),
+                1 => merged.extend(group.pop()),
+                _ => {
+                    let mut merge = RunMerge::new(group, &self.keys)?;
+                    merged.push(SpilledRun::write(iter::from_fn(|| {
+                        merge.next().transpose()
+                    }))?);
+                }
+            }
+        }
+    }
+}
+
+impl Executor for SortExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.output = SortOutput::Empty;
+        self.input.open()?;
+        let mut buffer = Vec::new();
+        let mut buffered_bytes = 0;
+        let mut runs = Vec::new();
+        while let Some(row) = self.input.next()? {
+            buffered_bytes += estimated_size(&row);
+            buffer.push(row);
+            if buffered_bytes > self.memory_budget {
+                self.sort_rows(&mut buffer);
+                runs.push(SpilledRun::write(
+                    mem::take(&mut buffer).into_iter().map(Ok),
+                )?);
+                buffered_bytes = 0;
+            }
+        }
+        self.input.close();
+
+        self.sort_rows(&mut buffer);
+        if runs.is_empty() {
+            self.output = SortOutput::Memory(buffer.into_iter());
+            return Ok(());
+        }
+        if !buffer.is_empty() {
+            runs.push(SpilledRun::write(buffer.into_iter().map(Ok))?);
+        }
+        while runs.len() > self.fan_in {
+            runs = self.merge_pass(runs)?;
+        }
+        self.output = SortOutput::Merge(RunMerge::new(runs, &self.keys)?);
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        match mem::replace(&mut self.output, SortOutput::Empty) {
+            SortOutput::Empty => Ok(None),
+            SortOutput::Memory(mut rows) => {
+                let row = rows.next();
+                self.output = SortOutput::Memory(rows);
+                Ok(row)
+            }
+            SortOutput::Merge(mut merge) => {
+                let row = merge.next()?;
+                self.output = SortOutput::Merge(merge);
+                Ok(row)
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.output = SortOutput::Empty;
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.input.get_column_names()
+    }
+}
+
+#[allow(dead_code)]
+pub(crate) fn top_n<T>(table: &T, keys: &[SortKey], n: usize) -> Result<QueryResult, DbError>
+where
+    T: ManipulateTable,
+{
+    let mut executor = TopNExecutor::new(Box::new(TableScan::new(table)), keys, n)?;
+    collect(&mut executor)
+}
+
+/// Yields the first `n` rows of the input in sort order, in the same order a
+/// full stable sort would. Only `n` rows are held at a time: a max-heap keeps
+/// the current best rows and evicts the worst one whenever it grows past `n`,
+/// so the whole input is ranked in O(rows * log n).
+pub(crate) struct TopNExecutor<'a> {
+    input: Box<dyn Executor + 'a>,
+    keys: Vec<(usize, bool)>,
+    n: usize,
+    output: std::vec::IntoIter<TableRow>,
+}
+
+impl<'a> TopNExecutor<'a> {
+    pub(crate) fn new(
+        input: Box<dyn Executor + 'a>,
+        keys: &[SortKey],
+        n: usize,
+    ) -> Result<Self, DbError> {
+        let keys = resolve_keys(input.get_column_names(), keys)?;
+        Ok(TopNExecutor {
+            input,
+            keys,
+            n,
+            output: Vec::new().into_iter(),
+        })
+    }
+}
+
+impl Executor for TopNExecutor<'_> {
+    fn open(&mut self) -> Result<(), DbError> {
+        self.input.open()?;
+        let mut heap = BinaryHeap::new();
+        let mut position = 0;
+        while let Some(row) = self.input.next()? {
+            let candidate = RankedRow {
+                key: sort_key(&row, &self.keys),
+                position,
+                row,
+            };
+            position += 1;
+            if heap.len() < self.n {
+                heap.push(candidate);
+            } else if heap.peek().is_some_and(|worst| candidate < *worst) {
+                heap.pop();
+                heap.push(candidate);
+            }
+        }
+        self.input.close();
+        self.output = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.row)
+            .collect::<Vec<TableRow>>()
+            .into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        Ok(self.output.next())
+    }
+
+    fn close(&mut self) {
+        self.output = Vec::new().into_iter();
+    }
+
+    fn get_column_names(&self) -> &Vec<String> {
+        self.input.get_column_names()
+    }
+}
+
+fn resolve_keys(column_names: &[String], keys: &[SortKey]) -> Result<Vec<(usize, bool)>, DbError> {
+    keys.iter()
+        .map(|key| {
+            resolve_column_index(column_names, &key.column)
+                .map(|index| (index, key.ascending))
+                .ok_or_else(|| DbError::column_not_found(&key.column))
+        })
+        .collect()
+}
+
+fn sort_key(row: &TableRow, keys: &[(usize, bool)]) -> Vec<SortValue> {
+    keys.iter()
+        .map(|&(index, ascending)| SortValue {
+            value: normalize_key(&row.get_values()[index]),
+            ascending,
+        })
+        .collect()
+}
+
+fn estimated_size(row: &TableRow) -> usize {
+    mem::size_of::<TableRow>()
+        + row
+            .get_values()
+            .iter()
+            .map(|value| match value {
+                DbType::Text(text) => mem::size_of::<DbType>() + text.len(),
+                DbType::Blob(bytes) => mem::size_of::<DbType>() + bytes.len(),
+                _ => mem::size_of::<DbType>(),
+            })
+            .sum::<usize>()
+}
+
+#[derive(PartialEq, Eq)]
+struct SortValue {
+    value: DbType,
+    ascending: bool,
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.ascending {
+            self.value.cmp(&other.value)
+        } else {
+            other.value.cmp(&self.value)
+        }
+    }
+}
+
+/// A row paired with its sort key. Ties are broken by `position`, the order in
+/// which the row (or the run holding it) was read, which keeps sorting stable.
+struct RankedRow {
+    key: Vec<SortValue>,
+    position: usize,
+    row: TableRow,
+}
+
+impl PartialEq for RankedRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RankedRow {}
+
+impl PartialOrd for RankedRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.key, self.position).cmp(&(&other.key, other.position))
+    }
+}
+
+/// A k-way merge of sorted runs. Ties go to the earlier run, which keeps the
+/// merge stable.
+struct RunMerge {
+    runs: Vec<SpilledRun>,
+    heap: BinaryHeap<Reverse<RankedRow>>,
+    keys: Vec<(usize, bool)>,
+}
+
+impl RunMerge {
+    fn new(runs: Vec<SpilledRun>, keys: &[(usize, bool)]) -> Result<Self, DbError> {
+        let mut merge = RunMerge {
+            heap: BinaryHeap::with_capacity(runs.len()),
+            runs,
+            keys: keys.to_vec(),
+        };
+        for run in 0..merge.runs.len() {
+            merge.push_from_run(run)?;
+        }
+        Ok(merge)
+    }
+
+    fn push_from_run(&mut self, run: usize) -> Result<(), DbError> {
+        if let Some(row) = self.runs[run].next_row()? {
+            self.heap.push(Reverse(RankedRow {
+                key: sort_key(&row, &self.keys),
+                position: run,
+                row,
+            }));
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<TableRow>, DbError> {
+        let Some(Reverse(entry)) = self.heap.pop() else {
+            return Ok(None);
+        };
+        self.push_from_run(entry.position)?;
+        Ok(Some(entry.row))
+    }
+}
+
+/// A sorted run in a temporary file, which is removed when the run is
+/// dropped. The file is only opened for reading when its first row is.
+struct SpilledRun {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    remaining: usize,
+}
+
+impl SpilledRun {
+    fn write<I>(rows: I) -> Result<Self, DbError>
+    where
+        I: IntoIterator<Item = Result<TableRow, DbError>>,
+    {
+        let path = std::env::temp_dir().join(format!(
+            "toy_db_sort_{}_{}.run",
+            process::id(),
+            NEXT_RUN_ID.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        // The run owns the path from here on, so a failed write removes it.
+        let mut run = SpilledRun {
+            path,
+            reader: None,
+            remaining: 0,
+        };
+        let mut writer = BufWriter::new(File::create(&run.path)?);
+        for row in rows {
+            bincode::serialize_into(&mut writer, row?.get_values())?;
+            run.remaining += 1;
+        }
+        writer.flush()?;
+        Ok(run)
+    }
+
+    fn next_row(&mut self) -> Result<Option<TableRow>, DbError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let reader = match &mut self.reader {
+            Some(reader) => reader,
+            slot @ None => slot.insert(BufReader::new(File::open(&self.path)?)),
+        };
+        let values: Vec<DbType> = bincode::deserialize_from(reader)?;
+        self.remaining -= 1;
+        Ok(Some(TableRow::new(values)))
+    }
+}
+
+impl Drop for SpilledRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::Decimal;
+
+    struct MockTable {
+        column_names: Vec<String>,
+        data: Vec<TableRow>,
+    }
+
+    impl ManipulateTable for MockTable {
+        fn get_column_names(&self) -> &Vec<String> {
+            &self.column_names
+        }
+
+        fn get_data(&self) -> &Vec<TableRow> {
+            &self.data
+        }
+    }
+
+    fn set_up_table() -> MockTable {
+        MockTable {
+            column_names: vec!["id".to_string(), "dept".to_string(), "salary".to_string()],
+            data: vec![
+                TableRow::new(vec![
+                    DbType::Int(1),
+                    DbType::Text("eng".to_string()),
+                    DbType::Int(100),
+                ]),
+                TableRow::new(vec![
+                    DbType::Int(2),
+                    DbType::Text("ops".to_string()),
+                    DbType::Int(80),
+                ]),
+                TableRow::new(vec![
+                    DbType::Int(3),
+                    DbType::Text("eng".to_string()),
+                    DbType::Int(120),
+                ]),
+                TableRow::new(vec![
+                    DbType::Int(4),
+                    DbType::Text("ops".to_string()),
+                    DbType::Int(80),
+                ]),
+                TableRow::new(vec![
+                    DbType::Int(5),
+                    DbType::Text("eng".to_string()),
+                    DbType::Int(100),
+                ]),
+            ],
+        }
+    }
+
+    fn ids(result: &QueryResult) -> Vec<DbType> {
+        result
+            .get_data()
+            .iter()
+            .map(|row| row.get_values()[0].clone())
+            .collect()
+    }
+
+    fn int_ids(values: &[i32]) -> Vec<DbType> {
+        values.iter().map(|&value| DbType::Int(value)).collect()
+    }
+
+    #[test]
+    fn test_sort_single_key_ascending() {
+        let table = set_up_table();
+        let result = sort(&table, &[SortKey::asc("salary")]).unwrap();
+        assert_eq!(ids(&result), int_ids(&[2, 4, 1, 5, 3]));
+    }
+
+    #[test]
+    fn test_sort_multiple_keys_with_directions() {
+        let table = set_up_table();
+        let result = sort(&table, &[SortKey::asc("dept"), SortKey::desc("salary")]).unwrap();
+        assert_eq!(ids(&result), int_ids(&[3, 1, 5, 2, 4]));
+    }
+
+    #[test]
+    fn test_sort_is_stable() {
+        let table = set_up_table();
+        let result = sort(&table, &[SortKey::desc("dept")]).unwrap();
+        assert_eq!(ids(&result), int_ids(&[2, 4, 1, 3, 5]));
+    }
+
+    #[test]
+    fn test_sort_unknown_column() {
+        let table = set_up_table();
+        assert!(sort(&table, &[SortKey::asc("missing")]).is_err());
+    }
+
+    #[test]
+    fn test_external_sort_matches_in_memory_sort() {
+        let table = MockTable {
+            column_names: vec!["id".to_string(), "bucket".to_string()],
+            data: (0..500)
+                .map(|id| TableRow::new(vec![DbType::Int(id), DbType::Int((id * 37) % 11)]))
+                .collect(),
+        };
+        let keys = [SortKey::desc("bucket")];
+        let expected = sort(&table, &keys).unwrap();
+
+        let mut executor = SortExecutor::new(Box::new(TableScan::new(&table)), &keys)
+            .unwrap()
+            .with_memory_budget(1024);
+        let spilled = collect(&mut executor).unwrap();
+        assert_eq!(ids(&spilled), ids(&expected));
+
+        // Reopening re-sorts the input from scratch.
+        let again = collect(&mut executor).unwrap();
+        assert_eq!(ids(&again), ids(&expected));
+    }
+
+    #[test]
+    fn test_external_sort_merges_in_passes() {
+        let table = MockTable {
+            column_names: vec!["id".to_string(), "bucket".to_string()],
+            data: (0..500)
+                .map(|id| TableRow::new(vec![DbType::Int(id), DbType::Int((id * 37) % 11)]))
+                .collect(),
+        };
+        let keys = [SortKey::asc("bucket")];
+        let expected = sort(&table, &keys).unwrap();
+
+        // A small budget spills dozens of runs, merged two and three at a time.
+        for fan_in in [2, 3] {
+            let mut executor = SortExecutor::new(Box::new(TableScan::new(&table)), &keys)
+                .unwrap()
+                .with_memory_budget(512)
+                .with_merge_fan_in(fan_in);
+            assert_eq!(ids(&collect(&mut executor).unwrap()), ids(&expected));
+        }
+    }
+
+    #[test]
+    fn test_sort_orders_numbers_of_different_types_by_value() {
+        let values = [
+            DbType::Int(100),
+            DbType::Float(1.25),
+            DbType::Decimal(Decimal::parse("2.50").unwrap()),
+            DbType::BigInt(-3),
+            DbType::Float(2.0),
+        ];
+        let table = MockTable {
+            column_names: vec!["id".to_string(), "value".to_string()],
+            data: (0..200)
+                .zip(values.iter().cycle())
+                .map(|(id, value)| TableRow::new(vec![DbType::Int(id), value.clone()]))
+                .collect(),
+        };
+        let keys = [SortKey::asc("value")];
+        let in_memory = sort(&table, &keys).unwrap();
+        let first_of_each: Vec<DbType> = in_memory
+            .get_data()
+            .iter()
+            .step_by(40)
+            .map(|row| row.get_values()[1].clone())
+            .collect();
+        assert_eq!(
+            first_of_each,
+            vec![
+                values[3].clone(),
+                values[1].clone(),
+                values[4].clone(),
+                values[2].clone(),
+                values[0].clone()
+            ]
+        );
+        let mut executor = SortExecutor::new(Box::new(TableScan::new(&table)), &keys)
+            .unwrap()
+            .with_memory_budget(512);
+        assert_eq!(ids(&collect(&mut executor).unwrap()), ids(&in_memory));
+        let top = top_n(&table, &keys, 40).unwrap();
+        assert_eq!(ids(&top), ids(&in_memory)[..40]);
+    }
+
+    #[test]
+    fn test_top_n_matches_sort_prefix() {
+        let table = set_up_table();
+        let keys = [SortKey::desc("salary")];
+        let result = top_n(&table, &keys, 3).unwrap();
+        assert_eq!(ids(&result), int_ids(&[3, 1, 5]));
+
+        let sorted = sort(&table, &keys).unwrap();
+        for n in 0..=6 {
+            let expected: Vec<DbType> = ids(&sorted).into_iter().take(n).collect();
+            assert_eq!(ids(&top_n(&table, &keys, n).unwrap()), expected);
+        }
+        // A huge LIMIT plus OFFSET saturates to usize::MAX and keeps every row.
+        let all = top_n(&table, &keys, usize::MAX).unwrap();
+        assert_eq!(ids(&all), ids(&sorted));
+    }
+}
@@ -0,0 +1,636 @@
+use crate::coercion::{common_type, compare, TypeError};
+use crate::db_type::DbType;
+use crate::decimal::MAX_SCALE;
+use crate::expression::{apply, binary_type, negate, BinaryOperator, EvaluationError};
+use crate::schema_struct::ColumnType;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// What a parameter of a scalar function accepts. NULL is accepted by every
+/// kind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterKind {
+    Any,
+    Text,
+    Integer,
+    Number,
+    /// A DATE or a TIMESTAMP.
+    Temporal,
+}
+
+impl ParameterKind {
+    fn accepts(self, column_type: &ColumnType) -> bool {
+        match self {
+            ParameterKind::Any => true,
+            ParameterKind::Text => matches!(column_type, ColumnType::Text { .. }),
+            ParameterKind::Integer => matches!(column_type, ColumnType::Int | ColumnType::BigInt),
+            ParameterKind::Number => column_type.is_numeric(),
+            ParameterKind::Temporal => {
+                matches!(column_type, ColumnType::Date | ColumnType::Timestamp)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParameterKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ParameterKind::Any => "any value",
+            ParameterKind::Text => "TEXT",
+            ParameterKind::Integer => "an integer",
+            ParameterKind::Number => "a number",
+            ParameterKind::Temporal => "a DATE or TIMESTAMP",
+        };
+        write!(f, "{description}")
+    }
+}
+
+/// The parameters of a function: the required ones, then the optional ones,
+/// then, for variadic functions, any number of further arguments of one kind.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    required: Vec<ParameterKind>,
+    optional: Vec<ParameterKind>,
+    variadic: Option<ParameterKind>,
+}
+
+impl Signature {
+    #[must_use]
+    pub fn fixed(required: &[ParameterKind]) -> Self {
+        Signature {
+            required: required.to_vec(),
+            optional: Vec::new(),
+            variadic: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_optional(required: &[ParameterKind], optional: &[ParameterKind]) -> Self {
+        Signature {
+            required: required.to_vec(),
+            optional: optional.to_vec(),
+            variadic: None,
+        }
+    }
+
+    #[must_use]
+    pub fn variadic(required: &[ParameterKind], rest: ParameterKind) -> Self {
+        Signature {
+            required: required.to_vec(),
+            optional: Vec::new(),
+            variadic: Some(rest),
+        }
+    }
+
+    fn parameter(&self, index: usize) -> Option<ParameterKind> {
+        self.required
+            .iter()
+            .chain(&self.optional)
+            .nth(index)
+            .copied()
+            .or(self.variadic)
+    }
+
+    fn check_arity(&self, name: &str, count: usize) -> Result<(), TypeError> {
+        let minimum = self.required.len();
+        let maximum = minimum + self.optional.len();
+        let expected = match (self.variadic, minimum == maximum) {
+            (Some(_), _) if count >= minimum => return Ok(()),
+            (None, _) if (minimum..=maximum).contains(&count) => return Ok(()),
+            (Some(_), _) => format!("at least {minimum} {}", arguments(minimum)),
+            (None, true) => format!("{minimum} {}", arguments(minimum)),
+            (None, false) => format!("{minimum} to {maximum} arguments"),
+        };
+        Err(TypeError::new(&format!(
+            "{name} takes {expected}, got {count}"
+        )))
+    }
+}
+
+fn arguments(count: usize) -> &'static str {
+    if count == 1 {
+        "argument"
+    } else {
+        "arguments"
+    }
+}
+
+type ReturnType = Box<dyn Fn(&[Option<ColumnType>]) -> Result<Option<ColumnType>, TypeError>>;
+type Body = Box<dyn Fn(&[DbType]) -> Result<DbType, EvaluationError>>;
+
+/// A scalar function, built in or registered from Rust. Arguments are checked
+/// against the signature both when a query is planned, from the argument
+/// types, and when the function is called, from the values.
+pub struct ScalarFunction {
+    name: String,
+    signature: Signature,
+    /// Whether any NULL argument makes the result NULL without calling `body`.
+    strict: bool,
+    /// Whether the result is converted to the return type, for functions
+    /// that return one of arguments of different types.
+    coerce_result: bool,
+    return_type: ReturnType,
+    body: Body,
+}
+
+impl fmt::Debug for ScalarFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScalarFunction")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for ScalarFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl ScalarFunction {
+    /// A function computed by `body`, which by default accepts any number of
+    /// arguments of any type, NULLs included, and whose result type is only
+    /// known once it runs.
+    #[must_use]
+    pub fn new<F>(name: &str, body: F) -> Self
+    where
+        F: Fn(&[DbType]) -> Result<DbType, EvaluationError> + 'static,
+    {
+        ScalarFunction {
+            name: name.to_ascii_uppercase(),
+            signature: Signature::variadic(&[], ParameterKind::Any),
+            strict: false,
+            coerce_result: false,
+            return_type: Box::new(|_| Ok(None)),
+            body: Box::new(body),
+        }
+    }
+
+    /// Restricts the arguments the function accepts.
+    #[must_use]
+    pub fn with_signature(mut self, signature: Signature) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    /// Declares the type of the result, whatever the arguments.
+    #[must_use]
+    pub fn returning(mut self, column_type: ColumnType) -> Self {
+        self.return_type = Box::new(move |_| Ok(Some(column_type.clone())));
+        self
+    }
+
+    /// Makes a NULL argument give NULL without calling the function.
+    #[must_use]
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    fn lenient(mut self) -> Self {
+        self.strict = false;
+        self
+    }
+
+    fn coercing_result(mut self) -> Self {
+        self.coerce_result = true;
+        self
+    }
+
+    fn builtin<T, B>(name: &str, signature: Signature, return_type: T, body: B) -> Self
+    where
+        T: Fn(&[Option<ColumnType>]) -> Result<Option<ColumnType>, TypeError> + 'static,
+        B: Fn(&[DbType]) -> Result<DbType, EvaluationError> + 'static,
+    {
+        ScalarFunction {
+            name: name.to_string(),
+            signature,
+            strict: true,
+            coerce_result: false,
+            return_type: Box::new(return_type),
+            body: Box::new(body),
+        }
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether a query must convert the result to the return type, because
+    /// the function passes on arguments whose types differ.
+    pub(crate) fn coerces_result(&self) -> bool {
+        self.coerce_result
+    }
+
+    pub(crate) fn check_arity(&self, count: usize) -> Result<(), TypeError> {
+        self.signature.check_arity(&self.name, count)
+    }
+
+    /// The result type for arguments of the given types, `None` standing for
+    /// a type that is not known before the query runs.
+    pub(crate) fn return_type(
+        &self,
+        argument_types: &[Option<ColumnType>],
+    ) -> Result<Option<ColumnType>, TypeError> {
+        self.check_arity(argument_types.len())?;
+        for (index, argument_type) in argument_types.iter().enumerate() {
+            if let Some(argument_type) = argument_type {
+                self.check_argument(index, argument_type)?;
+            }
+        }
+        (self.return_type)(argument_types)
+    }
+
+    pub(crate) fn call(&self, arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+        self.check_arity(arguments.len())?;
+        for (index, argument) in arguments.iter().enumerate() {
+            if let Some(argument_type) = ColumnType::of(argument) {
+                self.check_argument(index, &argument_type)?;
+            } else if self.strict {
+                return Ok(DbType::Null);
+            }
+        }
+        (self.body)(arguments)
+    }
+
+    fn check_argument(&self, index: usize, argument_type: &ColumnType) -> Result<(), TypeError> {
+        match self.signature.parameter(index) {
+            Some(kind) if !kind.accepts(argument_type) => Err(TypeError::new(&format!(
+                "{} argument {} must be {kind}, found {argument_type}",
+                self.name,
+                index + 1
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+use ParameterKind::{Any, Integer, Number, Temporal, Text};
+
+/// The functions every registry starts with.
+pub(crate) fn builtin_functions() -> Vec<ScalarFunction> {
+    let mut functions = vec![
+        ScalarFunction::builtin(
+            "UPPER",
+            Signature::fixed(&[Text]),
+            |_| Ok(Some(ColumnType::Text { max_length: None })),
+            |arguments| Ok(upper(arguments)),
+        ),
+        ScalarFunction::builtin(
+            "LOWER",
+            Signature::fixed(&[Text]),
+            |_| Ok(Some(ColumnType::Text { max_length: None })),
+            |arguments| Ok(lower(arguments)),
+        ),
+        ScalarFunction::builtin(
+            "LENGTH",
+            Signature::fixed(&[Text]),
+            |_| Ok(Some(ColumnType::Int)),
+            length,
+        ),
+        ScalarFunction::builtin(
+            "SUBSTR",
+            Signature::with_optional(&[Text, Integer], &[Integer]),
+            |_| Ok(Some(ColumnType::Text { max_length: None })),
+            substr,
+        ),
+        ScalarFunction::builtin(
+            "TRIM",
+            Signature::fixed(&[Text]),
+            |_| Ok(Some(ColumnType::Text { max_length: None })),
+            |arguments| Ok(trim(arguments)),
+        ),
+        ScalarFunction::builtin(
+            "CONCAT",
+            Signature::variadic(&[Any], Any),
+            |_| Ok(Some(ColumnType::Text { max_length: None })),
+            |arguments| Ok(concat(arguments)),
+        )
+        .lenient(),
+        ScalarFunction::builtin(
+            "ABS",
+            Signature::fixed(&[Number]),
+            |types| Ok(types[0].clone()),
+            abs,
+        ),
+        ScalarFunction::builtin(
+            "ROUND",
+            Signature::with_optional(&[Number], &[Integer]),
+            |types| Ok(types[0].clone()),
+            round,
+        ),
+        ScalarFunction::builtin(
+            "MOD",
+            Signature::fixed(&[Number, Number]),
+            modulo_type,
+            modulo,
+        ),
+        ScalarFunction::builtin(
+            "COALESCE",
+            Signature::variadic(&[Any], Any),
+            common_type,
+            |arguments| Ok(coalesce(arguments)),
+        )
+        .lenient()
+        .coercing_result(),
+        ScalarFunction::builtin("NULLIF", Signature::fixed(&[Any, Any]), common_type, nullif)
+            .lenient()
+            .coercing_result(),
+    ];
+    let date_parts = [
+        ("YEAR", DatePart::Year),
+        ("MONTH", DatePart::Month),
+        ("DAY", DatePart::Day),
+        ("HOUR", DatePart::Hour),
+        ("MINUTE", DatePart::Minute),
+        ("SECOND", DatePart::Second),
+    ];
+    functions.extend(date_parts.map(|(name, part)| {
+        ScalarFunction::builtin(
+            name,
+            Signature::fixed(&[Temporal]),
+            |_| Ok(Some(ColumnType::Int)),
+            move |arguments| Ok(date_part(arguments, part)),
+        )
+    }));
+    functions
+}
+
+fn modulo_type(types: &[Option<ColumnType>]) -> Result<Option<ColumnType>, TypeError> {
+    match (&types[0], &types[1]) {
+        (Some(left), Some(right)) => binary_type(BinaryOperator::Modulo, left, right).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn as_text(value: &DbType) -> &str {
+    match value {
+        DbType::Text(text) => text,
+        other => unreachable!("{} is not TEXT", other.type_name()),
+    }
+}
+
+fn as_integer(value: &DbType) -> i64 {
+    match value {
+        DbType::Int(value) => i64::from(*value),
+        DbType::BigInt(value) => *value,
+        other => unreachable!("{} is not an integer", other.type_name()),
+    }
+}
+
+fn upper(arguments: &[DbType]) -> DbType {
+    DbType::Text(as_text(&arguments[0]).to_uppercase())
+}
+
+fn lower(arguments: &[DbType]) -> DbType {
+    DbType::Text(as_text(&arguments[0]).to_lowercase())
+}
+
+fn trim(arguments: &[DbType]) -> DbType {
+    DbType::Text(as_text(&arguments[0]).trim().to_string())
+}
+
+fn length(arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+    let length = as_text(&arguments[0]).chars().count();
+    i32::try_from(length)
+        .map(DbType::Int)
+        .map_err(|_| EvaluationError::new("INT out of range"))
+}
+
+/// `SUBSTR(text, start[, length])` with a 1-based `start`. Positions before
+/// the first character count towards `length`, as in standard SQL.
+fn substr(arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+    let text = as_text(&arguments[0]);
+    let start = as_integer(&arguments[1]);
+    let end = match arguments.get(2).map(as_integer) {
+        Some(length) if length < 0 => {
+            return Err(EvaluationError::new("SUBSTR length must not be negative"))
+        }
+        Some(length) => start.saturating_add(length),
+        None => i64::MAX,
+    };
+    let skip = usize::try_from(start.max(1) - 1).unwrap_or(usize::MAX);
+    let take = usize::try_from(end.max(1) - start.max(1)).unwrap_or(usize::MAX);
+    Ok(DbType::Text(text.chars().skip(skip).take(take).collect()))
+}
+
+/// Joins the text of its arguments, skipping NULLs.
+fn concat(arguments: &[DbType]) -> DbType {
+    DbType::Text(
+        arguments
+            .iter()
+            .filter(|argument| !argument.is_null())
+            .map(ToString::to_string)
+            .collect(),
+    )
+}
+
+fn abs(arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+    let value = &arguments[0];
+    if compare(value, &DbType::Int(0))? == Some(Ordering::Less) {
+        negate(value)
+    } else {
+        Ok(value.clone())
+    }
+}
+
+/// `ROUND(number[, digits])` rounds half away from zero to `digits`
+/// fractional digits, 0 by default. Integers are returned unchanged.
+fn round(arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+    let digits = arguments.get(1).map_or(0, as_integer);
+    let digits = u32::try_from(digits)
+        .ok()
+        .filter(|digits| *digits <= MAX_SCALE)
+        .ok_or_else(|| {
+            EvaluationError::new(&format!("ROUND digits must be between 0 and {MAX_SCALE}"))
+        })?;
+    Ok(match &arguments[0] {
+        DbType::Float(value) => {
+            let factor = 10f64.powi(i32::try_from(digits).unwrap_or(i32::MAX));
+            DbType::Float((value * factor).round() / factor)
+        }
+        DbType::Decimal(value) => DbType::Decimal(
+            value
+                .rescale(digits.min(value.get_scale()))
+                .expect("dropping fractional digits cannot overflow"),
+        ),
+        value => value.clone(),
+    })
+}
+
+fn modulo(arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+    apply(BinaryOperator::Modulo, &arguments[0], &arguments[1])
+}
+
+fn coalesce(arguments: &[DbType]) -> DbType {
+    arguments
+        .iter()
+        .find(|argument| !argument.is_null())
+        .cloned()
+        .unwrap_or(DbType::Null)
+}
+
+/// NULL when both arguments are equal, otherwise the first one.
+fn nullif(arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+    if compare(&arguments[0], &arguments[1])? == Some(Ordering::Equal) {
+        Ok(DbType::Null)
+    } else {
+        Ok(arguments[0].clone())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DatePart {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+/// Extracts a field of a DATE or TIMESTAMP. A DATE is at midnight.
+fn date_part(arguments: &[DbType], part: DatePart) -> DbType {
+    let (date, time) = match &arguments[0] {
+        DbType::Date(date) => (*date, (0, 0, 0, 0)),
+        DbType::Timestamp(timestamp) => (timestamp.date(), timestamp.time()),
+        other => unreachable!("{} is not a DATE or TIMESTAMP", other.type_name()),
+    };
+    let (year, month, day) = date.to_ymd();
+    let (hour, minute, second, _) = time;
+    let component = |value: u32| i32::try_from(value).expect("date components fit in i32");
+    DbType::Int(match part {
+        DatePart::Year => year,
+        DatePart::Month => component(month),
+        DatePart::Day => component(day),
+        DatePart::Hour => component(hour),
+        DatePart::Minute => component(minute),
+        DatePart::Second => component(second),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date_time::{Date, Timestamp};
+    use crate::decimal::Decimal;
+
+    fn function(name: &str) -> ScalarFunction {
+        builtin_functions()
+            .into_iter()
+            .find(|function| function.get_name().eq_ignore_ascii_case(name))
+            .unwrap()
+    }
+
+    fn call(name: &str, arguments: &[DbType]) -> Result<DbType, EvaluationError> {
+        function(name).call(arguments)
+    }
+
+    fn text(value: &str) -> DbType {
+        DbType::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_string_functions() {
+        assert_eq!(call("upper", &[text("abc")]).unwrap(), text("ABC"));
+        assert_eq!(call("LENGTH", &[text("héllo")]).unwrap(), DbType::Int(5));
+        assert_eq!(call("TRIM", &[text("  x ")]).unwrap(), text("x"));
+        let substr = |arguments: &[DbType]| call("SUBSTR", arguments).unwrap();
+        assert_eq!(substr(&[text("hello"), DbType::Int(2)]), text("ello"));
+        assert_eq!(
+            substr(&[text("hello"), DbType::Int(2), DbType::Int(3)]),
+            text("ell")
+        );
+        assert_eq!(
+            substr(&[text("hello"), DbType::Int(0), DbType::Int(3)]),
+            text("he")
+        );
+        assert_eq!(
+            call("CONCAT", &[text("a"), DbType::Null, DbType::Int(1)]).unwrap(),
+            text("a1")
+        );
+        assert_eq!(call("UPPER", &[DbType::Null]).unwrap(), DbType::Null);
+    }
+
+    #[test]
+    fn test_numeric_and_null_functions() {
+        assert_eq!(call("ABS", &[DbType::Int(-3)]).unwrap(), DbType::Int(3));
+        let decimal = |text: &str| DbType::Decimal(Decimal::parse(text).unwrap());
+        assert_eq!(
+            call("ROUND", &[decimal("2.345"), DbType::Int(2)]).unwrap(),
+            decimal("2.35")
+        );
+        assert_eq!(
+            call("ROUND", &[DbType::Float(2.5)]).unwrap(),
+            DbType::Float(3.0)
+        );
+        assert_eq!(
+            call("MOD", &[DbType::Int(7), DbType::Int(3)]).unwrap(),
+            DbType::Int(1)
+        );
+        assert_eq!(
+            call("COALESCE", &[DbType::Null, DbType::Int(2), DbType::Int(3)]).unwrap(),
+            DbType::Int(2)
+        );
+        assert_eq!(
+            call("NULLIF", &[DbType::Int(1), DbType::BigInt(1)]).unwrap(),
+            DbType::Null
+        );
+        assert_eq!(
+            call("NULLIF", &[DbType::Int(1), DbType::Null]).unwrap(),
+            DbType::Int(1)
+        );
+    }
+
+    #[test]
+    fn test_date_parts() {
+        let timestamp = DbType::Timestamp(Timestamp::parse("2024-03-05 14:30:15").unwrap());
+        assert_eq!(
+            call("YEAR", std::slice::from_ref(&timestamp)).unwrap(),
+            DbType::Int(2024)
+        );
+        assert_eq!(call("MINUTE", &[timestamp]).unwrap(), DbType::Int(30));
+        let date = DbType::Date(Date::parse("2024-03-05").unwrap());
+        assert_eq!(
+            call("DAY", std::slice::from_ref(&date)).unwrap(),
+            DbType::Int(5)
+        );
+        assert_eq!(call("HOUR", &[date]).unwrap(), DbType::Int(0));
+    }
+
+    #[test]
+    fn test_arity_and_type_checks() {
+        let error = call("UPPER", &[text("a"), text("b")]).unwrap_err();
+        assert_eq!(error.message, "UPPER takes 1 argument, got 2");
+        let error = call("SUBSTR", &[text("a")]).unwrap_err();
+        assert_eq!(error.message, "SUBSTR takes 2 to 3 arguments, got 1");
+        let error = call("COALESCE", &[]).unwrap_err();
+        assert_eq!(error.message, "COALESCE takes at least 1 argument, got 0");
+        let error = call("LENGTH", &[DbType::Int(1)]).unwrap_err();
+        assert_eq!(error.message, "LENGTH argument 1 must be TEXT, found INT");
+        let substr = function("SUBSTR");
+        assert!(substr
+            .return_type(&[
+                Some(ColumnType::Text { max_length: None }),
+                Some(ColumnType::Float)
+            ])
+            .is_err());
+        assert_eq!(
+            function("MOD")
+                .return_type(&[Some(ColumnType::Int), Some(ColumnType::BigInt)])
+                .unwrap(),
+            Some(ColumnType::BigInt)
+        );
+    }
+
+    #[test]
+    fn test_strict_function_skips_null_arguments() {
+        let is_null = |arguments: &[DbType]| Ok(DbType::Bool(arguments[0].is_null()));
+        let lenient = ScalarFunction::new("is_null", is_null);
+        assert_eq!(lenient.call(&[DbType::Null]).unwrap(), DbType::Bool(true));
+        let strict = ScalarFunction::new("is_null", is_null).strict();
+        assert_eq!(strict.call(&[DbType::Null]).unwrap(), DbType::Null);
+        assert_eq!(strict.call(&[DbType::Int(1)]).unwrap(), DbType::Bool(false));
+    }
+}
@@ -1 +1,323 @@
+use crate::db_type::DbType;
+use crate::error::DbError;
+use std::collections::HashMap;
+use std::fmt;
 
+/// The largest DECIMAL precision whose values always fit in the `i128` units
+/// of a `Decimal`.
+pub(crate) const MAX_DECIMAL_PRECISION: u32 = 38;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnType {
+    Int,
+    BigInt,
+    Float,
+    Bool,
+    Text { max_length: Option<usize> },
+    Date,
+    Timestamp,
+    Decimal { precision: u32, scale: u32 },
+    Blob,
+}
+
+impl ColumnType {
+    /// The type of a value, or `None` for NULL. Text is unbounded and a
+    /// decimal gets the widest precision at its own scale.
+    pub(crate) fn of(value: &DbType) -> Option<ColumnType> {
+        Some(match value {
+            DbType::Null => return None,
+            DbType::Bool(_) => ColumnType::Bool,
+            DbType::Int(_) => ColumnType::Int,
+            DbType::BigInt(_) => ColumnType::BigInt,
+            DbType::Float(_) => ColumnType::Float,
+            DbType::Decimal(decimal) => ColumnType::Decimal {
+                precision: MAX_DECIMAL_PRECISION,
+                scale: decimal.get_scale(),
+            },
+            DbType::Text(_) => ColumnType::Text { max_length: None },
+            DbType::Date(_) => ColumnType::Date,
+            DbType::Timestamp(_) => ColumnType::Timestamp,
+            DbType::Blob(_) => ColumnType::Blob,
+        })
+    }
+
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::Int | ColumnType::BigInt | ColumnType::Float | ColumnType::Decimal { .. }
+        )
+    }
+
+    /// Checks that a non-null value can be stored in a column of this type,
+    /// describing the problem when it cannot. NULL is always accepted here;
+    /// nullability belongs to the column definition.
+    pub(crate) fn check(&self, value: &DbType) -> Result<(), String> {
+        let fits = match (self, value) {
+            (_, DbType::Null)
+            | (ColumnType::Int, DbType::Int(_))
+            | (ColumnType::BigInt, DbType::BigInt(_))
+            | (ColumnType::Float, DbType::Float(_))
+            | (ColumnType::Bool, DbType::Bool(_))
+            | (ColumnType::Date, DbType::Date(_))
+            | (ColumnType::Timestamp, DbType::Timestamp(_))
+            | (ColumnType::Blob, DbType::Blob(_)) => true,
+            (ColumnType::Text { max_length }, DbType::Text(text)) => {
+                max_length.is_none_or(|length| text.chars().count() <= length)
+            }
+            (ColumnType::Decimal { precision, scale }, DbType::Decimal(decimal)) => {
+                decimal.get_scale() == *scale
+                    && decimal.get_units().unsigned_abs() < 10u128.pow(*precision)
+            }
+            (column_type, value) => {
+                return Err(format!(
+                    "expects {column_type}, found {}",
+                    value.type_name()
+                ))
+            }
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(format!("value {value:?} does not fit {self}"))
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColumnType::Int => write!(f, "INT"),
+            ColumnType::BigInt => write!(f, "BIGINT"),
+            ColumnType::Float => write!(f, "FLOAT"),
+            ColumnType::Bool => write!(f, "BOOL"),
+            ColumnType::Text { max_length: None } => write!(f, "TEXT"),
+            ColumnType::Text {
+                max_length: Some(length),
+            } => write!(f, "VARCHAR({length})"),
+            ColumnType::Date => write!(f, "DATE"),
+            ColumnType::Timestamp => write!(f, "TIMESTAMP"),
+            ColumnType::Decimal { precision, scale } => {
+                write!(f, "DECIMAL({precision}, {scale})")
+            }
+            ColumnType::Blob => write!(f, "BLOB"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ColumnConstraint {
+    PrimaryKey,
+    Unique,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ColumnDef {
+    name: String,
+    column_type: ColumnType,
+    nullable: bool,
+    default: Option<DbType>,
+    constraints: Vec<ColumnConstraint>,
+}
+
+impl ColumnDef {
+    pub(crate) fn new(name: &str, column_type: ColumnType) -> Self {
+        ColumnDef {
+            name: name.to_string(),
+            column_type,
+            nullable: true,
+            default: None,
+            constraints: Vec::new(),
+        }
+    }
+
+    pub(crate) fn not_null(mut self) -> Self {
+        self.nullable = false;
+        self
+    }
+
+    pub(crate) fn with_default(mut self, default: DbType) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Adds a constraint; a primary key column is also made NOT NULL.
+    pub(crate) fn with_constraint(mut self, constraint: ColumnConstraint) -> Self {
+        if constraint == ColumnConstraint::PrimaryKey {
+            self.nullable = false;
+        }
+        if !self.constraints.contains(&constraint) {
+            self.constraints.push(constraint);
+        }
+        self
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn get_type(&self) -> &ColumnType {
+        &self.column_type
+    }
+
+    pub(crate) fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    pub(crate) fn get_default(&self) -> Option<&DbType> {
+        self.default.as_ref()
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn get_constraints(&self) -> &Vec<ColumnConstraint> {
+        &self.constraints
+    }
+
+    pub(crate) fn has_constraint(&self, constraint: &ColumnConstraint) -> bool {
+        self.constraints.contains(constraint)
+    }
+
+    /// Checks a value against the column's nullability and type.
+    pub(crate) fn check(&self, value: &DbType) -> Result<(), DbError> {
+        if value.is_null() && !self.nullable {
+            return Err(DbError::Constraint(format!(
+                "column '{}' cannot be NULL",
+                self.name
+            )));
+        }
+        self.column_type
+            .check(value)
+            .map_err(|message| DbError::Type(format!("column '{}' {message}", self.name)))
+    }
+}
+
+/// The ordered column definitions of a table.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Schema {
+    columns: Vec<ColumnDef>,
+    column_names: Vec<String>,
+}
+
+impl Schema {
+    pub(crate) fn new(columns: Vec<ColumnDef>) -> Self {
+        let column_names = columns.iter().map(|column| column.name.clone()).collect();
+        Schema {
+            columns,
+            column_names,
+        }
+    }
+
+    pub(crate) fn get_columns(&self) -> &Vec<ColumnDef> {
+        &self.columns
+    }
+
+    pub(crate) fn get_column_names(&self) -> &Vec<String> {
+        &self.column_names
+    }
+
+    pub(crate) fn get_column(&self, name: &str) -> Option<&ColumnDef> {
+        self.position(name).map(|index| &self.columns[index])
+    }
+
+    /// The type of every column, keyed by column name.
+    pub(crate) fn get_column_types(&self) -> HashMap<String, ColumnType> {
+        self.columns
+            .iter()
+            .map(|column| (column.name.clone(), column.column_type.clone()))
+            .collect()
+    }
+
+    pub(crate) fn position(&self, name: &str) -> Option<usize> {
+        self.column_names.iter().position(|c| c == name)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Indexes of the columns that have the given constraint, in column order.
+    pub(crate) fn indexes_with(&self, constraint: &ColumnConstraint) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.has_constraint(constraint))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub(crate) fn add_constraint(&mut self, name: &str, constraint: ColumnConstraint) {
+        if let Some(index) = self.position(name) {
+            let column = self.columns[index].clone();
+            self.columns[index] = column.with_constraint(constraint);
+        }
+    }
+
+    pub(crate) fn set_not_null(&mut self, name: &str) {
+        if let Some(index) = self.position(name) {
+            self.columns[index].nullable = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::Decimal;
+
+    #[test]
+    fn test_check_value_types() {
+        assert!(ColumnType::Int.check(&DbType::Int(1)).is_ok());
+        assert!(ColumnType::Int.check(&DbType::Null).is_ok());
+        assert_eq!(
+            ColumnType::BigInt.check(&DbType::Int(1)).unwrap_err(),
+            "expects BIGINT, found INT"
+        );
+        let varchar = ColumnType::Text {
+            max_length: Some(3),
+        };
+        assert!(varchar.check(&DbType::Text("abc".to_string())).is_ok());
+        assert_eq!(
+            varchar
+                .check(&DbType::Text("abcd".to_string()))
+                .unwrap_err(),
+            "value Text(\"abcd\") does not fit VARCHAR(3)"
+        );
+    }
+
+    #[test]
+    fn test_check_decimal_precision_and_scale() {
+        let price = ColumnType::Decimal {
+            precision: 4,
+            scale: 2,
+        };
+        let decimal = |text: &str| DbType::Decimal(Decimal::parse(text).unwrap());
+        assert!(price.check(&decimal("99.99")).is_ok());
+        assert!(price.check(&decimal("-10.00")).is_ok());
+        assert!(price.check(&decimal("100.00")).is_err());
+        assert!(price.check(&decimal("1.5")).is_err());
+    }
+
+    #[test]
+    fn test_column_def() {
+        let id =
+            ColumnDef::new("id", ColumnType::Int).with_constraint(ColumnConstraint::PrimaryKey);
+        assert!(!id.is_nullable());
+        assert_eq!(
+            id.check(&DbType::Null).unwrap_err(),
+            DbError::Constraint("column 'id' cannot be NULL".to_string())
+        );
+        let schema = Schema::new(vec![
+            id,
+            ColumnDef::new("name", ColumnType::Text { max_length: None })
+                .with_default(DbType::Text("anonymous".to_string())),
+        ]);
+        assert_eq!(
+            schema.get_column_names(),
+            &vec!["id".to_string(), "name".to_string()]
+        );
+        assert_eq!(schema.indexes_with(&ColumnConstraint::PrimaryKey), vec![0]);
+        assert_eq!(
+            schema.get_column("name").unwrap().get_default(),
+            Some(&DbType::Text("anonymous".to_string()))
+        );
+    }
+}
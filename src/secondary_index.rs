@@ -0,0 +1,176 @@
+use crate::db_type::DbType;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// The values of an index's leading column that a scan visits.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum KeyRange {
+    Between(Bound<DbType>, Bound<DbType>),
+    /// Text values starting with the given string.
+    Prefix(String),
+}
+
+impl KeyRange {
+    pub(crate) fn equal_to(value: DbType) -> Self {
+        KeyRange::Between(Bound::Included(value.clone()), Bound::Included(value))
+    }
+
+    /// The values bounding the range.
+    pub(crate) fn get_values(&self) -> Vec<DbType> {
+        match self {
+            KeyRange::Between(low, high) => [low, high]
+                .into_iter()
+                .filter_map(|bound| match bound {
+                    Bound::Included(value) | Bound::Excluded(value) => Some(value.clone()),
+                    Bound::Unbounded => None,
+                })
+                .collect(),
+            KeyRange::Prefix(prefix) => vec![DbType::Text(prefix.clone())],
+        }
+    }
+
+    /// Where a scan starts: keys before it are skipped as below the range.
+    fn start(&self) -> Bound<DbType> {
+        match self {
+            KeyRange::Between(low, _) => low.clone(),
+            KeyRange::Prefix(prefix) => Bound::Included(DbType::Text(prefix.clone())),
+        }
+    }
+
+    /// Whether `value` and every value after it lie beyond the range.
+    fn is_past(&self, value: &DbType) -> bool {
+        match self {
+            KeyRange::Between(_, Bound::Included(high)) => value > high,
+            KeyRange::Between(_, Bound::Excluded(high)) => value >= high,
+            KeyRange::Between(_, Bound::Unbounded) => false,
+            KeyRange::Prefix(prefix) => {
+                !matches!(value, DbType::Text(text) if text.starts_with(prefix.as_str()))
+            }
+        }
+    }
+}
+
+/// An ordered index over one or more columns of a table, mapping each
+/// combination of their values to the positions of the rows holding it.
+/// Scans are driven by the leading column.
+#[derive(Clone, Debug)]
+pub(crate) struct SecondaryIndex {
+    name: String,
+    columns: Vec<usize>,
+    entries: BTreeMap<Vec<DbType>, Vec<usize>>,
+}
+
+impl SecondaryIndex {
+    pub(crate) fn new(name: &str, columns: Vec<usize>) -> Self {
+        SecondaryIndex {
+            name: name.to_string(),
+            columns,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn get_columns(&self) -> &Vec<usize> {
+        &self.columns
+    }
+
+    pub(crate) fn add(&mut self, row: &[DbType], position: usize) {
+        self.entries
+            .entry(self.key_of(row))
+            .or_default()
+            .push(position);
+    }
+
+    pub(crate) fn remove(&mut self, row: &[DbType], position: usize) {
+        let key = self.key_of(row);
+        if let Some(positions) = self.entries.get_mut(&key) {
+            positions.retain(|&p| p != position);
+            if positions.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The positions, in ascending order, of the rows whose leading column
+    /// lies in `range`. NULLs never match a range.
+    pub(crate) fn scan(&self, range: &KeyRange) -> Vec<usize> {
+        let start = range.start();
+        let from = match &start {
+            Bound::Included(value) | Bound::Excluded(value) => Bound::Included(vec![value.clone()]),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let mut positions: Vec<usize> = self
+            .entries
+            .range::<Vec<DbType>, _>((from, Bound::Unbounded))
+            .skip_while(|(key, _)| {
+                key[0].is_null() || matches!(&start, Bound::Excluded(low) if key[0] == *low)
+            })
+            .take_while(|(key, _)| !range.is_past(&key[0]))
+            .flat_map(|(_, positions)| positions.iter().copied())
+            .collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    fn key_of(&self, row: &[DbType]) -> Vec<DbType> {
+        self.columns
+            .iter()
+            .map(|&index| row[index].clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> DbType {
+        DbType::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_range_scans() {
+        let mut index = SecondaryIndex::new("by_age", vec![1]);
+        for (position, age) in [30, 25, 30, 41].into_iter().enumerate() {
+            index.add(&[DbType::Int(0), DbType::Int(age)], position);
+        }
+        index.add(&[DbType::Int(0), DbType::Null], 4);
+        assert_eq!(index.scan(&KeyRange::equal_to(DbType::Int(30))), vec![0, 2]);
+        let above = KeyRange::Between(Bound::Excluded(DbType::Int(30)), Bound::Unbounded);
+        assert_eq!(index.scan(&above), vec![3]);
+        let below = KeyRange::Between(Bound::Unbounded, Bound::Excluded(DbType::Int(30)));
+        assert_eq!(index.scan(&below), vec![1]);
+        let between = KeyRange::Between(
+            Bound::Included(DbType::Int(26)),
+            Bound::Included(DbType::Int(41)),
+        );
+        assert_eq!(index.scan(&between), vec![0, 2, 3]);
+        index.remove(&[DbType::Int(0), DbType::Int(30)], 0);
+        assert_eq!(index.scan(&KeyRange::equal_to(DbType::Int(30))), vec![2]);
+    }
+
+    #[test]
+    fn test_prefix_scan_on_composite_index() {
+        let mut index = SecondaryIndex::new("by_name", vec![0, 1]);
+        for (position, (name, id)) in [("Bob", 1), ("Alice", 2), ("Al", 3), ("Bo", 4), ("Alice", 5)]
+            .into_iter()
+            .enumerate()
+        {
+            index.add(&[text(name), DbType::Int(id)], position);
+        }
+        assert_eq!(
+            index.scan(&KeyRange::Prefix("Al".to_string())),
+            vec![1, 2, 4]
+        );
+        assert_eq!(index.scan(&KeyRange::Prefix("Bob".to_string())), vec![0]);
+        assert!(index.scan(&KeyRange::Prefix("C".to_string())).is_empty());
+        assert_eq!(index.scan(&KeyRange::equal_to(text("Alice"))), vec![1, 4]);
+    }
+}
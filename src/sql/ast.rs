@@ -0,0 +1,159 @@
+use crate::condition::Operator;
+use crate::db_type::DbType;
+use crate::expression::BinaryOperator;
+use crate::rational_algebra::join::JoinKind;
+use crate::schema_struct::{ColumnDef, ColumnType};
+
+#[derive(Clone, Debug)]
+pub(crate) enum Statement {
+    Select(Box<SelectStatement>),
+    Insert(InsertStatement),
+    CreateTable(CreateTableStatement),
+    CreateIndex(CreateIndexStatement),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SelectStatement {
+    pub(crate) distinct: bool,
+    pub(crate) items: Vec<SelectItem>,
+    pub(crate) from: TableReference,
+    pub(crate) joins: Vec<Join>,
+    pub(crate) selection: Option<Expression>,
+    pub(crate) group_by: Vec<ColumnRef>,
+    pub(crate) having: Option<Expression>,
+    pub(crate) order_by: Vec<OrderByItem>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum SelectItem {
+    Wildcard,
+    Expression {
+        expression: Operand,
+        alias: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum FunctionArguments {
+    Star,
+    List(Vec<Operand>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ColumnRef {
+    pub(crate) table: Option<String>,
+    pub(crate) name: String,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TableReference {
+    pub(crate) name: String,
+    pub(crate) alias: Option<String>,
+}
+
+impl TableReference {
+    pub(crate) fn reference_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Join {
+    pub(crate) kind: JoinKind,
+    pub(crate) table: TableReference,
+    pub(crate) constraint: JoinConstraint,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum JoinConstraint {
+    On(Expression),
+    Using(Vec<String>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Expression {
+    Comparison {
+        left: Operand,
+        operator: Operator,
+        right: Operand,
+    },
+    IsNull {
+        column: ColumnRef,
+        negated: bool,
+    },
+    In {
+        operand: Operand,
+        list: Vec<Operand>,
+        negated: bool,
+    },
+    Between {
+        operand: Operand,
+        low: Operand,
+        high: Operand,
+        negated: bool,
+    },
+    Like {
+        operand: Operand,
+        pattern: String,
+        case_insensitive: bool,
+        negated: bool,
+    },
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+/// A scalar expression. Whether a function call is an aggregate or a scalar
+/// function is left to the planner.
+#[derive(Clone, Debug)]
+pub(crate) enum Operand {
+    Column(ColumnRef),
+    Literal(DbType),
+    Function {
+        name: String,
+        arguments: FunctionArguments,
+    },
+    Cast {
+        operand: Box<Operand>,
+        target: ColumnType,
+    },
+    Binary {
+        left: Box<Operand>,
+        operator: BinaryOperator,
+        right: Box<Operand>,
+    },
+    Negate(Box<Operand>),
+    Case {
+        branches: Vec<(Expression, Operand)>,
+        otherwise: Option<Box<Operand>>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct OrderByItem {
+    pub(crate) column: ColumnRef,
+    pub(crate) ascending: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct InsertStatement {
+    pub(crate) table: String,
+    pub(crate) columns: Option<Vec<String>>,
+    pub(crate) rows: Vec<Vec<DbType>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CreateTableStatement {
+    pub(crate) name: String,
+    pub(crate) columns: Vec<ColumnDef>,
+    pub(crate) primary_key: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CreateIndexStatement {
+    pub(crate) name: String,
+    pub(crate) table: String,
+    pub(crate) columns: Vec<String>,
+}
@@ -0,0 +1,541 @@
+use crate::sql::parser::ParseError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Keyword {
+    Select,
+    Distinct,
+    From,
+    Join,
+    Inner,
+    Left,
+    Right,
+    Full,
+    Outer,
+    On,
+    Using,
+    Where,
+    And,
+    Or,
+    Not,
+    Is,
+    Null,
+    Group,
+    Having,
+    Order,
+    By,
+    Asc,
+    Desc,
+    Limit,
+    Offset,
+    As,
+    Insert,
+    Into,
+    Values,
+    Create,
+    Table,
+    Index,
+    Primary,
+    Key,
+    Int,
+    Integer,
+    Float,
+    Real,
+    Double,
+    Text,
+    Varchar,
+    Bigint,
+    Boolean,
+    Bool,
+    Date,
+    Timestamp,
+    Decimal,
+    Numeric,
+    Blob,
+    True,
+    False,
+    Default,
+    Unique,
+    Cast,
+    Case,
+    When,
+    Then,
+    Else,
+    End,
+    In,
+    Between,
+    Like,
+    Ilike,
+}
+
+impl Keyword {
+    fn from_word(word: &str) -> Option<Keyword> {
+        let keyword = match word.to_ascii_uppercase().as_str() {
+            "SELECT" => Keyword::Select,
+            "DISTINCT" => Keyword::Distinct,
+            "FROM" => Keyword::From,
+            "JOIN" => Keyword::Join,
+            "INNER" => Keyword::Inner,
+            "LEFT" => Keyword::Left,
+            "RIGHT" => Keyword::Right,
+            "FULL" => Keyword::Full,
+            "OUTER" => Keyword::Outer,
+            "ON" => Keyword::On,
+            "USING" => Keyword::Using,
+            "WHERE" => Keyword::Where,
+            "AND" => Keyword::And,
+            "OR" => Keyword::Or,
+            "NOT" => Keyword::Not,
+            "IS" => Keyword::Is,
+            "NULL" => Keyword::Null,
+            "GROUP" => Keyword::Group,
+            "HAVING" => Keyword::Having,
+            "ORDER" => Keyword::Order,
+            "BY" => Keyword::By,
+            "ASC" => Keyword::Asc,
+            "DESC" => Keyword::Desc,
+            "LIMIT" => Keyword::Limit,
+            "OFFSET" => Keyword::Offset,
+            "AS" => Keyword::As,
+            "INSERT" => Keyword::Insert,
+            "INTO" => Keyword::Into,
+            "VALUES" => Keyword::Values,
+            "CREATE" => Keyword::Create,
+            "TABLE" => Keyword::Table,
+            "INDEX" => Keyword::Index,
+            "PRIMARY" => Keyword::Primary,
+            "KEY" => Keyword::Key,
+            "INT" => Keyword::Int,
+            "INTEGER" => Keyword::Integer,
+            "FLOAT" => Keyword::Float,
+            "REAL" => Keyword::Real,
+            "DOUBLE" => Keyword::Double,
+            "TEXT" => Keyword::Text,
+            "VARCHAR" => Keyword::Varchar,
+            "BIGINT" => Keyword::Bigint,
+            "BOOLEAN" => Keyword::Boolean,
+            "BOOL" => Keyword::Bool,
+            "DATE" => Keyword::Date,
+            "TIMESTAMP" => Keyword::Timestamp,
+            "DECIMAL" => Keyword::Decimal,
+            "NUMERIC" => Keyword::Numeric,
+            "BLOB" => Keyword::Blob,
+            "TRUE" => Keyword::True,
+            "FALSE" => Keyword::False,
+            "DEFAULT" => Keyword::Default,
+            "UNIQUE" => Keyword::Unique,
+            "CAST" => Keyword::Cast,
+            "CASE" => Keyword::Case,
+            "WHEN" => Keyword::When,
+            "THEN" => Keyword::Then,
+            "ELSE" => Keyword::Else,
+            "END" => Keyword::End,
+            "IN" => Keyword::In,
+            "BETWEEN" => Keyword::Between,
+            "LIKE" => Keyword::Like,
+            "ILIKE" => Keyword::Ilike,
+            _ => return None,
+        };
+        Some(keyword)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TokenKind {
+    Keyword(Keyword),
+    Identifier(String),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Blob(Vec<u8>),
+    Comma,
+    Dot,
+    Semicolon,
+    LeftParen,
+    RightParen,
+    Asterisk,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Concat,
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEquals,
+    GreaterThan,
+    GreaterThanOrEquals,
+    EndOfInput,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Position {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    pub(crate) position: Position,
+}
+
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer {
+        chars: input.chars().collect(),
+        index: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let done = token.kind == TokenKind::EndOfInput;
+        tokens.push(token);
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let current = self.peek()?;
+        self.index += 1;
+        if current == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(current)
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(current) = self.peek() {
+            if current.is_whitespace() {
+                self.bump();
+            } else if current == '-' && self.chars.get(self.index + 1) == Some(&'-') {
+                while let Some(current) = self.bump() {
+                    if current == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_whitespace_and_comments();
+        let position = self.position();
+        let Some(current) = self.bump() else {
+            return Ok(Token {
+                kind: TokenKind::EndOfInput,
+                position,
+            });
+        };
+        let kind = match current {
+            ',' => TokenKind::Comma,
+            '.' => TokenKind::Dot,
+            ';' => TokenKind::Semicolon,
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            '*' => TokenKind::Asterisk,
+            '+' => TokenKind::Plus,
+            '-' => TokenKind::Minus,
+            '/' => TokenKind::Slash,
+            '%' => TokenKind::Percent,
+            '|' if self.peek() == Some('|') => {
+                self.bump();
+                TokenKind::Concat
+            }
+            '=' => TokenKind::Equals,
+            '<' if self.peek() == Some('>') => {
+                self.bump();
+                TokenKind::NotEquals
+            }
+            '<' if self.peek() == Some('=') => {
+                self.bump();
+                TokenKind::LessThanOrEquals
+            }
+            '<' => TokenKind::LessThan,
+            '>' if self.peek() == Some('=') => {
+                self.bump();
+                TokenKind::GreaterThanOrEquals
+            }
+            '>' => TokenKind::GreaterThan,
+            '!' if self.peek() == Some('=') => {
+                self.bump();
+                TokenKind::NotEquals
+            }
+            '\'' => self.string_literal(position)?,
+            '"' => TokenKind::Identifier(self.quoted_identifier(position)?),
+            c if c.is_ascii_digit() => self.number(c, position)?,
+            'x' | 'X' if self.peek() == Some('\'') => {
+                self.bump();
+                self.blob_literal(position)?
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let word = self.word(c);
+                match Keyword::from_word(&word) {
+                    Some(keyword) => TokenKind::Keyword(keyword),
+                    None => TokenKind::Identifier(word),
+                }
+            }
+            other => {
+                return Err(ParseError::new(
+                    format!("unexpected character '{other}'"),
+                    position,
+                ))
+            }
+        };
+        Ok(Token { kind, position })
+    }
+
+    fn string_literal(&mut self, start: Position) -> Result<TokenKind, ParseError> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') if self.peek() == Some('\'') => {
+                    self.bump();
+                    value.push('\'');
+                }
+                Some('\'') => return Ok(TokenKind::String(value)),
+                Some(current) => value.push(current),
+                None => {
+                    return Err(ParseError::new(
+                        "unterminated string literal".to_string(),
+                        start,
+                    ))
+                }
+            }
+        }
+    }
+
+    fn blob_literal(&mut self, start: Position) -> Result<TokenKind, ParseError> {
+        let TokenKind::String(hex) = self.string_literal(start)? else {
+            unreachable!("string_literal always returns a string token")
+        };
+        let invalid = || ParseError::new(format!("invalid blob literal X'{hex}'"), start);
+        if hex.len() % 2 != 0 {
+            return Err(invalid());
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| {
+                hex.get(index..index + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(invalid)
+            })
+            .collect::<Result<Vec<u8>, ParseError>>()
+            .map(TokenKind::Blob)
+    }
+
+    fn quoted_identifier(&mut self, start: Position) -> Result<String, ParseError> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(value),
+                Some(current) => value.push(current),
+                None => {
+                    return Err(ParseError::new(
+                        "unterminated quoted identifier".to_string(),
+                        start,
+                    ))
+                }
+            }
+        }
+    }
+
+    fn number(&mut self, first: char, start: Position) -> Result<TokenKind, ParseError> {
+        let mut digits = String::from(first);
+        while let Some(current) = self.peek() {
+            if !current.is_ascii_digit() {
+                break;
+            }
+            digits.push(current);
+            self.bump();
+        }
+        let is_fraction = self.peek() == Some('.')
+            && self
+                .chars
+                .get(self.index + 1)
+                .is_some_and(char::is_ascii_digit);
+        if is_fraction {
+            self.bump();
+            digits.push('.');
+            while let Some(current) = self.peek() {
+                if !current.is_ascii_digit() {
+                    break;
+                }
+                digits.push(current);
+                self.bump();
+            }
+            return digits.parse().map(TokenKind::Float).map_err(|_| {
+                ParseError::new(format!("invalid numeric literal '{digits}'"), start)
+            });
+        }
+        digits
+            .parse()
+            .map(TokenKind::Integer)
+            .map_err(|_| ParseError::new(format!("integer literal '{digits}' is too large"), start))
+    }
+
+    fn word(&mut self, first: char) -> String {
+        let mut word = String::from(first);
+        while let Some(current) = self.peek() {
+            if !(current.is_alphanumeric() || current == '_') {
+                break;
+            }
+            word.push(current);
+            self.bump();
+        }
+        word
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_select() {
+        assert_eq!(
+            kinds("select id FROM users where age <> 18;"),
+            vec![
+                TokenKind::Keyword(Keyword::Select),
+                TokenKind::Identifier("id".to_string()),
+                TokenKind::Keyword(Keyword::From),
+                TokenKind::Identifier("users".to_string()),
+                TokenKind::Keyword(Keyword::Where),
+                TokenKind::Identifier("age".to_string()),
+                TokenKind::NotEquals,
+                TokenKind::Integer(18),
+                TokenKind::Semicolon,
+                TokenKind::EndOfInput,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_arithmetic_operators() {
+        assert_eq!(
+            kinds("a+b-c*d/e%f||g"),
+            vec![
+                TokenKind::Identifier("a".to_string()),
+                TokenKind::Plus,
+                TokenKind::Identifier("b".to_string()),
+                TokenKind::Minus,
+                TokenKind::Identifier("c".to_string()),
+                TokenKind::Asterisk,
+                TokenKind::Identifier("d".to_string()),
+                TokenKind::Slash,
+                TokenKind::Identifier("e".to_string()),
+                TokenKind::Percent,
+                TokenKind::Identifier("f".to_string()),
+                TokenKind::Concat,
+                TokenKind::Identifier("g".to_string()),
+                TokenKind::EndOfInput,
+            ]
+        );
+        assert!(tokenize("a | b").is_err());
+    }
+
+    #[test]
+    fn test_tokenize_comparison_operators() {
+        assert_eq!(
+            kinds("<= >= <> < > != ="),
+            vec![
+                TokenKind::LessThanOrEquals,
+                TokenKind::GreaterThanOrEquals,
+                TokenKind::NotEquals,
+                TokenKind::LessThan,
+                TokenKind::GreaterThan,
+                TokenKind::NotEquals,
+                TokenKind::Equals,
+                TokenKind::EndOfInput,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_float() {
+        assert_eq!(
+            kinds("2.5 t.x"),
+            vec![
+                TokenKind::Float(2.5),
+                TokenKind::Identifier("t".to_string()),
+                TokenKind::Dot,
+                TokenKind::Identifier("x".to_string()),
+                TokenKind::EndOfInput,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_string_with_escaped_quote() {
+        assert_eq!(
+            kinds("'it''s'"),
+            vec![TokenKind::String("it's".to_string()), TokenKind::EndOfInput]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_blob_literal() {
+        assert_eq!(
+            kinds("X'0aFF' x''"),
+            vec![
+                TokenKind::Blob(vec![0x0a, 0xff]),
+                TokenKind::Blob(Vec::new()),
+                TokenKind::EndOfInput
+            ]
+        );
+        assert!(tokenize("X'abc'").is_err());
+        assert!(tokenize("X'zz'").is_err());
+    }
+
+    #[test]
+    fn test_token_positions() {
+        let tokens = tokenize("SELECT\n  name").unwrap();
+        assert_eq!(tokens[1].position, Position { line: 2, column: 3 });
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let error = tokenize("SELECT 'abc").unwrap_err();
+        assert_eq!(error.position, Position { line: 1, column: 8 });
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        let error = tokenize("SELECT #").unwrap_err();
+        assert_eq!(error.message, "unexpected character '#'");
+    }
+}
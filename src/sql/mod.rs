@@ -0,0 +1,40 @@
+pub(crate) mod ast;
+pub(crate) mod lexer;
+pub(crate) mod parser;
+pub(crate) mod planner;
+
+use crate::error::DbError;
+use crate::sql::parser::ParseError;
+use std::error::Error;
+use std::fmt;
+
+/// Why a statement failed: its text did not parse, or it parsed but could
+/// not be planned or run.
+#[derive(Debug)]
+pub enum SqlError {
+    Parse(ParseError),
+    Execution(DbError),
+}
+
+impl Error for SqlError {}
+
+impl fmt::Display for SqlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SqlError::Parse(error) => error.fmt(f),
+            SqlError::Execution(error) => error.fmt(f),
+        }
+    }
+}
+
+impl From<ParseError> for SqlError {
+    fn from(error: ParseError) -> Self {
+        SqlError::Parse(error)
+    }
+}
+
+impl From<DbError> for SqlError {
+    fn from(error: DbError) -> Self {
+        SqlError::Execution(error)
+    }
+}
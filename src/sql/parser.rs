@@ -0,0 +1,1294 @@
+use crate::condition::Operator;
+use crate::date_time::{Date, Timestamp};
+use crate::db_type::DbType;
+use crate::decimal::{Decimal, MAX_SCALE};
+use crate::expression::BinaryOperator;
+use crate::rational_algebra::join::JoinKind;
+use crate::schema_struct::{ColumnConstraint, ColumnDef, ColumnType, MAX_DECIMAL_PRECISION};
+use crate::sql::ast::{
+    ColumnRef, CreateIndexStatement, CreateTableStatement, Expression, FunctionArguments,
+    InsertStatement, Join, JoinConstraint, Operand, OrderByItem, SelectItem, SelectStatement,
+    Statement, TableReference,
+};
+use crate::sql::lexer::{tokenize, Keyword, Position, Token, TokenKind};
+use std::error::Error;
+use std::fmt;
+
+pub(crate) fn parse(sql: &str) -> Result<Statement, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        index: 0,
+    };
+    let statement = parser.statement()?;
+    parser.consume(&TokenKind::Semicolon);
+    parser.expect(&TokenKind::EndOfInput, "end of input")?;
+    Ok(statement)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    fn peek_kind_at(&self, offset: usize) -> &TokenKind {
+        let index = (self.index + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].kind
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.index].clone();
+        if token.kind != TokenKind::EndOfInput {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn consume(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_keyword(&mut self, keyword: Keyword) -> bool {
+        self.consume(&TokenKind::Keyword(keyword))
+    }
+
+    fn expect(&mut self, kind: &TokenKind, expected: &str) -> Result<Token, ParseError> {
+        if &self.peek().kind == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: Keyword) -> Result<Token, ParseError> {
+        let expected = format!("{keyword:?}").to_ascii_uppercase();
+        self.expect(&TokenKind::Keyword(keyword), &expected)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let token = self.peek();
+        let found = match &token.kind {
+            TokenKind::EndOfInput => "end of input".to_string(),
+            other => format!("{other:?}"),
+        };
+        ParseError::new(
+            format!("expected {expected}, found {found}"),
+            token.position,
+        )
+    }
+
+    fn identifier(&mut self) -> Result<String, ParseError> {
+        if let TokenKind::Identifier(name) = &self.peek().kind {
+            let name = name.clone();
+            self.advance();
+            Ok(name)
+        } else {
+            Err(self.unexpected("identifier"))
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        match &self.peek().kind {
+            TokenKind::Keyword(Keyword::Select) => Ok(Statement::Select(Box::new(self.select()?))),
+            TokenKind::Keyword(Keyword::Insert) => Ok(Statement::Insert(self.insert()?)),
+            TokenKind::Keyword(Keyword::Create) => match self.peek_kind_at(1) {
+                TokenKind::Keyword(Keyword::Index) => {
+                    Ok(Statement::CreateIndex(self.create_index()?))
+                }
+                _ => Ok(Statement::CreateTable(self.create_table()?)),
+            },
+            _ => Err(self.unexpected("SELECT, INSERT or CREATE")),
+        }
+    }
+
+    fn select(&mut self) -> Result<SelectStatement, ParseError> {
+        self.expect_keyword(Keyword::Select)?;
+        let distinct = self.consume_keyword(Keyword::Distinct);
+        let mut items = vec![self.select_item()?];
+        while self.consume(&TokenKind::Comma) {
+            items.push(self.select_item()?);
+        }
+        self.expect_keyword(Keyword::From)?;
+        let from = self.table_reference()?;
+        let mut joins = Vec::new();
+        while matches!(
+            self.peek().kind,
+            TokenKind::Keyword(
+                Keyword::Join | Keyword::Inner | Keyword::Left | Keyword::Right | Keyword::Full
+            )
+        ) {
+            joins.push(self.join()?);
+        }
+        let selection = if self.consume_keyword(Keyword::Where) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        let mut group_by = Vec::new();
+        if self.consume_keyword(Keyword::Group) {
+            self.expect_keyword(Keyword::By)?;
+            group_by.push(self.column_ref()?);
+            while self.consume(&TokenKind::Comma) {
+                group_by.push(self.column_ref()?);
+            }
+        }
+        let having = if self.consume_keyword(Keyword::Having) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        let mut order_by = Vec::new();
+        if self.consume_keyword(Keyword::Order) {
+            self.expect_keyword(Keyword::By)?;
+            order_by.push(self.order_by_item()?);
+            while self.consume(&TokenKind::Comma) {
+                order_by.push(self.order_by_item()?);
+            }
+        }
+        let limit = if self.consume_keyword(Keyword::Limit) {
+            Some(self.row_count()?)
+        } else {
+            None
+        };
+        let offset = if self.consume_keyword(Keyword::Offset) {
+            self.row_count()?
+        } else {
+            0
+        };
+        Ok(SelectStatement {
+            distinct,
+            items,
+            from,
+            joins,
+            selection,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    fn select_item(&mut self) -> Result<SelectItem, ParseError> {
+        if self.consume(&TokenKind::Asterisk) {
+            return Ok(SelectItem::Wildcard);
+        }
+        let expression = self.operand()?;
+        let alias = self.alias()?;
+        Ok(SelectItem::Expression { expression, alias })
+    }
+
+    fn cast(&mut self) -> Result<Operand, ParseError> {
+        self.expect_keyword(Keyword::Cast)?;
+        self.expect(&TokenKind::LeftParen, "'('")?;
+        let operand = self.operand()?;
+        self.expect_keyword(Keyword::As)?;
+        let target = self.column_type()?;
+        self.expect(&TokenKind::RightParen, "')'")?;
+        Ok(Operand::Cast {
+            operand: Box::new(operand),
+            target,
+        })
+    }
+
+    /// Parses `CASE WHEN condition THEN result ... [ELSE result] END`, and the
+    /// short form `CASE operand WHEN value THEN result ... END`, which compares
+    /// the operand with each value for equality.
+    fn case(&mut self) -> Result<Operand, ParseError> {
+        self.expect_keyword(Keyword::Case)?;
+        let subject = if self.peek().kind == TokenKind::Keyword(Keyword::When) {
+            None
+        } else {
+            Some(self.operand()?)
+        };
+        let mut branches = Vec::new();
+        while self.consume_keyword(Keyword::When) {
+            let condition = match &subject {
+                Some(subject) => Expression::Comparison {
+                    left: subject.clone(),
+                    operator: Operator::Equals,
+                    right: self.operand()?,
+                },
+                None => self.expression()?,
+            };
+            self.expect_keyword(Keyword::Then)?;
+            branches.push((condition, self.operand()?));
+        }
+        if branches.is_empty() {
+            return Err(self.unexpected("WHEN"));
+        }
+        let otherwise = if self.consume_keyword(Keyword::Else) {
+            Some(Box::new(self.operand()?))
+        } else {
+            None
+        };
+        self.expect_keyword(Keyword::End)?;
+        Ok(Operand::Case {
+            branches,
+            otherwise,
+        })
+    }
+
+    fn is_function_call(&self) -> bool {
+        matches!(self.peek().kind, TokenKind::Identifier(_))
+            && self.peek_kind_at(1) == &TokenKind::LeftParen
+    }
+
+    fn function_call(&mut self) -> Result<Operand, ParseError> {
+        let name = self.identifier()?.to_ascii_uppercase();
+        self.expect(&TokenKind::LeftParen, "'('")?;
+        let arguments = if self.consume(&TokenKind::Asterisk) {
+            FunctionArguments::Star
+        } else if self.peek().kind == TokenKind::RightParen {
+            FunctionArguments::List(Vec::new())
+        } else {
+            let mut arguments = vec![self.operand()?];
+            while self.consume(&TokenKind::Comma) {
+                arguments.push(self.operand()?);
+            }
+            FunctionArguments::List(arguments)
+        };
+        self.expect(&TokenKind::RightParen, "')'")?;
+        Ok(Operand::Function { name, arguments })
+    }
+
+    fn alias(&mut self) -> Result<Option<String>, ParseError> {
+        if self.consume_keyword(Keyword::As) {
+            return Ok(Some(self.identifier()?));
+        }
+        if matches!(self.peek().kind, TokenKind::Identifier(_)) {
+            return Ok(Some(self.identifier()?));
+        }
+        Ok(None)
+    }
+
+    fn column_ref(&mut self) -> Result<ColumnRef, ParseError> {
+        let first = self.identifier()?;
+        if self.consume(&TokenKind::Dot) {
+            let name = self.identifier()?;
+            Ok(ColumnRef {
+                table: Some(first),
+                name,
+            })
+        } else {
+            Ok(ColumnRef {
+                table: None,
+                name: first,
+            })
+        }
+    }
+
+    fn table_reference(&mut self) -> Result<TableReference, ParseError> {
+        let name = self.identifier()?;
+        let alias = self.alias()?;
+        Ok(TableReference { name, alias })
+    }
+
+    fn join(&mut self) -> Result<Join, ParseError> {
+        let kind = if self.consume_keyword(Keyword::Left) {
+            JoinKind::Left
+        } else if self.consume_keyword(Keyword::Right) {
+            JoinKind::Right
+        } else if self.consume_keyword(Keyword::Full) {
+            JoinKind::Full
+        } else {
+            self.consume_keyword(Keyword::Inner);
+            JoinKind::Inner
+        };
+        if kind != JoinKind::Inner {
+            self.consume_keyword(Keyword::Outer);
+        }
+        self.expect_keyword(Keyword::Join)?;
+        let table = self.table_reference()?;
+        let constraint = if self.consume_keyword(Keyword::On) {
+            JoinConstraint::On(self.expression()?)
+        } else if self.consume_keyword(Keyword::Using) {
+            self.expect(&TokenKind::LeftParen, "'('")?;
+            let columns = self.identifier_list()?;
+            self.expect(&TokenKind::RightParen, "')'")?;
+            JoinConstraint::Using(columns)
+        } else {
+            return Err(self.unexpected("ON or USING"));
+        };
+        Ok(Join {
+            kind,
+            table,
+            constraint,
+        })
+    }
+
+    fn identifier_list(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut names = vec![self.identifier()?];
+        while self.consume(&TokenKind::Comma) {
+            names.push(self.identifier()?);
+        }
+        Ok(names)
+    }
+
+    fn order_by_item(&mut self) -> Result<OrderByItem, ParseError> {
+        let column = self.column_ref()?;
+        let ascending = if self.consume_keyword(Keyword::Desc) {
+            false
+        } else {
+            self.consume_keyword(Keyword::Asc);
+            true
+        };
+        Ok(OrderByItem { column, ascending })
+    }
+
+    fn row_count(&mut self) -> Result<usize, ParseError> {
+        if let TokenKind::Integer(value) = self.peek().kind {
+            if let Ok(count) = usize::try_from(value) {
+                self.advance();
+                return Ok(count);
+            }
+        }
+        Err(self.unexpected("non-negative row count"))
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        let mut left = self.and_expression()?;
+        while self.consume_keyword(Keyword::Or) {
+            let right = self.and_expression()?;
+            left = Expression::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expression(&mut self) -> Result<Expression, ParseError> {
+        let mut left = self.not_expression()?;
+        while self.consume_keyword(Keyword::And) {
+            let right = self.not_expression()?;
+            left = Expression::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn not_expression(&mut self) -> Result<Expression, ParseError> {
+        if self.consume_keyword(Keyword::Not) {
+            let expression = self.not_expression()?;
+            return Ok(Expression::Not(Box::new(expression)));
+        }
+        self.primary_expression()
+    }
+
+    fn primary_expression(&mut self) -> Result<Expression, ParseError> {
+        if self.peek().kind == TokenKind::LeftParen {
+            let start = self.index;
+            self.advance();
+            if let Ok(expression) = self.expression() {
+                if self.consume(&TokenKind::RightParen) && !self.continues_operand() {
+                    return Ok(expression);
+                }
+            }
+            // In `(a + b) > c` the parenthesis opens an operand, not a condition.
+            self.index = start;
+        }
+        let position = self.peek().position;
+        let left = self.operand()?;
+        if self.consume_keyword(Keyword::Is) {
+            let negated = self.consume_keyword(Keyword::Not);
+            self.expect_keyword(Keyword::Null)?;
+            return match left {
+                Operand::Column(column) => Ok(Expression::IsNull { column, negated }),
+                _ => Err(ParseError::new(
+                    "IS NULL can only be applied to a column".to_string(),
+                    position,
+                )),
+            };
+        }
+        let negated = self.consume_keyword(Keyword::Not);
+        if let Some(expression) = self.predicate(&left, negated)? {
+            return Ok(expression);
+        }
+        if negated {
+            return Err(self.unexpected("IN, BETWEEN, LIKE or ILIKE"));
+        }
+        let operator = match self.peek().kind {
+            TokenKind::Equals => Operator::Equals,
+            TokenKind::NotEquals => Operator::NotEquals,
+            TokenKind::LessThan => Operator::LessThan,
+            TokenKind::GreaterThan => Operator::GreaterThan,
+            TokenKind::LessThanOrEquals => Operator::LessThanOrEquals,
+            TokenKind::GreaterThanOrEquals => Operator::GreaterThanOrEquals,
+            _ => return Err(self.unexpected("comparison operator")),
+        };
+        self.advance();
+        let right = self.operand()?;
+        Ok(Expression::Comparison {
+            left,
+            operator,
+            right,
+        })
+    }
+
+    /// Parses the `IN`, `BETWEEN` and `LIKE` forms that follow an operand, or
+    /// returns `None` when the next token starts none of them.
+    fn predicate(
+        &mut self,
+        operand: &Operand,
+        negated: bool,
+    ) -> Result<Option<Expression>, ParseError> {
+        let operand = operand.clone();
+        let expression = match self.peek().kind.clone() {
+            TokenKind::Keyword(Keyword::In) => {
+                self.advance();
+                self.expect(&TokenKind::LeftParen, "(")?;
+                let mut list = vec![self.operand()?];
+                while self.consume(&TokenKind::Comma) {
+                    list.push(self.operand()?);
+                }
+                self.expect(&TokenKind::RightParen, ")")?;
+                Expression::In {
+                    operand,
+                    list,
+                    negated,
+                }
+            }
+            TokenKind::Keyword(Keyword::Between) => {
+                self.advance();
+                let low = self.operand()?;
+                self.expect_keyword(Keyword::And)?;
+                let high = self.operand()?;
+                Expression::Between {
+                    operand,
+                    low,
+                    high,
+                    negated,
+                }
+            }
+            TokenKind::Keyword(keyword @ (Keyword::Like | Keyword::Ilike)) => {
+                self.advance();
+                let TokenKind::String(pattern) = self.peek().kind.clone() else {
+                    return Err(self.unexpected("pattern string"));
+                };
+                self.advance();
+                Expression::Like {
+                    operand,
+                    pattern,
+                    case_insensitive: keyword == Keyword::Ilike,
+                    negated,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(expression))
+    }
+
+    fn continues_operand(&self) -> bool {
+        matches!(
+            self.peek().kind,
+            TokenKind::Plus
+                | TokenKind::Minus
+                | TokenKind::Asterisk
+                | TokenKind::Slash
+                | TokenKind::Percent
+                | TokenKind::Concat
+                | TokenKind::Equals
+                | TokenKind::NotEquals
+                | TokenKind::LessThan
+                | TokenKind::GreaterThan
+                | TokenKind::LessThanOrEquals
+                | TokenKind::GreaterThanOrEquals
+                | TokenKind::Keyword(
+                    Keyword::Is
+                        | Keyword::Not
+                        | Keyword::In
+                        | Keyword::Between
+                        | Keyword::Like
+                        | Keyword::Ilike
+                )
+        )
+    }
+
+    /// Parses a scalar expression. `||` binds loosest, then `+` and `-`, then
+    /// `*`, `/` and `%`, and unary minus binds tightest.
+    fn operand(&mut self) -> Result<Operand, ParseError> {
+        let mut left = self.additive_operand()?;
+        while self.consume(&TokenKind::Concat) {
+            let right = self.additive_operand()?;
+            left = binary(left, BinaryOperator::Concat, right);
+        }
+        Ok(left)
+    }
+
+    fn additive_operand(&mut self) -> Result<Operand, ParseError> {
+        let mut left = self.multiplicative_operand()?;
+        loop {
+            let operator = match self.peek().kind {
+                TokenKind::Plus => BinaryOperator::Add,
+                TokenKind::Minus => BinaryOperator::Subtract,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.multiplicative_operand()?;
+            left = binary(left, operator, right);
+        }
+    }
+
+    fn multiplicative_operand(&mut self) -> Result<Operand, ParseError> {
+        let mut left = self.unary_operand()?;
+        loop {
+            let operator = match self.peek().kind {
+                TokenKind::Asterisk => BinaryOperator::Multiply,
+                TokenKind::Slash => BinaryOperator::Divide,
+                TokenKind::Percent => BinaryOperator::Modulo,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.unary_operand()?;
+            left = binary(left, operator, right);
+        }
+    }
+
+    fn unary_operand(&mut self) -> Result<Operand, ParseError> {
+        let is_negative_number = matches!(
+            self.peek_kind_at(1),
+            TokenKind::Integer(_) | TokenKind::Float(_)
+        );
+        if self.peek().kind == TokenKind::Minus && !is_negative_number {
+            self.advance();
+            return Ok(Operand::Negate(Box::new(self.unary_operand()?)));
+        }
+        self.primary_operand()
+    }
+
+    fn primary_operand(&mut self) -> Result<Operand, ParseError> {
+        if self.consume(&TokenKind::LeftParen) {
+            let operand = self.operand()?;
+            self.expect(&TokenKind::RightParen, "')'")?;
+            Ok(operand)
+        } else if self.peek().kind == TokenKind::Keyword(Keyword::Cast) {
+            self.cast()
+        } else if self.peek().kind == TokenKind::Keyword(Keyword::Case) {
+            self.case()
+        } else if self.is_function_call() {
+            self.function_call()
+        } else if matches!(self.peek().kind, TokenKind::Identifier(_)) {
+            Ok(Operand::Column(self.column_ref()?))
+        } else {
+            Ok(Operand::Literal(self.literal()?))
+        }
+    }
+
+    fn literal(&mut self) -> Result<DbType, ParseError> {
+        let token = self.peek().clone();
+        let negative = self.consume(&TokenKind::Minus);
+        match self.peek().kind.clone() {
+            TokenKind::Integer(value) => {
+                self.advance();
+                let value = if negative { -value } else { value };
+                Ok(i32::try_from(value).map_or(DbType::BigInt(value), DbType::Int))
+            }
+            TokenKind::Float(value) => {
+                self.advance();
+                Ok(DbType::Float(if negative { -value } else { value }))
+            }
+            TokenKind::String(value) if !negative => {
+                self.advance();
+                Ok(DbType::Text(value))
+            }
+            TokenKind::Blob(value) if !negative => {
+                self.advance();
+                Ok(DbType::Blob(value))
+            }
+            TokenKind::Keyword(Keyword::Null) if !negative => {
+                self.advance();
+                Ok(DbType::Null)
+            }
+            TokenKind::Keyword(Keyword::True) if !negative => {
+                self.advance();
+                Ok(DbType::Bool(true))
+            }
+            TokenKind::Keyword(Keyword::False) if !negative => {
+                self.advance();
+                Ok(DbType::Bool(false))
+            }
+            TokenKind::Keyword(
+                keyword @ (Keyword::Date | Keyword::Timestamp | Keyword::Decimal),
+            ) if !negative => {
+                self.advance();
+                let TokenKind::String(text) = self.peek().kind.clone() else {
+                    return Err(self.unexpected("string literal"));
+                };
+                self.advance();
+                let value = match keyword {
+                    Keyword::Date => Date::parse(&text).map(DbType::Date),
+                    Keyword::Timestamp => Timestamp::parse(&text).map(DbType::Timestamp),
+                    _ => Decimal::parse(&text).map(DbType::Decimal),
+                };
+                value.ok_or_else(|| {
+                    ParseError::new(
+                        format!(
+                            "invalid {} literal '{text}'",
+                            format!("{keyword:?}").to_ascii_uppercase()
+                        ),
+                        token.position,
+                    )
+                })
+            }
+            _ => Err(self.unexpected("literal")),
+        }
+    }
+
+    fn insert(&mut self) -> Result<InsertStatement, ParseError> {
+        self.expect_keyword(Keyword::Insert)?;
+        self.expect_keyword(Keyword::Into)?;
+        let table = self.identifier()?;
+        let columns = if self.consume(&TokenKind::LeftParen) {
+            let columns = self.identifier_list()?;
+            self.expect(&TokenKind::RightParen, "')'")?;
+            Some(columns)
+        } else {
+            None
+        };
+        self.expect_keyword(Keyword::Values)?;
+        let mut rows = vec![self.value_row()?];
+        while self.consume(&TokenKind::Comma) {
+            rows.push(self.value_row()?);
+        }
+        Ok(InsertStatement {
+            table,
+            columns,
+            rows,
+        })
+    }
+
+    fn value_row(&mut self) -> Result<Vec<DbType>, ParseError> {
+        self.expect(&TokenKind::LeftParen, "'('")?;
+        let mut values = vec![self.literal()?];
+        while self.consume(&TokenKind::Comma) {
+            values.push(self.literal()?);
+        }
+        self.expect(&TokenKind::RightParen, "')'")?;
+        Ok(values)
+    }
+
+    fn create_table(&mut self) -> Result<CreateTableStatement, ParseError> {
+        self.expect_keyword(Keyword::Create)?;
+        self.expect_keyword(Keyword::Table)?;
+        let name = self.identifier()?;
+        self.expect(&TokenKind::LeftParen, "'('")?;
+        let mut columns = Vec::new();
+        let mut primary_key: Vec<String> = Vec::new();
+        loop {
+            let position = self.peek().position;
+            if self.consume_keyword(Keyword::Primary) {
+                self.expect_keyword(Keyword::Key)?;
+                self.expect(&TokenKind::LeftParen, "'('")?;
+                let key = self.identifier_list()?;
+                self.expect(&TokenKind::RightParen, "')'")?;
+                if !primary_key.is_empty() {
+                    return Err(ParseError::new(
+                        "multiple primary keys defined".to_string(),
+                        position,
+                    ));
+                }
+                primary_key = key;
+            } else {
+                let column = self.column_definition()?;
+                if column.has_constraint(&ColumnConstraint::PrimaryKey) {
+                    if !primary_key.is_empty() {
+                        return Err(ParseError::new(
+                            "multiple primary keys defined".to_string(),
+                            position,
+                        ));
+                    }
+                    primary_key.push(column.get_name().to_string());
+                }
+                columns.push(column);
+            }
+            if !self.consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(&TokenKind::RightParen, "')'")?;
+        Ok(CreateTableStatement {
+            name,
+            columns,
+            primary_key,
+        })
+    }
+
+    fn create_index(&mut self) -> Result<CreateIndexStatement, ParseError> {
+        self.expect_keyword(Keyword::Create)?;
+        self.expect_keyword(Keyword::Index)?;
+        let name = self.identifier()?;
+        self.expect_keyword(Keyword::On)?;
+        let table = self.identifier()?;
+        self.expect(&TokenKind::LeftParen, "'('")?;
+        let columns = self.identifier_list()?;
+        self.expect(&TokenKind::RightParen, "')'")?;
+        Ok(CreateIndexStatement {
+            name,
+            table,
+            columns,
+        })
+    }
+
+    fn column_definition(&mut self) -> Result<ColumnDef, ParseError> {
+        let name = self.identifier()?;
+        let column_type = self.column_type()?;
+        let mut column = ColumnDef::new(&name, column_type);
+        loop {
+            if self.consume_keyword(Keyword::Not) {
+                self.expect_keyword(Keyword::Null)?;
+                column = column.not_null();
+            } else if self.consume_keyword(Keyword::Null) {
+            } else if self.consume_keyword(Keyword::Default) {
+                column = column.with_default(self.literal()?);
+            } else if self.consume_keyword(Keyword::Primary) {
+                self.expect_keyword(Keyword::Key)?;
+                column = column.with_constraint(ColumnConstraint::PrimaryKey);
+            } else if self.consume_keyword(Keyword::Unique) {
+                column = column.with_constraint(ColumnConstraint::Unique);
+            } else {
+                return Ok(column);
+            }
+        }
+    }
+
+    fn column_type(&mut self) -> Result<ColumnType, ParseError> {
+        let keyword = match &self.peek().kind {
+            TokenKind::Keyword(keyword) => keyword.clone(),
+            _ => return Err(self.unexpected("column type")),
+        };
+        let type_position = self.peek().position;
+        self.advance();
+        let mut parameters = Vec::new();
+        if self.consume(&TokenKind::LeftParen) {
+            loop {
+                match self.peek().kind {
+                    TokenKind::Integer(value) => parameters.push((value, self.peek().position)),
+                    _ => return Err(self.unexpected("type parameter")),
+                }
+                self.advance();
+                if !self.consume(&TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.expect(&TokenKind::RightParen, "')'")?;
+        }
+        let column_type =
+            match (keyword, parameters.as_slice()) {
+                (Keyword::Decimal | Keyword::Numeric, []) => {
+                    decimal_column_type(i64::from(MAX_DECIMAL_PRECISION), 0, type_position)?
+                }
+                (Keyword::Decimal | Keyword::Numeric, [(precision, position)]) => {
+                    decimal_column_type(*precision, 0, *position)?
+                }
+                (Keyword::Decimal | Keyword::Numeric, [(precision, _), (scale, position)]) => {
+                    decimal_column_type(*precision, *scale, *position)?
+                }
+                (Keyword::Text | Keyword::Varchar, []) => ColumnType::Text { max_length: None },
+                (Keyword::Text | Keyword::Varchar, [(length, position)]) => ColumnType::Text {
+                    max_length: Some(usize::try_from(*length).map_err(|_| {
+                        ParseError::new(format!("invalid length {length}"), *position)
+                    })?),
+                },
+                (keyword, [] | [_]) => match keyword {
+                    Keyword::Int | Keyword::Integer => ColumnType::Int,
+                    Keyword::Bigint => ColumnType::BigInt,
+                    Keyword::Float | Keyword::Real | Keyword::Double => ColumnType::Float,
+                    Keyword::Boolean | Keyword::Bool => ColumnType::Bool,
+                    Keyword::Date => ColumnType::Date,
+                    Keyword::Timestamp => ColumnType::Timestamp,
+                    Keyword::Blob => ColumnType::Blob,
+                    _ => {
+                        return Err(ParseError::new(
+                            format!("expected column type, found Keyword({keyword:?})"),
+                            type_position,
+                        ))
+                    }
+                },
+                (keyword, [.., (_, position)]) => {
+                    return Err(ParseError::new(
+                        format!(
+                            "too many parameters for type {}",
+                            format!("{keyword:?}").to_ascii_uppercase()
+                        ),
+                        *position,
+                    ))
+                }
+            };
+        Ok(column_type)
+    }
+}
+
+fn binary(left: Operand, operator: BinaryOperator, right: Operand) -> Operand {
+    Operand::Binary {
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+    }
+}
+
+fn decimal_column_type(
+    precision: i64,
+    scale: i64,
+    position: Position,
+) -> Result<ColumnType, ParseError> {
+    let precision = match u32::try_from(precision) {
+        Ok(precision) if (1..=MAX_DECIMAL_PRECISION).contains(&precision) => precision,
+        _ => {
+            return Err(ParseError::new(
+                format!("invalid DECIMAL precision {precision}"),
+                position,
+            ))
+        }
+    };
+    match u32::try_from(scale) {
+        Ok(scale) if scale <= MAX_SCALE && scale <= precision => {
+            Ok(ColumnType::Decimal { precision, scale })
+        }
+        _ => Err(ParseError::new(
+            format!("invalid DECIMAL scale {scale} for precision {precision}"),
+            position,
+        )),
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub(crate) message: String,
+    pub(crate) position: Position,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: String, position: Position) -> Self {
+        ParseError { message, position }
+    }
+}
+
+impl Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Parse Error at line {}, column {}: {}",
+            self.position.line, self.position.column, self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_select(sql: &str) -> SelectStatement {
+        match parse(sql).unwrap() {
+            Statement::Select(select) => *select,
+            other => panic!("expected SELECT, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select_with_join_and_where() {
+        let select = parse_select(
+            "SELECT u.name, age AS years FROM users u JOIN ages a ON u.id = a.id WHERE age > 18 AND name <> 'Bob'",
+        );
+        assert_eq!(select.items.len(), 2);
+        assert_eq!(select.from.reference_name(), "u");
+        assert_eq!(select.joins.len(), 1);
+        assert!(matches!(select.selection, Some(Expression::And(_, _))));
+        match &select.items[1] {
+            SelectItem::Expression {
+                expression: Operand::Column(column),
+                alias,
+            } => {
+                assert_eq!(column.name, "age");
+                assert_eq!(alias.as_deref(), Some("years"));
+            }
+            other => panic!("unexpected item {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_group_by_and_order_by() {
+        let select =
+            parse_select("SELECT dept, COUNT(*) FROM staff GROUP BY dept ORDER BY dept DESC;");
+        assert_eq!(select.group_by.len(), 1);
+        assert_eq!(select.order_by.len(), 1);
+        assert!(!select.order_by[0].ascending);
+        assert!(matches!(
+            select.items[1],
+            SelectItem::Expression {
+                expression: Operand::Function {
+                    arguments: FunctionArguments::Star,
+                    ..
+                },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_limit_and_offset() {
+        let select = parse_select("SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20");
+        assert_eq!(select.limit, Some(10));
+        assert_eq!(select.offset, 20);
+        assert!(parse("SELECT id FROM users LIMIT -1").is_err());
+    }
+
+    #[test]
+    fn test_parse_null_handling() {
+        let select = parse_select("SELECT id FROM users WHERE name IS NOT NULL OR age = NULL");
+        match select.selection {
+            Some(Expression::Or(left, right)) => {
+                assert!(matches!(*left, Expression::IsNull { negated: true, .. }));
+                assert!(matches!(
+                    *right,
+                    Expression::Comparison {
+                        right: Operand::Literal(DbType::Null),
+                        ..
+                    }
+                ));
+            }
+            other => panic!("unexpected selection {other:?}"),
+        }
+        match parse("CREATE TABLE t (id INT NOT NULL, name TEXT NULL)").unwrap() {
+            Statement::CreateTable(create) => {
+                assert!(!create.columns[0].is_nullable());
+                assert!(create.columns[1].is_nullable());
+            }
+            other => panic!("unexpected statement {other:?}"),
+        }
+        assert!(parse("SELECT id FROM users WHERE 1 IS NULL").is_err());
+    }
+
+    #[test]
+    fn test_parse_having() {
+        let select = parse_select(
+            "SELECT dept FROM staff GROUP BY dept HAVING COUNT(*) > 5 AND MAX(salary) < 100",
+        );
+        match select.having {
+            Some(Expression::And(left, _)) => assert!(matches!(
+                *left,
+                Expression::Comparison {
+                    left: Operand::Function { .. },
+                    ..
+                }
+            )),
+            other => panic!("unexpected HAVING {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_insert() {
+        match parse("INSERT INTO users (id, name) VALUES (1, 'Alice'), (-2, 'Bob')").unwrap() {
+            Statement::Insert(insert) => {
+                assert_eq!(insert.table, "users");
+                assert_eq!(insert.rows.len(), 2);
+                assert_eq!(insert.rows[1][0], DbType::Int(-2));
+            }
+            other => panic!("expected INSERT, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_create_table() {
+        match parse("CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20))").unwrap() {
+            Statement::CreateTable(create) => {
+                assert_eq!(create.columns.len(), 2);
+                assert_eq!(create.primary_key, vec!["id".to_string()]);
+            }
+            other => panic!("expected CREATE TABLE, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_create_index() {
+        match parse("CREATE INDEX by_name ON users (name, id)").unwrap() {
+            Statement::CreateIndex(create) => {
+                assert_eq!(create.name, "by_name");
+                assert_eq!(create.table, "users");
+                assert_eq!(create.columns, vec!["name".to_string(), "id".to_string()]);
+            }
+            other => panic!("expected CREATE INDEX, got {other:?}"),
+        }
+        assert!(parse("CREATE INDEX by_name users (name)").is_err());
+    }
+
+    #[test]
+    fn test_parse_typed_literals() {
+        match parse(
+            "INSERT INTO t VALUES (DATE '2024-01-31', TIMESTAMP '2024-01-31 12:00:00', \
+             DECIMAL '-1.50', TRUE, X'00ff', 3000000000)",
+        )
+        .unwrap()
+        {
+            Statement::Insert(insert) => assert_eq!(
+                insert.rows[0],
+                vec![
+                    DbType::Date(Date::parse("2024-01-31").unwrap()),
+                    DbType::Timestamp(Timestamp::parse("2024-01-31 12:00:00").unwrap()),
+                    DbType::Decimal(Decimal::parse("-1.50").unwrap()),
+                    DbType::Bool(true),
+                    DbType::Blob(vec![0x00, 0xff]),
+                    DbType::BigInt(3_000_000_000),
+                ]
+            ),
+            other => panic!("expected INSERT, got {other:?}"),
+        }
+        let error = parse("INSERT INTO t VALUES (DATE '2024-02-30')").unwrap_err();
+        assert_eq!(error.message, "invalid DATE literal '2024-02-30'");
+    }
+
+    #[test]
+    fn test_parse_decimal_column_scale() {
+        match parse("CREATE TABLE t (price DECIMAL(10, 2), ratio NUMERIC(5))").unwrap() {
+            Statement::CreateTable(create) => {
+                assert_eq!(
+                    create.columns[0].get_type(),
+                    &ColumnType::Decimal {
+                        precision: 10,
+                        scale: 2
+                    }
+                );
+                assert_eq!(
+                    create.columns[1].get_type(),
+                    &ColumnType::Decimal {
+                        precision: 5,
+                        scale: 0
+                    }
+                );
+            }
+            other => panic!("expected CREATE TABLE, got {other:?}"),
+        }
+        assert!(parse("CREATE TABLE t (price DECIMAL(4, 5))").is_err());
+        assert!(parse("CREATE TABLE t (price DECIMAL(39, 2))").is_err());
+    }
+
+    #[test]
+    fn test_parse_column_constraints_and_defaults() {
+        match parse(
+            "CREATE TABLE t (id INT NOT NULL PRIMARY KEY, email VARCHAR(40) UNIQUE, \
+             active BOOL DEFAULT TRUE NOT NULL)",
+        )
+        .unwrap()
+        {
+            Statement::CreateTable(create) => {
+                assert_eq!(create.primary_key, vec!["id".to_string()]);
+                assert_eq!(
+                    create.columns[1].get_type(),
+                    &ColumnType::Text {
+                        max_length: Some(40)
+                    }
+                );
+                assert_eq!(
+                    create.columns[1].get_constraints(),
+                    &vec![ColumnConstraint::Unique]
+                );
+                assert_eq!(create.columns[2].get_default(), Some(&DbType::Bool(true)));
+                assert!(!create.columns[2].is_nullable());
+            }
+            other => panic!("expected CREATE TABLE, got {other:?}"),
+        }
+        assert!(parse("CREATE TABLE t (a INT PRIMARY KEY, b INT PRIMARY KEY)").is_err());
+    }
+
+    #[test]
+    fn test_parse_cast() {
+        let select = parse_select(
+            "SELECT CAST(price AS DECIMAL(8, 2)) AS p FROM t WHERE CAST(code AS INT) > CAST('1' AS INT)",
+        );
+        assert!(matches!(
+            &select.items[0],
+            SelectItem::Expression {
+                expression: Operand::Cast {
+                    target: ColumnType::Decimal {
+                        precision: 8,
+                        scale: 2
+                    },
+                    ..
+                },
+                alias: Some(_),
+            }
+        ));
+        match select.selection.unwrap() {
+            Expression::Comparison { left, right, .. } => {
+                assert!(matches!(
+                    left,
+                    Operand::Cast {
+                        target: ColumnType::Int,
+                        ..
+                    }
+                ));
+                assert!(matches!(right, Operand::Cast { .. }));
+            }
+            other => panic!("unexpected selection {other:?}"),
+        }
+        assert!(parse("SELECT id FROM t WHERE CAST(id INT) = 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_arithmetic_precedence() {
+        let select = parse_select(
+            "SELECT price * qty AS total, 'n' || -a + b % 2 FROM t WHERE (a + b) * 2 > c AND (a = 1 OR b = 2)",
+        );
+        match &select.items[0] {
+            SelectItem::Expression {
+                expression:
+                    Operand::Binary {
+                        operator: BinaryOperator::Multiply,
+                        ..
+                    },
+                alias: Some(alias),
+            } => assert_eq!(alias, "total"),
+            other => panic!("unexpected item {other:?}"),
+        }
+        let SelectItem::Expression {
+            expression:
+                Operand::Binary {
+                    operator: BinaryOperator::Concat,
+                    right,
+                    ..
+                },
+            ..
+        } = &select.items[1]
+        else {
+            panic!("unexpected item {:?}", select.items[1]);
+        };
+        match right.as_ref() {
+            Operand::Binary {
+                left,
+                operator: BinaryOperator::Add,
+                right,
+            } => {
+                assert!(matches!(left.as_ref(), Operand::Negate(_)));
+                assert!(matches!(
+                    right.as_ref(),
+                    Operand::Binary {
+                        operator: BinaryOperator::Modulo,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("unexpected operand {other:?}"),
+        }
+        let Some(Expression::And(left, right)) = select.selection else {
+            panic!("unexpected selection {:?}", select.selection);
+        };
+        assert!(matches!(
+            *left,
+            Expression::Comparison {
+                left: Operand::Binary {
+                    operator: BinaryOperator::Multiply,
+                    ..
+                },
+                ..
+            }
+        ));
+        assert!(matches!(*right, Expression::Or(_, _)));
+        assert!(parse("SELECT a + FROM t").is_err());
+    }
+
+    #[test]
+    fn test_parse_case_and_function_calls() {
+        let select = parse_select(
+            "SELECT CASE WHEN a > 1 THEN UPPER(name) ELSE 'small' END, \
+             CASE kind WHEN 1 THEN 'one' WHEN 2 THEN 'two' END AS label FROM t",
+        );
+        match &select.items[0] {
+            SelectItem::Expression {
+                expression:
+                    Operand::Case {
+                        branches,
+                        otherwise: Some(_),
+                    },
+                ..
+            } => {
+                assert_eq!(branches.len(), 1);
+                assert!(matches!(
+                    &branches[0].1,
+                    Operand::Function { name, arguments: FunctionArguments::List(arguments) }
+                        if name == "UPPER" && arguments.len() == 1
+                ));
+            }
+            other => panic!("unexpected item {other:?}"),
+        }
+        match &select.items[1] {
+            SelectItem::Expression {
+                expression:
+                    Operand::Case {
+                        branches,
+                        otherwise: None,
+                    },
+                ..
+            } => assert!(matches!(
+                &branches[1].0,
+                Expression::Comparison {
+                    left: Operand::Column(_),
+                    operator: Operator::Equals,
+                    right: Operand::Literal(DbType::Int(2)),
+                }
+            )),
+            other => panic!("unexpected item {other:?}"),
+        }
+        assert!(parse("SELECT CASE ELSE 1 END FROM t").is_err());
+        assert!(parse("SELECT CASE WHEN a = 1 THEN 2 FROM t").is_err());
+    }
+
+    #[test]
+    fn test_parse_in_between_like_and_not() {
+        let select = parse_select(
+            "SELECT id FROM t WHERE NOT a IN (1, 2) AND b NOT BETWEEN 1 AND 5 \
+             AND name ILIKE 'al%' AND c >= 3",
+        );
+        let Some(Expression::And(left, comparison)) = &select.selection else {
+            panic!("unexpected selection {:?}", select.selection);
+        };
+        assert!(matches!(
+            comparison.as_ref(),
+            Expression::Comparison {
+                operator: Operator::GreaterThanOrEquals,
+                ..
+            }
+        ));
+        let Expression::And(left, like) = left.as_ref() else {
+            panic!("unexpected expression {left:?}");
+        };
+        assert!(matches!(
+            like.as_ref(),
+            Expression::Like { pattern, case_insensitive: true, negated: false, .. }
+                if pattern == "al%"
+        ));
+        let Expression::And(not_in, between) = left.as_ref() else {
+            panic!("unexpected expression {left:?}");
+        };
+        assert!(matches!(
+            not_in.as_ref(),
+            Expression::Not(inner)
+                if matches!(inner.as_ref(), Expression::In { list, negated: false, .. } if list.len() == 2)
+        ));
+        assert!(matches!(
+            between.as_ref(),
+            Expression::Between { negated: true, .. }
+        ));
+        assert!(parse("SELECT id FROM t WHERE a NOT = 1").is_err());
+        assert!(parse("SELECT id FROM t WHERE a LIKE b").is_err());
+        assert!(parse("SELECT id FROM t WHERE a IN ()").is_err());
+    }
+
+    #[test]
+    fn test_syntax_error_reports_position() {
+        let error = parse("SELECT id\nFROM WHERE").unwrap_err();
+        assert_eq!(error.position, Position { line: 2, column: 6 });
+        assert_eq!(error.message, "expected identifier, found Keyword(Where)");
+    }
+
+    #[test]
+    fn test_trailing_tokens_rejected() {
+        assert!(parse("SELECT id FROM users users2 users3").is_err());
+    }
+}
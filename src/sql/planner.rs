@@ -0,0 +1,730 @@
+use crate::coercion::{cast, check_comparable, coerce_for_comparison};
+use crate::condition::{Condition, Operator};
+use crate::error::DbError;
+use crate::expression::Expr;
+use crate::function_registry::FunctionRegistry;
+use crate::like_pattern::LikePattern;
+use crate::query_result::QueryResult;
+use crate::rational_algebra::aggregation::{aggregate, AggregateExpr, AggregateFunction};
+use crate::rational_algebra::distinct::DistinctExecutor;
+use crate::rational_algebra::executor::{collect, AliasExecutor, Executor, TableScan};
+use crate::rational_algebra::helper_functions::resolve_column_index;
+use crate::rational_algebra::join::JoinExecutor;
+use crate::rational_algebra::limit::LimitExecutor;
+use crate::rational_algebra::projection::{ComputeExecutor, ProjectionExecutor};
+use crate::rational_algebra::selection::{table_selection, SelectionExecutor};
+use crate::rational_algebra::sort::{SortExecutor, SortKey, TopNExecutor};
+use crate::schema_struct::ColumnType;
+use crate::sql::ast::{
+    ColumnRef, Expression, FunctionArguments, JoinConstraint, Operand, SelectItem, SelectStatement,
+};
+use crate::table_struct::Table;
+use std::collections::HashMap;
+
+/// Plans and runs a SELECT. `sort_memory_budget` is the number of bytes of
+/// rows an ORDER BY keeps in memory before it spills sorted runs to disk.
+pub(crate) fn execute_select(
+    tables: &HashMap<String, Table>,
+    functions: &FunctionRegistry,
+    select: &SelectStatement,
+    sort_memory_budget: usize,
+) -> Result<QueryResult, DbError> {
+    let mut scope = Vec::new();
+    let mut types = HashMap::new();
+    let mut plan = plan_from(tables, functions, select, &mut scope, &mut types)?;
+
+    if let Some(expression) = &select.selection {
+        let context = Context {
+            column_names: plan.get_column_names(),
+            scope: &scope,
+            functions,
+            types: &types,
+            clause: Clause::Where,
+        };
+        let condition = to_condition(expression, &context)?;
+        let selection = if select.joins.is_empty() {
+            table_selection(find_table(tables, &select.from.name)?, &condition)
+        } else {
+            SelectionExecutor::new(plan, &condition)
+        };
+        plan = Box::new(selection?);
+    }
+
+    let is_grouped = !select.group_by.is_empty()
+        || select.having.is_some()
+        || select.items.iter().any(|item| {
+            matches!(item, SelectItem::Expression { expression, .. } if contains_aggregate(expression, functions))
+        });
+    if is_grouped {
+        return execute_grouped(plan, functions, select, &scope, &types, sort_memory_budget);
+    }
+
+    let mut columns = Vec::new();
+    let mut output_names = Vec::new();
+    let mut computed = Vec::new();
+    for item in &select.items {
+        match item {
+            SelectItem::Wildcard => {
+                columns.extend(plan.get_column_names().iter().cloned());
+                output_names.extend(plan.get_column_names().iter().cloned());
+            }
+            SelectItem::Expression {
+                expression: Operand::Column(column),
+                alias,
+            } => {
+                let name = resolve_column(column, plan.get_column_names(), &scope)?;
+                output_names.push(alias.clone().unwrap_or_else(|| column.name.clone()));
+                columns.push(name);
+            }
+            SelectItem::Expression { expression, alias } => {
+                let context = Context {
+                    column_names: plan.get_column_names(),
+                    scope: &scope,
+                    functions,
+                    types: &types,
+                    clause: Clause::Where,
+                };
+                let (expr, _) = to_typed_expr(expression, &context)?;
+                output_names.push(
+                    alias
+                        .clone()
+                        .unwrap_or_else(|| output_name(expression, &expr)),
+                );
+                columns.push(add_computed(&mut computed, expr));
+            }
+        }
+    }
+    if !computed.is_empty() {
+        plan = Box::new(ComputeExecutor::new(plan, &computed)?);
+    }
+    let order_by = to_sort_keys(
+        select,
+        plan.get_column_names(),
+        &columns,
+        &output_names,
+        &scope,
+    )?;
+    finish(
+        plan,
+        &columns,
+        output_names,
+        select,
+        &order_by,
+        sort_memory_budget,
+    )
+}
+
+/// Plans the FROM clause and its joins, recording the tables in scope and
+/// the type of every column they bring in.
+fn plan_from<'a>(
+    tables: &'a HashMap<String, Table>,
+    functions: &FunctionRegistry,
+    select: &SelectStatement,
+    scope: &mut Vec<String>,
+    types: &mut HashMap<String, ColumnType>,
+) -> Result<Box<dyn Executor + 'a>, DbError> {
+    let table = find_table(tables, &select.from.name)?;
+    scope.push(select.from.reference_name().to_string());
+    let mut plan: Box<dyn Executor + 'a> = Box::new(TableScan::new(table));
+    if select.joins.is_empty() {
+        add_column_types(types, table, None);
+    } else {
+        plan = Box::new(AliasExecutor::new(plan, select.from.reference_name()));
+        add_column_types(types, table, Some(select.from.reference_name()));
+    }
+    for join in &select.joins {
+        let right_table = find_table(tables, &join.table.name)?;
+        scope.push(join.table.reference_name().to_string());
+        add_column_types(types, right_table, Some(join.table.reference_name()));
+        let right: Box<dyn Executor + 'a> = Box::new(AliasExecutor::new(
+            Box::new(TableScan::new(right_table)),
+            join.table.reference_name(),
+        ));
+        let executor = match &join.constraint {
+            JoinConstraint::Using(columns) if columns.len() == 1 => {
+                JoinExecutor::new(plan, right, &columns[0], join.kind)
+            }
+            JoinConstraint::Using(_) => {
+                return Err(DbError::Query(
+                    "USING joins on more than one column are not supported, use ON instead"
+                        .to_string(),
+                ))
+            }
+            JoinConstraint::On(expression) => {
+                let mut combined_names = plan.get_column_names().clone();
+                combined_names.extend_from_slice(right.get_column_names());
+                let context = Context {
+                    column_names: &combined_names,
+                    scope,
+                    functions,
+                    types,
+                    clause: Clause::Where,
+                };
+                let condition = to_condition(expression, &context)?;
+                JoinExecutor::on(plan, right, &condition, join.kind)
+            }
+        };
+        plan = Box::new(executor?);
+    }
+    Ok(plan)
+}
+
+/// Plans a query with GROUP BY, HAVING or aggregates. Select items that
+/// combine aggregates and grouped columns, such as `SUM(price) / COUNT(*)`,
+/// are computed on the grouped rows.
+fn execute_grouped(
+    mut plan: Box<dyn Executor + '_>,
+    functions: &FunctionRegistry,
+    select: &SelectStatement,
+    scope: &[String],
+    types: &HashMap<String, ColumnType>,
+    sort_memory_budget: usize,
+) -> Result<QueryResult, DbError> {
+    let column_names = plan.get_column_names().clone();
+    let group_by = select
+        .group_by
+        .iter()
+        .map(|column| resolve_column(column, &column_names, scope))
+        .collect::<Result<Vec<String>, DbError>>()?;
+    let select_list = Context {
+        column_names: &column_names,
+        scope,
+        functions,
+        types,
+        clause: Clause::Grouped(&group_by),
+    };
+    let mut aggregates: Vec<AggregateExpr> = Vec::new();
+    let mut columns = Vec::new();
+    let mut output_names = Vec::new();
+    let mut computed = Vec::new();
+    for item in &select.items {
+        let SelectItem::Expression { expression, alias } = item else {
+            return Err(DbError::Query(
+                "SELECT * cannot be combined with GROUP BY or aggregate functions".to_string(),
+            ));
+        };
+        let (expr, _) = to_typed_expr(expression, &select_list)?;
+        output_names.push(
+            alias
+                .clone()
+                .unwrap_or_else(|| output_name(expression, &expr)),
+        );
+        match expr {
+            Expr::Column(name) => columns.push(name),
+            Expr::Aggregate(aggregate) => {
+                let aggregate = match alias {
+                    Some(alias) => aggregate.with_alias(alias),
+                    None => aggregate,
+                };
+                columns.push(aggregate.get_output_name());
+                aggregates.push(aggregate);
+            }
+            expr => {
+                for aggregate in expr.get_aggregates() {
+                    if !aggregates.contains(aggregate) {
+                        aggregates.push(aggregate.clone());
+                    }
+                }
+                columns.push(add_computed(&mut computed, expr));
+            }
+        }
+    }
+    let having = match &select.having {
+        Some(expression) => Some(to_condition(
+            expression,
+            &Context {
+                clause: Clause::Having(&group_by),
+                ..select_list
+            },
+        )?),
+        None => None,
+    };
+    let grouped = aggregate(plan.as_mut(), &group_by, &aggregates, having.as_ref())?;
+    let mut grouped_plan: Box<dyn Executor + '_> = Box::new(TableScan::new(&grouped));
+    if !computed.is_empty() {
+        grouped_plan = Box::new(ComputeExecutor::new(grouped_plan, &computed)?);
+    }
+    let order_by = to_sort_keys(
+        select,
+        grouped_plan.get_column_names(),
+        &columns,
+        &output_names,
+        scope,
+    )?;
+    finish(
+        grouped_plan,
+        &columns,
+        output_names,
+        select,
+        &order_by,
+        sort_memory_budget,
+    )
+}
+
+fn to_aggregate(
+    function: AggregateFunction,
+    arguments: &FunctionArguments,
+    context: &Context,
+) -> Result<AggregateExpr, DbError> {
+    match arguments {
+        FunctionArguments::Star if function == AggregateFunction::Count => {
+            Ok(AggregateExpr::new(function, None))
+        }
+        FunctionArguments::Star => Err(DbError::Query(format!(
+            "{}(*) is not supported",
+            function.name()
+        ))),
+        FunctionArguments::List(arguments) => match arguments.as_slice() {
+            [Operand::Column(column)] => {
+                let name = resolve_column(column, context.column_names, context.scope)?;
+                Ok(AggregateExpr::new(function, Some(&name)))
+            }
+            _ => Err(DbError::Query(format!(
+                "{} takes a single column argument",
+                function.name()
+            ))),
+        },
+    }
+}
+
+fn contains_aggregate(operand: &Operand, functions: &FunctionRegistry) -> bool {
+    let contains = |operand: &Operand| contains_aggregate(operand, functions);
+    match operand {
+        Operand::Column(_) | Operand::Literal(_) => false,
+        Operand::Function { name, arguments } => {
+            functions.aggregate(name).is_some()
+                || matches!(arguments, FunctionArguments::List(arguments)
+                    if arguments.iter().any(contains))
+        }
+        Operand::Cast { operand, .. } | Operand::Negate(operand) => contains(operand),
+        Operand::Binary { left, right, .. } => contains(left) || contains(right),
+        Operand::Case {
+            branches,
+            otherwise,
+        } => {
+            branches.iter().any(|(_, result)| contains(result))
+                || otherwise.as_deref().is_some_and(contains)
+        }
+    }
+}
+
+/// The name shown for an unaliased select item: a `CAST` of a column keeps
+/// the column's name, an aggregate is spelled as written and any other
+/// expression is printed.
+fn output_name(operand: &Operand, expr: &Expr) -> String {
+    match (operand, expr) {
+        (Operand::Cast { operand, .. }, _) => match operand.as_ref() {
+            Operand::Column(column) => column.name.clone(),
+            _ => expr.to_string(),
+        },
+        (
+            Operand::Function {
+                name,
+                arguments: FunctionArguments::Star,
+            },
+            Expr::Aggregate(_),
+        ) => format!("{name}(*)"),
+        (Operand::Function { name, arguments }, Expr::Aggregate(_)) => match arguments {
+            FunctionArguments::List(arguments) => match arguments.as_slice() {
+                [Operand::Column(column)] => format!("{name}({})", column.name),
+                _ => expr.to_string(),
+            },
+            FunctionArguments::Star => unreachable!("matched above"),
+        },
+        _ => expr.to_string(),
+    }
+}
+
+/// Adds a computed column for the expression unless an identical one is
+/// already there, returning the column's name.
+fn add_computed(computed: &mut Vec<(Expr, String)>, expr: Expr) -> String {
+    if let Some((_, name)) = computed.iter().find(|(existing, _)| *existing == expr) {
+        return name.clone();
+    }
+    let name = expr.to_string();
+    computed.push((expr, name.clone()));
+    name
+}
+
+/// Resolves ORDER BY items against the select list aliases first and then the
+/// columns of the plan, so both `ORDER BY total` and `ORDER BY u.name` work.
+fn to_sort_keys(
+    select: &SelectStatement,
+    column_names: &[String],
+    columns: &[String],
+    output_names: &[String],
+    scope: &[String],
+) -> Result<Vec<SortKey>, DbError> {
+    select
+        .order_by
+        .iter()
+        .map(|item| {
+            let alias = output_names
+                .iter()
+                .position(|name| item.column.table.is_none() && *name == item.column.name);
+            let column = match alias {
+                Some(position) => columns[position].clone(),
+                None => resolve_column(&item.column, column_names, scope)?,
+            };
+            Ok(SortKey {
+                column,
+                ascending: item.ascending,
+            })
+        })
+        .collect()
+}
+
+/// Applies ORDER BY, the select list, DISTINCT and LIMIT/OFFSET on top of the
+/// plan. Without DISTINCT, `ORDER BY ... LIMIT k` only keeps the best
+/// `k + offset` rows instead of sorting the whole input.
+fn finish(
+    mut plan: Box<dyn Executor + '_>,
+    columns: &[String],
+    output_names: Vec<String>,
+    select: &SelectStatement,
+    order_by: &[SortKey],
+    sort_memory_budget: usize,
+) -> Result<QueryResult, DbError> {
+    if !order_by.is_empty() {
+        plan = match select.limit {
+            Some(limit) if !select.distinct => Box::new(TopNExecutor::new(
+                plan,
+                order_by,
+                limit.saturating_add(select.offset),
+            )?),
+            _ => {
+                Box::new(SortExecutor::new(plan, order_by)?.with_memory_budget(sort_memory_budget))
+            }
+        };
+    }
+    plan = Box::new(ProjectionExecutor::new(plan, columns)?);
+    if select.distinct {
+        let all_columns = plan.get_column_names().clone();
+        plan = Box::new(DistinctExecutor::new(plan, &all_columns));
+    }
+    if select.limit.is_some() || select.offset > 0 {
+        plan = Box::new(LimitExecutor::new(plan, select.limit, select.offset));
+    }
+    let mut result = collect(plan.as_mut())?;
+    result.rename_columns(output_names)?;
+    Ok(result)
+}
+
+fn find_table<'a>(tables: &'a HashMap<String, Table>, name: &str) -> Result<&'a Table, DbError> {
+    tables
+        .get(name)
+        .ok_or_else(|| DbError::NotFound(format!("table '{name}' does not exist")))
+}
+
+fn check_qualifier(column: &ColumnRef, scope: &[String]) -> Result<(), DbError> {
+    match &column.table {
+        Some(table) if !scope.contains(table) => Err(DbError::NotFound(format!(
+            "unknown table '{table}' in column reference '{table}.{}'",
+            column.name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn resolve_column(
+    column: &ColumnRef,
+    column_names: &[String],
+    scope: &[String],
+) -> Result<String, DbError> {
+    check_qualifier(column, scope)?;
+    let qualified = column
+        .table
+        .as_ref()
+        .and_then(|table| resolve_column_index(column_names, &format!("{table}.{}", column.name)));
+    match qualified.or_else(|| resolve_column_index(column_names, &column.name)) {
+        Some(index) => Ok(column_names[index].clone()),
+        None => Err(DbError::NotFound(format!(
+            "column '{}' does not exist or is ambiguous",
+            column.name
+        ))),
+    }
+}
+
+/// Where an expression appears, which decides the columns it may read
+/// outside aggregates and whether it may call aggregates at all.
+#[derive(Clone, Copy)]
+enum Clause<'b> {
+    Where,
+    /// The select list of a grouped query.
+    Grouped(&'b [String]),
+    Having(&'b [String]),
+}
+
+/// What an expression is planned against: the columns of the plan, the
+/// tables in scope, the callable functions and the known column types.
+#[derive(Clone, Copy)]
+struct Context<'b> {
+    column_names: &'b [String],
+    scope: &'b [String],
+    functions: &'b FunctionRegistry,
+    types: &'b HashMap<String, ColumnType>,
+    clause: Clause<'b>,
+}
+
+fn to_condition(expression: &Expression, context: &Context) -> Result<Condition, DbError> {
+    match expression {
+        Expression::And(left, right) => Ok(Condition::And(
+            Box::new(to_condition(left, context)?),
+            Box::new(to_condition(right, context)?),
+        )),
+        Expression::Or(left, right) => Ok(Condition::Or(
+            Box::new(to_condition(left, context)?),
+            Box::new(to_condition(right, context)?),
+        )),
+        Expression::IsNull { column, negated } => {
+            let field = clause_column(column, context)?;
+            Ok(if *negated {
+                Condition::IsNotNull { field }
+            } else {
+                Condition::IsNull { field }
+            })
+        }
+        Expression::Comparison {
+            left,
+            operator,
+            right,
+        } => comparison(
+            to_typed_expr(left, context)?,
+            *operator,
+            to_typed_expr(right, context)?,
+        ),
+        Expression::Not(expression) => {
+            Ok(Condition::Not(Box::new(to_condition(expression, context)?)))
+        }
+        Expression::In {
+            operand,
+            list,
+            negated,
+        } => {
+            let (operand, operand_type) = to_typed_expr(operand, context)?;
+            let list = list
+                .iter()
+                .map(|item| comparable(operand_type.as_ref(), to_typed_expr(item, context)?))
+                .collect::<Result<_, _>>()?;
+            Ok(negate_if(Condition::In { operand, list }, *negated))
+        }
+        Expression::Between {
+            operand,
+            low,
+            high,
+            negated,
+        } => {
+            let (operand, operand_type) = to_typed_expr(operand, context)?;
+            let low = comparable(operand_type.as_ref(), to_typed_expr(low, context)?)?;
+            let high = comparable(operand_type.as_ref(), to_typed_expr(high, context)?)?;
+            Ok(negate_if(
+                Condition::Between { operand, low, high },
+                *negated,
+            ))
+        }
+        Expression::Like {
+            operand,
+            pattern,
+            case_insensitive,
+            negated,
+        } => {
+            let (operand, operand_type) = to_typed_expr(operand, context)?;
+            match operand_type {
+                Some(ColumnType::Text { .. }) | None => {}
+                Some(column_type) => {
+                    return Err(DbError::Type(format!(
+                        "LIKE cannot be applied to {column_type}"
+                    )))
+                }
+            }
+            let pattern = LikePattern::new(pattern, *case_insensitive);
+            Ok(negate_if(Condition::Like { operand, pattern }, *negated))
+        }
+    }
+}
+
+fn negate_if(condition: Condition, negated: bool) -> Condition {
+    if negated {
+        Condition::Not(Box::new(condition))
+    } else {
+        condition
+    }
+}
+
+/// Plans an expression compared with an operand of type `operand_type`: a
+/// literal is coerced to that type and anything else must be comparable.
+fn comparable(
+    operand_type: Option<&ColumnType>,
+    (expr, expr_type): (Expr, Option<ColumnType>),
+) -> Result<Expr, DbError> {
+    let Some(operand_type) = operand_type else {
+        return Ok(expr);
+    };
+    let checked = match (expr, expr_type) {
+        (Expr::Literal(value), _) => coerce_for_comparison(&value, operand_type).map(Expr::Literal),
+        (expr, Some(expr_type)) => check_comparable(operand_type, &expr_type).map(|()| expr),
+        (expr, None) => Ok(expr),
+    };
+    Ok(checked?)
+}
+
+/// Plans `left operator right` with any literal moved to the right. A column
+/// or aggregate compared with a literal and two compared columns become the
+/// dedicated conditions that joins and aggregation recognise; anything else
+/// compares the two expressions row by row.
+fn comparison(
+    left: (Expr, Option<ColumnType>),
+    operator: Operator,
+    right: (Expr, Option<ColumnType>),
+) -> Result<Condition, DbError> {
+    let ((left, left_type), operator, (right, right_type)) = match (&left.0, &right.0) {
+        (Expr::Literal(_), Expr::Literal(_)) => (left, operator, right),
+        (Expr::Literal(_), _) => (right, flip(operator), left),
+        _ => (left, operator, right),
+    };
+    if let Expr::Literal(value) = right {
+        let value = match &left_type {
+            Some(column_type) => coerce_for_comparison(&value, column_type)?,
+            None => value,
+        };
+        return Ok(match left {
+            Expr::Column(field) => Condition::Simple {
+                field,
+                operator,
+                value,
+            },
+            Expr::Aggregate(aggregate) => Condition::Aggregate {
+                aggregate,
+                operator,
+                value,
+            },
+            left => Condition::Expression {
+                left,
+                operator,
+                right: Expr::Literal(value),
+            },
+        });
+    }
+    if let (Some(left_type), Some(right_type)) = (&left_type, &right_type) {
+        check_comparable(left_type, right_type)?;
+    }
+    Ok(match (left, right) {
+        (Expr::Column(left_field), Expr::Column(right_field)) => Condition::Comparison {
+            left_field,
+            operator,
+            right_field,
+        },
+        (left, right) => Condition::Expression {
+            left,
+            operator,
+            right,
+        },
+    })
+}
+
+/// Plans an operand and checks the types of the operators it applies.
+fn to_typed_expr(
+    operand: &Operand,
+    context: &Context,
+) -> Result<(Expr, Option<ColumnType>), DbError> {
+    let expr = to_expr(operand, context)?;
+    let expr_type = expr.infer_type(context.types)?;
+    Ok((expr, expr_type))
+}
+
+fn to_expr(operand: &Operand, context: &Context) -> Result<Expr, DbError> {
+    let expr = match operand {
+        Operand::Column(column) => Expr::Column(clause_column(column, context)?),
+        Operand::Literal(value) => Expr::Literal(value.clone()),
+        Operand::Binary {
+            left,
+            operator,
+            right,
+        } => Expr::binary(to_expr(left, context)?, *operator, to_expr(right, context)?),
+        Operand::Negate(operand) => Expr::Negate(Box::new(to_expr(operand, context)?)),
+        // A literal is cast while planning, so a bad one is reported before
+        // any row is read.
+        Operand::Cast { operand, target } => match to_expr(operand, context)? {
+            Expr::Literal(value) => Expr::Literal(cast(&value, target)?),
+            expr => Expr::Cast {
+                expr: Box::new(expr),
+                target: target.clone(),
+            },
+        },
+        Operand::Function { name, arguments } => {
+            if let Some(function) = context.functions.aggregate(name) {
+                if matches!(context.clause, Clause::Where) {
+                    return Err(DbError::Query(
+                        "aggregate functions are only allowed in the select list and HAVING"
+                            .to_string(),
+                    ));
+                }
+                return Ok(Expr::Aggregate(to_aggregate(function, arguments, context)?));
+            }
+            let function = context.functions.scalar(name)?;
+            let FunctionArguments::List(arguments) = arguments else {
+                return Err(DbError::Query(format!("{name}(*) is not supported")));
+            };
+            Expr::Function {
+                function,
+                arguments: arguments
+                    .iter()
+                    .map(|argument| to_expr(argument, context))
+                    .collect::<Result<Vec<Expr>, DbError>>()?,
+            }
+            .unify_types(context.types)?
+        }
+        Operand::Case {
+            branches,
+            otherwise,
+        } => Expr::Case {
+            branches: branches
+                .iter()
+                .map(|(condition, result)| {
+                    Ok((to_condition(condition, context)?, to_expr(result, context)?))
+                })
+                .collect::<Result<Vec<(Condition, Expr)>, DbError>>()?,
+            otherwise: match otherwise {
+                Some(otherwise) => Some(Box::new(to_expr(otherwise, context)?)),
+                None => None,
+            },
+        }
+        .unify_types(context.types)?,
+    };
+    Ok(expr)
+}
+
+fn add_column_types(types: &mut HashMap<String, ColumnType>, table: &Table, prefix: Option<&str>) {
+    for column in table.get_schema().get_columns() {
+        let name = match prefix {
+            Some(prefix) => format!("{prefix}.{}", column.get_name()),
+            None => column.get_name().to_string(),
+        };
+        types.insert(name, column.get_type().clone());
+    }
+}
+
+fn clause_column(column: &ColumnRef, context: &Context) -> Result<String, DbError> {
+    let name = resolve_column(column, context.column_names, context.scope)?;
+    match context.clause {
+        Clause::Grouped(group_by) if !group_by.contains(&name) => Err(DbError::Query(format!(
+            "column '{}' must appear in GROUP BY or be used in an aggregate function",
+            column.name
+        ))),
+        Clause::Having(group_by) if !group_by.contains(&name) => Err(DbError::Query(format!(
+            "column '{}' in HAVING must appear in GROUP BY or be used in an aggregate function",
+            column.name
+        ))),
+        _ => Ok(name),
+    }
+}
+
+fn flip(operator: Operator) -> Operator {
+    match operator {
+        Operator::LessThan => Operator::GreaterThan,
+        Operator::GreaterThan => Operator::LessThan,
+        Operator::LessThanOrEquals => Operator::GreaterThanOrEquals,
+        Operator::GreaterThanOrEquals => Operator::LessThanOrEquals,
+        other => other,
+    }
+}
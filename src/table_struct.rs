@@ -1,96 +1,443 @@
+use crate::coercion::{coerce_for_column, normalize_key};
+use crate::condition::Condition;
 use crate::db_type::DbType;
+use crate::error::DbError;
+use crate::expression::{make_expr_evaluator, Expr, ExprEvaluator};
 use crate::metadata::MetaData;
+use crate::rational_algebra::selection::make_condition_evaluator;
+use crate::schema_struct::{ColumnConstraint, ColumnDef, ColumnType, Schema};
+use crate::secondary_index::SecondaryIndex;
 use crate::table_row::TableRow;
 use crate::ManipulateTable;
+use std::collections::{HashMap, HashSet};
 
 pub(crate) struct Table {
-    name_of_table: String,
+    name: String,
     meta_data: MetaData,
     data: Vec<TableRow>,
-    column_names: Vec<String>,
-    column_types: Vec<DbType>,
+    schema: Schema,
+    indexes: Vec<SecondaryIndex>,
+    unique_indexes: Vec<UniqueIndex>,
+}
+
+/// The position of each non-NULL value of a UNIQUE column, so that a new
+/// value is checked without scanning the table.
+struct UniqueIndex {
+    column: usize,
+    positions: HashMap<DbType, usize>,
+}
+
+impl UniqueIndex {
+    fn new(column: usize) -> Self {
+        UniqueIndex {
+            column,
+            positions: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, values: &[DbType], position: usize) {
+        let value = &values[self.column];
+        if !value.is_null() {
+            self.positions.insert(value.clone(), position);
+        }
+    }
+
+    fn remove(&mut self, values: &[DbType]) {
+        self.positions.remove(&values[self.column]);
+    }
 }
 impl ManipulateTable for Table {
     fn get_column_names(&self) -> &Vec<String> {
-        &self.column_names
+        self.schema.get_column_names()
     }
 
     fn get_data(&self) -> &Vec<TableRow> {
         &self.data
     }
+
+    fn get_column_types(&self) -> Vec<Option<ColumnType>> {
+        self.schema
+            .get_columns()
+            .iter()
+            .map(|column| Some(column.get_type().clone()))
+            .collect()
+    }
 }
 
 impl Table {
     pub(crate) fn new(
         name_of_table: String,
-        meta_data: MetaData,
-        column_names: Vec<String>,
-        column_types: Vec<DbType>,
+        mut meta_data: MetaData,
+        columns: Vec<ColumnDef>,
     ) -> Self {
-        assert_eq!(column_names.len(), column_types.len());
         let capacity = meta_data.get_table_capacity();
+        let schema = Schema::new(columns);
+        meta_data.set_pk(&schema.indexes_with(&ColumnConstraint::PrimaryKey));
+        let unique_indexes = schema
+            .indexes_with(&ColumnConstraint::Unique)
+            .into_iter()
+            .map(UniqueIndex::new)
+            .collect();
         Table {
-            name_of_table,
+            name: name_of_table,
             meta_data,
             data: Vec::with_capacity(capacity),
-            column_names,
-            column_types,
+            schema,
+            indexes: Vec::new(),
+            unique_indexes,
         }
     }
     pub(crate) fn set_primary_key(&mut self, column: &[String]) {
         let indices: Vec<usize> = column
             .iter()
-            .filter_map(|name| self.column_names.iter().position(|c| c == name))
+            .filter_map(|name| self.schema.position(name))
             .collect();
+        for name in column {
+            self.schema
+                .add_constraint(name, ColumnConstraint::PrimaryKey);
+        }
         self.meta_data.set_pk(&indices);
     }
-    fn insert(&mut self, data: Vec<DbType>) {
-        assert_eq!(
-            data.len(),
-            self.column_types.len(),
-            "Data length does not match column length."
-        );
+    #[allow(dead_code)]
+    pub(crate) fn set_not_null(&mut self, column: &[String]) {
+        for name in column {
+            self.schema.set_not_null(name);
+        }
+    }
+    #[allow(dead_code)]
+    pub(crate) fn is_nullable(&self, column: &str) -> bool {
+        self.schema
+            .get_column(column)
+            .is_some_and(ColumnDef::is_nullable)
+    }
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub(crate) fn get_schema(&self) -> &Schema {
+        &self.schema
+    }
+    pub(crate) fn get_indexes(&self) -> &Vec<SecondaryIndex> {
+        &self.indexes
+    }
+    /// Builds an ordered index over `columns` from the current rows; later
+    /// inserts, updates and deletes keep it up to date.
+    pub(crate) fn create_index(&mut self, name: &str, columns: &[String]) -> Result<(), DbError> {
+        if self.indexes.iter().any(|index| index.get_name() == name) {
+            return Err(DbError::Schema(format!("index '{name}' already exists")));
+        }
+        if columns.is_empty() {
+            return Err(DbError::Schema(format!("index '{name}' has no columns")));
+        }
+        let positions = columns
+            .iter()
+            .map(|column| {
+                self.schema.position(column).ok_or_else(|| {
+                    DbError::NotFound(format!(
+                        "column '{column}' does not exist in table '{}'",
+                        self.name
+                    ))
+                })
+            })
+            .collect::<Result<Vec<usize>, DbError>>()?;
+        let mut index = SecondaryIndex::new(name, positions);
+        for (position, row) in self.data.iter().enumerate() {
+            index.add(row.get_values(), position);
+        }
+        self.indexes.push(index);
+        Ok(())
+    }
+    /// The names of the primary-key columns, in key order.
+    pub(crate) fn get_primary_key(&self) -> Vec<&String> {
+        self.meta_data
+            .get_pk()
+            .iter()
+            .map(|&index| &self.schema.get_column_names()[index])
+            .collect()
+    }
+    /// Appends a row after checking its arity, its types and the NOT NULL,
+    /// UNIQUE and PRIMARY KEY constraints. A rejected row leaves the table
+    /// unchanged.
+    #[allow(dead_code)]
+    pub(crate) fn insert(&mut self, data: Vec<DbType>) -> Result<(), DbError> {
+        self.check_row(&data, None)?;
+        self.append(TableRow::new(data));
+        Ok(())
+    }
+
+    /// Appends several rows as one statement. Every row is checked against
+    /// the table and against the other rows before any is stored, so a
+    /// rejected batch leaves the table unchanged. Returns how many rows were
+    /// inserted.
+    pub(crate) fn insert_all(&mut self, rows: Vec<Vec<DbType>>) -> Result<usize, DbError> {
+        for row in &rows {
+            self.check_row(row, None)?;
+        }
+        let rows: Vec<TableRow> = rows.into_iter().map(TableRow::new).collect();
+        self.check_unique(&rows)?;
+        let count = rows.len();
+        for row in rows {
+            self.append(row);
+        }
+        Ok(count)
+    }
+
+    /// Stores a row that has passed `check_row` and indexes it.
+    fn append(&mut self, row: TableRow) {
+        if !self.meta_data.get_pk().is_empty() {
+            let key = self.primary_key_of(row.get_values());
+            self.meta_data.add_key(key, self.data.len());
+        }
+        for index in &mut self.indexes {
+            index.add(row.get_values(), self.data.len());
+        }
+        for index in &mut self.unique_indexes {
+            index.add(row.get_values(), self.data.len());
+        }
+        self.data.push(row);
+    }
+
+    /// The row whose primary key equals `key`, found through the index.
+    /// Values only need to compare equal to the stored ones, so `1.0`
+    /// finds the row with INT key `1`.
+    pub(crate) fn get_by_pk(&self, key: &[DbType]) -> Option<&TableRow> {
+        let pk = self.meta_data.get_pk();
+        if pk.is_empty() || key.len() != pk.len() {
+            return None;
+        }
+        let key = key.iter().map(normalize_key).collect();
+        self.meta_data
+            .find_key(&key)
+            .map(|position| &self.data[position])
+    }
+
+    /// Replaces the row at `position`, checking the new values like
+    /// `insert` does and moving its primary key in the index.
+    #[allow(dead_code)]
+    pub(crate) fn update_row(&mut self, position: usize, data: Vec<DbType>) -> Result<(), DbError> {
+        let Some(row) = self.data.get(position) else {
+            return Err(DbError::NotFound(format!(
+                "row {position} of table '{}'",
+                self.name
+            )));
+        };
+        self.check_row(&data, Some(position))?;
+        if !self.meta_data.get_pk().is_empty() {
+            let old_key = self.primary_key_of(row.get_values());
+            self.meta_data.remove_key(&old_key);
+            let key = self.primary_key_of(&data);
+            self.meta_data.add_key(key, position);
+        }
+        for index in &mut self.indexes {
+            index.remove(row.get_values(), position);
+            index.add(&data, position);
+        }
+        for index in &mut self.unique_indexes {
+            index.remove(row.get_values());
+            index.add(&data, position);
+        }
+        self.data[position] = TableRow::new(data);
+        Ok(())
+    }
 
-        for (data, column_type) in data.iter().zip(self.column_types.iter()) {
-            match (data, column_type) {
-                (DbType::Int(_), DbType::Int(_)) | (DbType::Text(_), DbType::Text(_)) => (),
-                (_, _) => panic!("type mismatch"),
+    /// Sets the assigned columns of every row satisfying `condition` and
+    /// returns how many rows were updated. Each expression sees the row as it
+    /// was before the update, and the constraints are checked on the result
+    /// as a whole, so `id = id + 1` may shift keys past each other. When any
+    /// check fails, the condition's types included, no row is changed.
+    #[allow(dead_code)]
+    pub(crate) fn update(
+        &mut self,
+        condition: &Condition,
+        assignments: &[(String, Expr)],
+    ) -> Result<usize, DbError> {
+        condition.check_types(&self.schema.get_column_types())?;
+        let predicate = make_condition_evaluator(condition, self.get_column_names())?;
+        let assignments = assignments
+            .iter()
+            .map(|(column, expr)| {
+                let index = self.schema.position(column).ok_or_else(|| {
+                    DbError::NotFound(format!(
+                        "column '{column}' does not exist in table '{}'",
+                        self.name
+                    ))
+                })?;
+                Ok((index, make_expr_evaluator(expr, self.get_column_names())?))
+            })
+            .collect::<Result<Vec<(usize, ExprEvaluator)>, DbError>>()?;
+        let mut data = self.data.clone();
+        let mut count = 0;
+        for row in &mut data {
+            if !predicate(row)? {
+                continue;
+            }
+            let mut values = row.get_values().clone();
+            for (index, evaluator) in &assignments {
+                let column_type = self.schema.get_columns()[*index].get_type();
+                values[*index] = coerce_for_column(evaluator(row)?, column_type)?;
+            }
+            self.check_values(&values)?;
+            *row = TableRow::new(values);
+            count += 1;
+        }
+        self.check_unique(&data)?;
+        self.data = data;
+        self.rebuild_indexes();
+        Ok(count)
+    }
+
+    /// Removes every row satisfying `condition` and returns how many there
+    /// were. A condition whose types do not match the columns is an error.
+    #[allow(dead_code)]
+    pub(crate) fn delete(&mut self, condition: &Condition) -> Result<usize, DbError> {
+        condition.check_types(&self.schema.get_column_types())?;
+        let predicate = make_condition_evaluator(condition, self.get_column_names())?;
+        let mut positions = Vec::new();
+        for (position, row) in self.data.iter().enumerate() {
+            if predicate(row)? {
+                positions.push(position);
+            }
+        }
+        Ok(self.delete_rows(&positions))
+    }
+
+    /// Removes the rows at `positions` and returns how many were removed.
+    /// The rows after them move up, so the index is rebuilt.
+    pub(crate) fn delete_rows(&mut self, positions: &[usize]) -> usize {
+        let before = self.data.len();
+        let positions: HashSet<&usize> = positions.iter().collect();
+        let mut position = 0;
+        self.data.retain(|_| {
+            let keep = !positions.contains(&position);
+            position += 1;
+            keep
+        });
+        self.rebuild_indexes();
+        before - self.data.len()
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.meta_data.clear_keys();
+        for index in &mut self.indexes {
+            index.clear();
+        }
+        for index in &mut self.unique_indexes {
+            index.positions.clear();
+        }
+        for position in 0..self.data.len() {
+            if !self.meta_data.get_pk().is_empty() {
+                let key = self.primary_key_of(self.data[position].get_values());
+                self.meta_data.add_key(key, position);
+            }
+            for index in &mut self.indexes {
+                index.add(self.data[position].get_values(), position);
+            }
+            for index in &mut self.unique_indexes {
+                index.add(self.data[position].get_values(), position);
             }
         }
-        //check if primary key exist
-        let mut result: Vec<DbType> = Vec::new();
+    }
+
+    /// The primary key of a row in the canonical form the index stores.
+    fn primary_key_of(&self, values: &[DbType]) -> Vec<DbType> {
+        self.meta_data
+            .get_pk()
+            .iter()
+            .filter_map(|&index| values.get(index))
+            .map(normalize_key)
+            .collect()
+    }
 
-        for &index in self.meta_data.get_pk() {
-            if let Some(value) = data.get(index) {
-                result.push(value.clone());
+    /// Checks that `data` may be stored, either as a new row or in place of
+    /// the row at `replacing`, which is then not a duplicate of itself.
+    fn check_row(&self, data: &[DbType], replacing: Option<usize>) -> Result<(), DbError> {
+        self.check_values(data)?;
+        for index in &self.unique_indexes {
+            let value = &data[index.column];
+            match index.positions.get(value) {
+                Some(&position) if Some(position) != replacing => {
+                    return Err(self.duplicate_value(value, index.column));
+                }
+                _ => {}
             }
         }
+        if self.meta_data.get_pk().is_empty() {
+            return Ok(());
+        }
+        let key = self.primary_key_of(data);
+        match self.meta_data.find_key(&key) {
+            Some(position) if Some(position) != replacing => Err(self.duplicate_key(data)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The checks that concern a row on its own: arity, NOT NULL and types.
+    fn check_values(&self, data: &[DbType]) -> Result<(), DbError> {
+        if data.len() != self.schema.len() {
+            return Err(DbError::Schema(format!(
+                "table '{}' has {} columns, got {} values",
+                self.name,
+                self.schema.len(),
+                data.len()
+            )));
+        }
+        for (column, value) in self.schema.get_columns().iter().zip(data) {
+            column.check(value)?;
+        }
+        Ok(())
+    }
 
-        let filter = self.meta_data.get_filter();
-        if filter.check(&result) {
-            panic!("Inserting a value that already exists (with high probability) into the primary key column. Please enter another value");
-        } else {
-            self.data.push(TableRow::new(data));
-            filter.add(&result);
+    /// Checks the UNIQUE and PRIMARY KEY constraints across `rows`.
+    fn check_unique(&self, rows: &[TableRow]) -> Result<(), DbError> {
+        for index in self.schema.indexes_with(&ColumnConstraint::Unique) {
+            let mut seen = HashSet::new();
+            for row in rows {
+                let value = &row.get_values()[index];
+                if !value.is_null() && !seen.insert(value) {
+                    return Err(self.duplicate_value(value, index));
+                }
+            }
+        }
+        if self.meta_data.get_pk().is_empty() {
+            return Ok(());
+        }
+        let mut seen = HashSet::new();
+        for row in rows {
+            if !seen.insert(self.primary_key_of(row.get_values())) {
+                return Err(self.duplicate_key(row.get_values()));
+            }
         }
+        Ok(())
+    }
+
+    fn duplicate_value(&self, value: &DbType, index: usize) -> DbError {
+        DbError::Constraint(format!(
+            "duplicate value {value:?} in unique column '{}'",
+            self.schema.get_column_names()[index]
+        ))
+    }
+
+    fn duplicate_key(&self, data: &[DbType]) -> DbError {
+        let key: Vec<&DbType> = self
+            .meta_data
+            .get_pk()
+            .iter()
+            .map(|&index| &data[index])
+            .collect();
+        DbError::Constraint(format!("duplicate primary key {key:?}"))
     }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decimal::Decimal;
+    use crate::schema_struct::ColumnType;
     fn set_up_table() -> Table {
         // Assume a default or new instance can be made
-        let mut column_names: Vec<String> = Vec::new();
-        column_names.push("id".parse().unwrap());
-        column_names.push("name".parse().unwrap());
-
-        let mut column_types = vec![DbType::Int(0), DbType::Text(String::new())];
-        Table::new(
-            "Test".to_string(),
-            set_up_meta_data(),
-            column_names,
-            column_types,
-        )
+        let columns = vec![
+            ColumnDef::new("id", ColumnType::Int),
+            ColumnDef::new("name", ColumnType::Text { max_length: None }),
+        ];
+        Table::new("Test".to_string(), set_up_meta_data(), columns)
     }
     fn set_up_meta_data() -> MetaData {
         MetaData::new(20, 0.05)
@@ -98,29 +445,511 @@ mod tests {
     #[test]
     fn test_new_table() {
         let table = set_up_table();
-        assert_eq!(table.column_names.len(), 2);
-        assert_eq!(table.column_types.len(), 2);
+        assert_eq!(table.get_column_names().len(), 2);
+        assert_eq!(table.schema.len(), 2);
     }
     #[test]
     fn test_insert_valid_data() {
         let mut table = set_up_table(); // Assume this is set up correctly
-        table.insert(vec![DbType::Int(1), DbType::Text("Alice".to_string())]);
+        table
+            .insert(vec![DbType::Int(1), DbType::Text("Alice".to_string())])
+            .unwrap();
         assert_eq!(table.data.len(), 1);
     }
+    #[test]
+    fn test_insert_wrong_arity() {
+        let mut table = set_up_table();
+        assert_eq!(
+            table.insert(vec![DbType::Int(1)]),
+            Err(DbError::Schema(
+                "table 'Test' has 2 columns, got 1 values".to_string()
+            ))
+        );
+        assert!(table.data.is_empty());
+    }
 
     #[test]
-    #[should_panic(expected = "type mismatch")]
     fn test_insert_type_mismatch() {
         let mut table = set_up_table();
-        table.insert(vec![DbType::Text("1".to_string()), DbType::Int(2)]); // Assuming a mismatch
+        let result = table.insert(vec![DbType::Text("1".to_string()), DbType::Int(2)]);
+        assert!(matches!(result, Err(DbError::Type(_))));
+        assert!(table.data.is_empty());
     }
     #[test]
-    #[should_panic(expected = "Inserting a value that already exists")]
     fn test_insert_duplicate_primary_key() {
         let mut table = set_up_table();
-        table.set_primary_key(&vec!["id".to_string()]);
-        table.insert(vec![DbType::Int(1), DbType::Text("Alice".to_string())]);
-        // This should panic because it attempts to insert a duplicate primary key.
-        table.insert(vec![DbType::Int(1), DbType::Text("Bob".to_string())]);
+        table.set_primary_key(&["id".to_string()]);
+        table
+            .insert(vec![DbType::Int(1), DbType::Text("Alice".to_string())])
+            .unwrap();
+        // This fails because it attempts to insert a duplicate primary key.
+        let result = table.insert(vec![DbType::Int(1), DbType::Text("Bob".to_string())]);
+        assert!(matches!(result, Err(DbError::Constraint(_))));
+        assert_eq!(table.data.len(), 1);
+    }
+    #[test]
+    fn test_bloom_false_positive_does_not_reject_new_key() {
+        let columns = vec![
+            ColumnDef::new("id", ColumnType::Int).with_constraint(ColumnConstraint::PrimaryKey)
+        ];
+        // One bit of filter, so every key after the first is a false positive.
+        let mut table = Table::new("Ids".to_string(), MetaData::new(1, 0.99), columns);
+        for id in 1..=3 {
+            table.insert(vec![DbType::Int(id)]).unwrap();
+        }
+        assert_eq!(table.data.len(), 3);
+        assert_eq!(
+            table.insert(vec![DbType::Int(2)]),
+            Err(DbError::Constraint(
+                "duplicate primary key [Int(2)]".to_string()
+            ))
+        );
+    }
+    #[test]
+    fn test_insert_null_into_nullable_column() {
+        let mut table = set_up_table();
+        table.insert(vec![DbType::Int(1), DbType::Null]).unwrap();
+        assert_eq!(table.data.len(), 1);
+        assert!(table.is_nullable("name"));
+    }
+    #[test]
+    fn test_insert_null_into_not_null_column() {
+        let mut table = set_up_table();
+        table.set_not_null(&["name".to_string()]);
+        assert_eq!(
+            table.insert(vec![DbType::Int(1), DbType::Null]),
+            Err(DbError::Constraint(
+                "column 'name' cannot be NULL".to_string()
+            ))
+        );
+    }
+    #[test]
+    fn test_primary_key_is_not_null() {
+        let mut table = set_up_table();
+        table.set_primary_key(&["id".to_string()]);
+        assert_eq!(
+            table.insert(vec![DbType::Null, DbType::Text("Alice".to_string())]),
+            Err(DbError::Constraint(
+                "column 'id' cannot be NULL".to_string()
+            ))
+        );
+    }
+    #[test]
+    fn test_insert_rich_types() {
+        use crate::date_time::{Date, Timestamp};
+        use crate::decimal::Decimal;
+        let columns = vec![
+            ColumnDef::new("id", ColumnType::BigInt),
+            ColumnDef::new("active", ColumnType::Bool),
+            ColumnDef::new("born", ColumnType::Date),
+            ColumnDef::new("seen", ColumnType::Timestamp),
+            ColumnDef::new(
+                "price",
+                ColumnType::Decimal {
+                    precision: 10,
+                    scale: 2,
+                },
+            ),
+            ColumnDef::new("photo", ColumnType::Blob),
+        ];
+        let mut table = Table::new("Rich".to_string(), set_up_meta_data(), columns);
+        table
+            .insert(vec![
+                DbType::BigInt(9_000_000_000),
+                DbType::Bool(true),
+                DbType::Date(Date::parse("1990-04-01").unwrap()),
+                DbType::Timestamp(Timestamp::parse("2024-01-01 12:00:00").unwrap()),
+                DbType::Decimal(Decimal::parse("19.99").unwrap()),
+                DbType::Blob(vec![0xde, 0xad]),
+            ])
+            .unwrap();
+        assert_eq!(table.data.len(), 1);
+    }
+    #[test]
+    fn test_insert_int_into_bigint_column_is_a_mismatch() {
+        let mut table = Table::new(
+            "Ids".to_string(),
+            set_up_meta_data(),
+            vec![ColumnDef::new("id", ColumnType::BigInt)],
+        );
+        assert!(matches!(
+            table.insert(vec![DbType::Int(1)]),
+            Err(DbError::Type(_))
+        ));
+    }
+    #[test]
+    fn test_insert_text_longer_than_varchar() {
+        let columns = vec![ColumnDef::new(
+            "name",
+            ColumnType::Text {
+                max_length: Some(5),
+            },
+        )];
+        let mut table = Table::new("Names".to_string(), set_up_meta_data(), columns);
+        table
+            .insert(vec![DbType::Text("Alex".to_string())])
+            .unwrap();
+        assert_eq!(
+            table.insert(vec![DbType::Text("Alexander".to_string())]),
+            Err(DbError::Type(
+                "column 'name' value Text(\"Alexander\") does not fit VARCHAR(5)".to_string()
+            ))
+        );
+    }
+    #[test]
+    fn test_insert_duplicate_unique_value() {
+        let columns = vec![
+            ColumnDef::new("id", ColumnType::Int).with_constraint(ColumnConstraint::PrimaryKey),
+            ColumnDef::new("email", ColumnType::Text { max_length: None })
+                .with_constraint(ColumnConstraint::Unique),
+        ];
+        let mut table = Table::new("Users".to_string(), set_up_meta_data(), columns);
+        assert_eq!(table.meta_data.get_pk(), &vec![0]);
+        table.insert(vec![DbType::Int(1), DbType::Null]).unwrap();
+        table.insert(vec![DbType::Int(2), DbType::Null]).unwrap();
+        table
+            .insert(vec![DbType::Int(3), DbType::Text("a@b.c".to_string())])
+            .unwrap();
+        assert_eq!(
+            table.insert(vec![DbType::Int(4), DbType::Text("a@b.c".to_string())]),
+            Err(DbError::Constraint(
+                "duplicate value Text(\"a@b.c\") in unique column 'email'".to_string()
+            ))
+        );
+    }
+    #[test]
+    fn test_unique_value_is_freed_by_update_and_delete() {
+        let columns = vec![
+            ColumnDef::new("id", ColumnType::Int),
+            ColumnDef::new("email", ColumnType::Text { max_length: None })
+                .with_constraint(ColumnConstraint::Unique),
+        ];
+        let mut table = Table::new("Users".to_string(), set_up_meta_data(), columns);
+        let email = |address: &str| DbType::Text(address.to_string());
+        table.insert(vec![DbType::Int(1), email("a@b.c")]).unwrap();
+        table.insert(vec![DbType::Int(2), email("d@e.f")]).unwrap();
+        table
+            .update_row(0, vec![DbType::Int(1), email("a@b.c")])
+            .unwrap();
+        assert!(table
+            .update_row(1, vec![DbType::Int(2), email("a@b.c")])
+            .is_err());
+        table
+            .update_row(0, vec![DbType::Int(1), email("g@h.i")])
+            .unwrap();
+        table.insert(vec![DbType::Int(3), email("a@b.c")]).unwrap();
+        assert_eq!(table.delete_rows(&[0]), 1);
+        table.insert(vec![DbType::Int(4), email("g@h.i")]).unwrap();
+        assert!(table.insert(vec![DbType::Int(5), email("d@e.f")]).is_err());
+    }
+    fn set_up_keyed_table() -> Table {
+        let mut table = set_up_table();
+        table.set_primary_key(&["id".to_string()]);
+        for (id, name) in [(1, "Alice"), (2, "Bob"), (3, "Carol")] {
+            table
+                .insert(vec![DbType::Int(id), DbType::Text(name.to_string())])
+                .unwrap();
+        }
+        table
+    }
+    fn name_of(row: Option<&TableRow>) -> Option<&DbType> {
+        row.map(|row| &row.get_values()[1])
+    }
+    #[test]
+    fn test_get_by_pk() {
+        let table = set_up_keyed_table();
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Int(2)])),
+            Some(&DbType::Text("Bob".to_string()))
+        );
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Float(3.0)])),
+            Some(&DbType::Text("Carol".to_string()))
+        );
+        assert!(table.get_by_pk(&[DbType::Int(4)]).is_none());
+        assert!(table.get_by_pk(&[]).is_none());
+        assert!(set_up_table().get_by_pk(&[DbType::Int(1)]).is_none());
+    }
+    #[test]
+    fn test_get_by_pk_with_keys_of_different_scale() {
+        let columns = vec![
+            ColumnDef::new(
+                "price",
+                ColumnType::Decimal {
+                    precision: 10,
+                    scale: 2,
+                },
+            )
+            .with_constraint(ColumnConstraint::PrimaryKey),
+            ColumnDef::new("name", ColumnType::Text { max_length: None }),
+        ];
+        let mut table = Table::new("Prices".to_string(), set_up_meta_data(), columns);
+        let decimal = |text: &str| DbType::Decimal(Decimal::parse(text).unwrap());
+        for (price, name) in [(decimal("3.00"), "three"), (decimal("4.50"), "four")] {
+            table
+                .insert(vec![price, DbType::Text(name.to_string())])
+                .unwrap();
+        }
+        let three = Some(&DbType::Text("three".to_string()));
+        assert_eq!(name_of(table.get_by_pk(&[DbType::Int(3)])), three);
+        assert_eq!(name_of(table.get_by_pk(&[DbType::Float(3.0)])), three);
+        assert_eq!(name_of(table.get_by_pk(&[decimal("3.000")])), three);
+        assert_eq!(
+            name_of(table.get_by_pk(&[decimal("4.5")])),
+            Some(&DbType::Text("four".to_string()))
+        );
+    }
+    #[test]
+    fn test_update_row_moves_primary_key() {
+        let mut table = set_up_keyed_table();
+        table
+            .update_row(0, vec![DbType::Int(5), DbType::Text("Alice".to_string())])
+            .unwrap();
+        assert!(table.get_by_pk(&[DbType::Int(1)]).is_none());
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Int(5)])),
+            Some(&DbType::Text("Alice".to_string()))
+        );
+        // Keeping its own key is not a duplicate, taking another row's is.
+        table
+            .update_row(1, vec![DbType::Int(2), DbType::Text("Robert".to_string())])
+            .unwrap();
+        assert_eq!(
+            table.update_row(1, vec![DbType::Int(3), DbType::Text("Bob".to_string())]),
+            Err(DbError::Constraint(
+                "duplicate primary key [Int(3)]".to_string()
+            ))
+        );
+        assert!(matches!(
+            table.update_row(9, vec![DbType::Int(9), DbType::Null]),
+            Err(DbError::NotFound(_))
+        ));
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Int(2)])),
+            Some(&DbType::Text("Robert".to_string()))
+        );
+    }
+    #[test]
+    fn test_delete_rows_reindexes() {
+        let mut table = set_up_keyed_table();
+        assert_eq!(table.delete_rows(&[0, 7]), 1);
+        assert!(table.get_by_pk(&[DbType::Int(1)]).is_none());
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Int(3)])),
+            Some(&DbType::Text("Carol".to_string()))
+        );
+        // A deleted key can be inserted again.
+        table
+            .insert(vec![DbType::Int(1), DbType::Text("Alice".to_string())])
+            .unwrap();
+        assert_eq!(table.data.len(), 3);
+    }
+    #[test]
+    fn test_selection_uses_primary_key_lookup() {
+        use crate::condition::{Condition, Operator};
+        use crate::rational_algebra::executor::collect;
+        use crate::rational_algebra::selection::table_selection;
+        let table = set_up_keyed_table();
+        let id_is = |id| Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Int(id),
+        };
+        let name_is = |name: &str| Condition::Simple {
+            field: "name".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Text(name.to_string()),
+        };
+        let count = |condition: &Condition| {
+            collect(&mut table_selection(&table, condition).unwrap())
+                .unwrap()
+                .get_data()
+                .len()
+        };
+        assert_eq!(count(&id_is(2)), 1);
+        assert_eq!(count(&id_is(4)), 0);
+        let both = Condition::And(Box::new(name_is("Bob")), Box::new(id_is(2)));
+        assert_eq!(count(&both), 1);
+        let mismatch = Condition::And(Box::new(id_is(2)), Box::new(name_is("Alice")));
+        assert_eq!(count(&mismatch), 0);
+        let either = Condition::Or(Box::new(id_is(2)), Box::new(name_is("Alice")));
+        assert_eq!(count(&either), 2);
+    }
+    #[test]
+    fn test_table_selection_checks_condition_types() {
+        use crate::condition::{Condition, Operator};
+        use crate::rational_algebra::selection::table_selection;
+        // The table is empty, so only the check against the schema can fail.
+        let table = set_up_table();
+        let condition = Condition::Simple {
+            field: "name".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Int(1),
+        };
+        assert_eq!(
+            table_selection(&table, &condition).err(),
+            Some(DbError::Type("cannot compare TEXT with INT".to_string()))
+        );
+        let condition = Condition::Comparison {
+            left_field: "id".to_string(),
+            operator: Operator::LessThan,
+            right_field: "name".to_string(),
+        };
+        assert!(matches!(
+            table_selection(&table, &condition),
+            Err(DbError::Type(_))
+        ));
+    }
+    #[test]
+    fn test_secondary_index_follows_mutations() {
+        use crate::secondary_index::KeyRange;
+        let mut table = set_up_keyed_table();
+        assert!(matches!(
+            table.create_index("by_age", &["age".to_string()]),
+            Err(DbError::NotFound(_))
+        ));
+        table
+            .create_index("by_name", &["name".to_string()])
+            .unwrap();
+        assert_eq!(
+            table.create_index("by_name", &["id".to_string()]),
+            Err(DbError::Schema(
+                "index 'by_name' already exists".to_string()
+            ))
+        );
+        let index = |table: &Table, name: &str| {
+            table.get_indexes()[0].scan(&KeyRange::equal_to(DbType::Text(name.to_string())))
+        };
+        assert_eq!(index(&table, "Bob"), vec![1]);
+        table
+            .insert(vec![DbType::Int(4), DbType::Text("Bob".to_string())])
+            .unwrap();
+        assert_eq!(index(&table, "Bob"), vec![1, 3]);
+        table
+            .update_row(1, vec![DbType::Int(2), DbType::Text("Robert".to_string())])
+            .unwrap();
+        assert_eq!(index(&table, "Bob"), vec![3]);
+        table.delete_rows(&[0]);
+        assert_eq!(index(&table, "Bob"), vec![2]);
+        assert_eq!(index(&table, "Robert"), vec![0]);
+    }
+    fn id_above(id: i32) -> Condition {
+        use crate::condition::Operator;
+        Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::GreaterThan,
+            value: DbType::Int(id),
+        }
+    }
+    #[test]
+    fn test_update_with_condition() {
+        use crate::expression::BinaryOperator;
+        let mut table = set_up_keyed_table();
+        let shift = vec![(
+            "id".to_string(),
+            Expr::binary(
+                Expr::column("id"),
+                BinaryOperator::Add,
+                Expr::Literal(DbType::Int(1)),
+            ),
+        )];
+        // Every key moves onto the next one, which is fine once all have moved.
+        assert_eq!(table.update(&id_above(0), &shift), Ok(3));
+        assert!(table.get_by_pk(&[DbType::Int(1)]).is_none());
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Int(4)])),
+            Some(&DbType::Text("Carol".to_string()))
+        );
+        let rename = vec![(
+            "name".to_string(),
+            Expr::Literal(DbType::Text("Someone".to_string())),
+        )];
+        assert_eq!(table.update(&id_above(2), &rename), Ok(2));
+        assert_eq!(table.update(&id_above(9), &rename), Ok(0));
+        assert_eq!(
+            name_of(table.get_by_pk(&[DbType::Int(2)])),
+            Some(&DbType::Text("Alice".to_string()))
+        );
+    }
+    #[test]
+    fn test_failed_update_changes_nothing() {
+        let mut table = set_up_keyed_table();
+        table.set_not_null(&["name".to_string()]);
+        let to_one = vec![("id".to_string(), Expr::Literal(DbType::Int(1)))];
+        assert_eq!(
+            table.update(&id_above(1), &to_one),
+            Err(DbError::Constraint(
+                "duplicate primary key [Int(1)]".to_string()
+            ))
+        );
+        let to_null = vec![("name".to_string(), Expr::Literal(DbType::Null))];
+        assert!(matches!(
+            table.update(&id_above(2), &to_null),
+            Err(DbError::Constraint(_))
+        ));
+        let unknown = vec![("age".to_string(), Expr::Literal(DbType::Int(1)))];
+        assert!(matches!(
+            table.update(&id_above(0), &unknown),
+            Err(DbError::NotFound(_))
+        ));
+        assert_eq!(
+            table
+                .data
+                .iter()
+                .map(|row| row.get_values().clone())
+                .collect::<Vec<_>>(),
+            set_up_keyed_table()
+                .data
+                .iter()
+                .map(|row| row.get_values().clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(table.get_by_pk(&[DbType::Int(3)]).is_some());
+    }
+    #[test]
+    fn test_delete_with_condition() {
+        let mut table = set_up_keyed_table();
+        assert_eq!(table.delete(&id_above(1)), Ok(2));
+        assert_eq!(table.delete(&id_above(1)), Ok(0));
+        assert_eq!(table.data.len(), 1);
+        assert!(table.get_by_pk(&[DbType::Int(2)]).is_none());
+        table
+            .insert(vec![DbType::Int(2), DbType::Text("Bob".to_string())])
+            .unwrap();
+        assert!(table.get_by_pk(&[DbType::Int(2)]).is_some());
+        assert_eq!(
+            table.delete(&Condition::IsNull {
+                field: "age".to_string()
+            }),
+            Err(DbError::column_not_found("age"))
+        );
+    }
+    #[test]
+    fn test_update_and_delete_check_condition_types() {
+        use crate::condition::Operator;
+        let mut table = set_up_keyed_table();
+        let name_is_one = Condition::Simple {
+            field: "name".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Int(1),
+        };
+        let id_is_text = Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Text("a".to_string()),
+        };
+        let rename = vec![(
+            "name".to_string(),
+            Expr::Literal(DbType::Text("Someone".to_string())),
+        )];
+        assert_eq!(
+            table.update(&name_is_one, &rename),
+            Err(DbError::Type("cannot compare TEXT with INT".to_string()))
+        );
+        assert_eq!(
+            table.delete(&id_is_text),
+            Err(DbError::Type("cannot compare INT with TEXT".to_string()))
+        );
+        assert_eq!(table.data.len(), 3);
     }
 }
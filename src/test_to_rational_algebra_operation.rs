@@ -1,13 +1,14 @@
-use crate::condition::{Condition, Operator};
-use crate::db_type::DbType;
-use crate::table_row::TableRow;
-use crate::ManipulateTable;
-
 #[cfg(test)]
 mod test {
-    use super::*;
+    use crate::condition::{Condition, Operator};
+    use crate::db_type::DbType;
+    use crate::error::DbError;
+    use crate::expression::{BinaryOperator, Expr};
+    use crate::like_pattern::LikePattern;
     use crate::rational_algebra::projection::projection;
     use crate::rational_algebra::selection::selection;
+    use crate::table_row::TableRow;
+    use crate::ManipulateTable;
     struct MockTable {
         columns_name: Vec<String>,
         data: Vec<TableRow>,
@@ -51,7 +52,7 @@ mod test {
             operator: Operator::Equals,
             value: DbType::Text("Alice".to_string()),
         };
-        let result = selection(&table, &condition);
+        let result = selection(&table, &condition).unwrap();
         assert_eq!(result.get_data().len(), 1);
         assert_eq!(
             result.get_data()[0].get_values(),
@@ -66,7 +67,7 @@ mod test {
     fn test_simple_projection() {
         let table = set_up_table();
         let columns_to_save = vec!["name".to_string(), "family_name".to_string()];
-        let result = projection(&table, &columns_to_save);
+        let result = projection(&table, &columns_to_save).unwrap();
         assert_eq!(result.get_column_names().len(), 2);
         assert_eq!(
             result.get_data()[0].get_values(),
@@ -84,14 +85,14 @@ mod test {
             operator: Operator::GreaterThan,
             value: DbType::Int(1),
         };
-        let result = selection(&table, &condition);
+        let result = selection(&table, &condition).unwrap();
         assert_eq!(result.get_data().len(), 1);
         assert_eq!(
             result.get_data()[0].get_values(),
             &vec![
                 DbType::Int(2),
-                DbType::Text("Bob".to_string()),
-                DbType::Text("Uncle".to_string())
+                DbType::Text("Uncle".to_string()),
+                DbType::Text("Bob".to_string())
             ]
         );
     }
@@ -111,7 +112,7 @@ mod test {
                 value: DbType::Text("Alice".to_string()),
             }),
         );
-        let result = selection(&table, &condition);
+        let result = selection(&table, &condition).unwrap();
         assert_eq!(result.get_data().len(), 1);
         assert_eq!(
             result.get_data()[0].get_values(),
@@ -127,12 +128,239 @@ mod test {
     fn test_projection_nonexistent_column() {
         let table = set_up_table();
         let columns_to_project = vec!["name".to_string(), "nonexistent_column".to_string()];
-        let result = projection(&table, &columns_to_project);
-        assert_eq!(result.get_column_names().len(), 1);
-        assert!(result.get_column_names().contains(&"name".to_string()));
         assert_eq!(
-            result.get_data()[0].get_values(),
-            &vec![DbType::Text("Alice".to_string()),]
+            projection(&table, &columns_to_project).err(),
+            Some(DbError::NotFound(
+                "column 'nonexistent_column' not found".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_selection_nonexistent_column() {
+        let table = set_up_table();
+        let condition = Condition::Simple {
+            field: "age".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Int(1),
+        };
+        assert_eq!(
+            selection(&table, &condition).err(),
+            Some(DbError::column_not_found("age"))
+        );
+        let negated = Condition::Not(Box::new(Condition::IsNull {
+            field: "age".to_string(),
+        }));
+        assert!(matches!(
+            selection(&table, &negated),
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    fn set_up_table_with_null() -> MockTable {
+        let mut table = set_up_table();
+        table.data.push(TableRow::new(vec![
+            DbType::Int(3),
+            DbType::Text("Carol".to_string()),
+            DbType::Null,
+        ]));
+        table
+    }
+
+    #[test]
+    fn test_selection_comparison_with_null_is_unknown() {
+        let table = set_up_table_with_null();
+        let not_bob = Condition::Simple {
+            field: "family_name".to_string(),
+            operator: Operator::NotEquals,
+            value: DbType::Text("Bob".to_string()),
+        };
+        let result = selection(&table, &not_bob).unwrap();
+        assert_eq!(result.get_data().len(), 1);
+
+        let equals_null = Condition::Simple {
+            field: "family_name".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Null,
+        };
+        assert!(selection(&table, &equals_null)
+            .unwrap()
+            .get_data()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_selection_three_valued_and_or() {
+        let table = set_up_table_with_null();
+        let unknown = Condition::Simple {
+            field: "family_name".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Text("Baum".to_string()),
+        };
+        let is_carol = Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Int(3),
+        };
+        let or = Condition::Or(Box::new(unknown.clone()), Box::new(is_carol.clone()));
+        assert_eq!(selection(&table, &or).unwrap().get_data().len(), 2);
+        let and = Condition::And(Box::new(unknown), Box::new(is_carol));
+        assert!(selection(&table, &and).unwrap().get_data().is_empty());
+    }
+
+    #[test]
+    fn test_selection_is_null_and_is_not_null() {
+        let table = set_up_table_with_null();
+        let is_null = Condition::IsNull {
+            field: "family_name".to_string(),
+        };
+        let result = selection(&table, &is_null).unwrap();
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(result.get_data()[0].get_values()[0], DbType::Int(3));
+
+        let is_not_null = Condition::IsNotNull {
+            field: "family_name".to_string(),
+        };
+        assert_eq!(selection(&table, &is_not_null).unwrap().get_data().len(), 2);
+    }
+    #[test]
+    fn test_selection_compares_numbers_across_types() {
+        let table = set_up_table();
+        let condition = Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::GreaterThan,
+            value: DbType::Float(1.5),
+        };
+        let result = selection(&table, &condition).unwrap();
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(result.get_data()[0].get_values()[0], DbType::Int(2));
+    }
+    #[test]
+    fn test_selection_evaluation_errors_are_returned() {
+        let table = set_up_table();
+        let condition = Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Text("1".to_string()),
+        };
+        assert_eq!(
+            selection(&table, &condition).err(),
+            Some(DbError::Type("cannot compare INT with TEXT".to_string()))
+        );
+        let condition = Condition::Simple {
+            field: "name".to_string(),
+            operator: Operator::Equals,
+            value: DbType::Int(1),
+        };
+        assert_eq!(
+            selection(&table, &condition).err(),
+            Some(DbError::Type("cannot compare TEXT with INT".to_string()))
+        );
+        let condition = Condition::Expression {
+            left: Expr::binary(
+                Expr::Literal(DbType::Int(10)),
+                BinaryOperator::Divide,
+                Expr::binary(
+                    Expr::column("id"),
+                    BinaryOperator::Subtract,
+                    Expr::Literal(DbType::Int(2)),
+                ),
+            ),
+            operator: Operator::GreaterThan,
+            right: Expr::Literal(DbType::Int(0)),
+        };
+        assert!(matches!(
+            selection(&table, &condition),
+            Err(DbError::Type(_))
+        ));
+        let condition = Condition::Like {
+            operand: Expr::column("id"),
+            pattern: LikePattern::new("1%", false),
+        };
+        assert_eq!(
+            selection(&table, &condition).err(),
+            Some(DbError::Type("LIKE cannot be applied to INT".to_string()))
+        );
+    }
+    #[test]
+    fn test_selection_on_expressions() {
+        let table = set_up_table();
+        let condition = Condition::Expression {
+            left: Expr::binary(
+                Expr::column("id"),
+                BinaryOperator::Multiply,
+                Expr::Literal(DbType::Int(10)),
+            ),
+            operator: Operator::GreaterThan,
+            right: Expr::binary(
+                Expr::column("id"),
+                BinaryOperator::Add,
+                Expr::Literal(DbType::Int(10)),
+            ),
+        };
+        let result = selection(&table, &condition).unwrap();
+        assert_eq!(result.get_data().len(), 1);
+        assert_eq!(result.get_data()[0].get_values()[0], DbType::Int(2));
+        let full_name = Condition::Expression {
+            left: Expr::binary(
+                Expr::column("name"),
+                BinaryOperator::Concat,
+                Expr::column("family_name"),
+            ),
+            operator: Operator::Equals,
+            right: Expr::Literal(DbType::Text("AliceBaum".to_string())),
+        };
+        assert_eq!(selection(&table, &full_name).unwrap().get_data().len(), 1);
+    }
+
+    #[test]
+    fn test_selection_with_in_between_like_and_not() {
+        let table = set_up_table();
+        let ids = |condition: &Condition| -> Vec<DbType> {
+            selection(&table, condition)
+                .unwrap()
+                .get_data()
+                .iter()
+                .map(|row| row.get_values()[0].clone())
+                .collect()
+        };
+        let in_list = Condition::In {
+            operand: Expr::column("id"),
+            list: vec![Expr::Literal(DbType::Int(2)), Expr::Literal(DbType::Int(5))],
+        };
+        assert_eq!(ids(&in_list), vec![DbType::Int(2)]);
+        assert_eq!(
+            ids(&Condition::Not(Box::new(in_list))),
+            vec![DbType::Int(1)]
         );
+        let between = Condition::Between {
+            operand: Expr::column("id"),
+            low: Expr::Literal(DbType::Int(1)),
+            high: Expr::Literal(DbType::Int(1)),
+        };
+        assert_eq!(ids(&between), vec![DbType::Int(1)]);
+        let like = Condition::Like {
+            operand: Expr::column("family_name"),
+            pattern: LikePattern::new("b_%", true),
+        };
+        assert_eq!(ids(&like), vec![DbType::Int(1), DbType::Int(2)]);
+        let at_most = Condition::Simple {
+            field: "id".to_string(),
+            operator: Operator::LessThanOrEquals,
+            value: DbType::Int(1),
+        };
+        assert_eq!(ids(&at_most), vec![DbType::Int(1)]);
+    }
+
+    #[test]
+    fn test_in_list_with_null_is_unknown() {
+        let table = set_up_table();
+        let in_list = Condition::In {
+            operand: Expr::column("id"),
+            list: vec![Expr::Literal(DbType::Int(1)), Expr::Literal(DbType::Null)],
+        };
+        assert_eq!(selection(&table, &in_list).unwrap().get_data().len(), 1);
+        let not_in = Condition::Not(Box::new(in_list));
+        assert!(selection(&table, &not_in).unwrap().get_data().is_empty());
     }
 }
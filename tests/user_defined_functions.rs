@@ -0,0 +1,133 @@
+use toy_db_01::{
+    ColumnType, CustomAggregate, Database, DbError, DbType, EvaluationError, ExecutionResult,
+    ParameterKind, QueryResult, ScalarFunction, Signature,
+};
+
+/// The difference between the largest and smallest value of a group.
+struct Spread;
+
+impl CustomAggregate for Spread {
+    type State = Option<(i32, i32)>;
+
+    fn init(&self) -> Self::State {
+        None
+    }
+
+    fn accumulate(&self, state: &mut Self::State, value: &DbType) -> Result<(), DbError> {
+        let DbType::Int(value) = *value else {
+            return Err(DbError::Type(format!("SPREAD is not defined for {value}")));
+        };
+        self.merge(state, Some((value, value)))
+    }
+
+    fn merge(&self, state: &mut Self::State, other: Self::State) -> Result<(), DbError> {
+        if let Some((low, high)) = other {
+            *state = Some(state.map_or((low, high), |(current_low, current_high)| {
+                (current_low.min(low), current_high.max(high))
+            }));
+        }
+        Ok(())
+    }
+
+    fn finalize(&self, state: Self::State) -> Result<DbType, DbError> {
+        Ok(state.map_or(DbType::Null, |(low, high)| DbType::Int(high - low)))
+    }
+
+    fn return_type(&self, _argument: Option<&ColumnType>) -> Option<ColumnType> {
+        Some(ColumnType::Int)
+    }
+}
+
+fn set_up_database() -> Database {
+    let mut database = Database::new();
+    for sql in [
+        "CREATE TABLE users (id INT PRIMARY KEY, name TEXT)",
+        "INSERT INTO users VALUES (1, 'Alice'), (2, 'Bob'), (4, 'Carol')",
+    ] {
+        database.execute(sql).unwrap();
+    }
+    let initials = ScalarFunction::new("initials", |arguments| match &arguments[0] {
+        DbType::Text(text) if text.is_empty() => Err(EvaluationError::new("empty name")),
+        DbType::Text(text) => Ok(DbType::Text(text.chars().take(2).collect())),
+        _ => Ok(DbType::Null),
+    })
+    .with_signature(Signature::fixed(&[ParameterKind::Text]))
+    .returning(ColumnType::Text { max_length: None })
+    .strict();
+    database.register_scalar_function(initials).unwrap();
+    database
+        .register_aggregate_function("spread", Spread)
+        .unwrap();
+    database
+}
+
+fn rows(database: &mut Database, sql: &str) -> QueryResult {
+    match database.execute(sql).unwrap() {
+        ExecutionResult::Rows(result) => result,
+        _ => panic!("expected rows from {sql}"),
+    }
+}
+
+fn values(result: &QueryResult) -> Vec<Vec<DbType>> {
+    result.rows().map(<[DbType]>::to_vec).collect()
+}
+
+#[test]
+fn test_registered_functions_are_callable_from_queries() {
+    let mut database = set_up_database();
+    let result = rows(
+        &mut database,
+        "SELECT INITIALS(name) AS short FROM users WHERE initials(name) <> 'Bo'",
+    );
+    assert_eq!(result.column_names(), ["short"]);
+    assert_eq!(
+        values(&result),
+        vec![
+            vec![DbType::Text("Al".to_string())],
+            vec![DbType::Text("Ca".to_string())],
+        ]
+    );
+    let result = rows(&mut database, "SELECT SPREAD(id) FROM users");
+    assert_eq!(result.column_names(), ["SPREAD(id)"]);
+    assert_eq!(values(&result), vec![vec![DbType::Int(3)]]);
+    let result = rows(
+        &mut database,
+        "SELECT name FROM users GROUP BY name HAVING SPREAD(id) = 0 AND name <> 'Bob'",
+    );
+    assert_eq!(
+        values(&result),
+        vec![
+            vec![DbType::Text("Alice".to_string())],
+            vec![DbType::Text("Carol".to_string())],
+        ]
+    );
+}
+
+#[test]
+fn test_registered_functions_report_errors() {
+    let mut database = set_up_database();
+    let error =
+        |database: &mut Database, sql: &str| database.execute(sql).err().unwrap().to_string();
+    assert_eq!(
+        error(&mut database, "SELECT INITIALS(id) FROM users"),
+        "Type Error: INITIALS argument 1 must be TEXT, found INT"
+    );
+    assert_eq!(
+        error(&mut database, "SELECT SPREAD(name) FROM users"),
+        "Type Error: SPREAD is not defined for Alice"
+    );
+    database
+        .execute("INSERT INTO users VALUES (5, '')")
+        .unwrap();
+    assert_eq!(
+        error(&mut database, "SELECT INITIALS(name) FROM users"),
+        "Type Error: empty name"
+    );
+    assert_eq!(
+        database
+            .register_aggregate_function("initials", Spread)
+            .unwrap_err()
+            .to_string(),
+        "Schema Error: function 'INITIALS' already exists"
+    );
+}